@@ -741,6 +741,7 @@ async function resolveRemoteInfo(
       url,
       download: false,
       cookies_from_browser: cookiesFromBrowser || undefined,
+      wait: true,
     }),
   });
 }
@@ -757,6 +758,7 @@ async function downloadRemoteMedia(
       url,
       download: true,
       cookies_from_browser: cookiesFromBrowser || undefined,
+      wait: true,
     }),
   });
 }
@@ -844,7 +846,7 @@ async function runGeminiVideoAnalysis(opts: {
   const pipeline = await toolserverJson<ToolserverFfmpegPipeline>(`/projects/${opts.projectId}/pipeline/ffmpeg`, {
     method: "POST",
     headers: { "content-type": "application/json" },
-    body: JSON.stringify({ input_video_artifact_id: opts.inputVideoArtifactId }),
+    body: JSON.stringify({ input_video_artifact_id: opts.inputVideoArtifactId, wait: true }),
   });
 
   const clips = pipeline.clips.slice(0, 3);
@@ -4220,7 +4222,7 @@ app.post("/api/projects/:projectId/gemini/analyze", async (req, res) => {
     const pipeline = await toolserverJson<ToolserverFfmpegPipeline>(`/projects/${projectId}/pipeline/ffmpeg`, {
       method: "POST",
       headers: { "content-type": "application/json" },
-      body: JSON.stringify({ input_video_artifact_id: inputVideoArtifactId }),
+      body: JSON.stringify({ input_video_artifact_id: inputVideoArtifactId, wait: true }),
     });
 
     const clips = pipeline.clips.slice(0, 3);
@@ -644,6 +644,7 @@ export default function App() {
   const [zipIncludeClips, setZipIncludeClips] = React.useState(true);
   const [zipIncludeAudio, setZipIncludeAudio] = React.useState(false);
   const [zipIncludeThumbnails, setZipIncludeThumbnails] = React.useState(true);
+  const [zipIncludePreviews, setZipIncludePreviews] = React.useState(true);
   const [zipEstimateBusy, setZipEstimateBusy] = React.useState(false);
   const [zipEstimateError, setZipEstimateError] = React.useState<string | null>(null);
   const [zipEstimate, setZipEstimate] = React.useState<
@@ -1074,6 +1075,7 @@ export default function App() {
         url: u.path,
         download,
         cookies_from_browser: clientConfig.ytdlp_cookies_from_browser,
+        wait: true,
       });
       setRemoteInfoByUrl((prev) => ({ ...prev, [u.path]: resp.info }));
       setRemoteInfoArtifactByUrl((prev) => ({ ...prev, [u.path]: resp.info_artifact }));
@@ -1411,6 +1413,7 @@ export default function App() {
         url,
         download,
         cookies_from_browser: clientConfig.ytdlp_cookies_from_browser,
+        wait: true,
       });
       setRemoteInfoByUrl((prev) => ({ ...prev, [url]: resp.info }));
       setRemoteInfoArtifactByUrl((prev) => ({ ...prev, [url]: resp.info_artifact }));
@@ -1638,6 +1641,7 @@ export default function App() {
           include_clips: zipIncludeClips,
           include_audio: zipIncludeAudio,
           include_thumbnails: zipIncludeThumbnails,
+          include_previews: zipIncludePreviews,
         },
       );
       setZipEstimate(resp);
@@ -1676,6 +1680,8 @@ export default function App() {
           include_clips: zipIncludeClips,
           include_audio: zipIncludeAudio,
           include_thumbnails: zipIncludeThumbnails,
+          include_previews: zipIncludePreviews,
+          wait: true,
         },
       );
       setZipExport(resp);
@@ -2308,6 +2314,19 @@ export default function App() {
                               <div className="export-option-desc">{tr("exportThumbnailsDesc")}</div>
                             </div>
                           </label>
+
+                          <label className={`export-option ${zipIncludePreviews ? "on" : ""}`}>
+                            <input
+                              type="checkbox"
+                              checked={zipIncludePreviews}
+                              onChange={(e) => setZipIncludePreviews(e.target.checked)}
+                              data-testid="include-previews"
+                            />
+                            <div className="export-option-body">
+                              <div className="export-option-title">{tr("exportPreviews")}</div>
+                              <div className="export-option-desc">{tr("exportPreviewsDesc")}</div>
+                            </div>
+                          </label>
                         </div>
                         <div className="text-xs text-dim mb-4">{tr("exportAlwaysIncluded")}</div>
 
@@ -167,6 +167,8 @@ const en = {
   exportAudioDesc: "Requires ffmpeg pipeline (run Analyze once).",
   exportThumbnails: "Thumbnails",
   exportThumbnailsDesc: "Requires ffmpeg pipeline (run Analyze once).",
+  exportPreviews: "Contact sheet + sprite",
+  exportPreviewsDesc: "Frame grid and WebVTT thumbnail sprite. Requires ffmpeg pipeline.",
   exportAlwaysIncluded: "Always included: selected_pool.json",
   generatingReport: "Generating Report…",
   genReport: "Gen Report",
@@ -358,6 +360,8 @@ const zhCN: typeof en = {
   exportAudioDesc: "需要先跑一次分析（会触发 ffmpeg）",
   exportThumbnails: "缩略图",
   exportThumbnailsDesc: "需要先跑一次分析（会触发 ffmpeg）",
+  exportPreviews: "联系表 + 预览雪碧图",
+  exportPreviewsDesc: "帧网格图与 WebVTT 缩略图轨道，需要先跑一次分析（会触发 ffmpeg）",
   exportAlwaysIncluded: "始终包含：selected_pool.json（已选素材快照）",
   generatingReport: "生成报告中…",
   genReport: "生成报告",
@@ -1,23 +1,35 @@
 use anyhow::Context;
 use axum::{
     body::Body,
-    extract::{DefaultBodyLimit, Multipart, Path, State},
-    http::{header, HeaderValue, StatusCode},
-    response::{IntoResponse, Response},
-    routing::{get, post},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, patch, post},
     Json, Router,
 };
+use futures_util::StreamExt;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::{
-    io::ErrorKind,
+    cell::Cell,
+    convert::Infallible,
+    io::{BufRead, ErrorKind, Read, Write},
     net::SocketAddr,
     path::{Path as FsPath, PathBuf},
-    process::Command,
-    time::{SystemTime, UNIX_EPOCH},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc};
 use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 use zip::write::FileOptions;
@@ -61,6 +73,8 @@ async fn main() -> anyhow::Result<()> {
     let db_path = data_dir.join("vidunpack.sqlite3");
     init_db(&db_path)?;
 
+    let (progress, _) = broadcast::channel(256);
+
     let state = AppState {
         data_dir,
         db_path,
@@ -68,12 +82,21 @@ async fn main() -> anyhow::Result<()> {
         ffprobe,
         ytdlp,
         ytdlp_cmd,
+        progress,
+        active_runs: Arc::new(Mutex::new(HashMap::new())),
+        active_uploads: Arc::new(Mutex::new(HashSet::new())),
     };
 
+    tokio::spawn(run_upload_gc(state.db_path.clone(), state.data_dir.clone()));
+
     let app = Router::new()
         .route("/health", get(health))
         .route("/profile", get(get_profile).post(update_profile))
         .route("/profile/reset", post(reset_profile))
+        .route(
+            "/ytdlp/cookies",
+            get(get_ytdlp_cookies).put(upload_ytdlp_cookies).delete(delete_ytdlp_cookies),
+        )
         .route("/projects", post(create_project).get(list_projects))
         .route("/projects/batch_delete", post(batch_delete_projects))
         .route("/projects/{id}", get(get_project).delete(delete_project))
@@ -93,11 +116,45 @@ async fn main() -> anyhow::Result<()> {
             get(list_chat_messages).post(create_chat_message),
         )
         .route("/projects/{id}/pool/items", get(list_pool_items).post(add_pool_item))
+        .route("/projects/{id}/pool/items/bulk", post(bulk_pool_items))
+        .route("/projects/{id}/pool/items/{item_id}", patch(update_pool_item).delete(delete_pool_item))
+        .route("/projects/{id}/pool/items/{item_id}/restore", post(restore_pool_item))
         .route("/projects/{id}/pool/items/{item_id}/selected", post(set_pool_item_selected))
+        .route("/projects/{id}/pool/items/{item_id}/asset", post(materialize_pool_item_asset))
+        .route("/projects/{id}/pool/items/{item_id}/mirrors", get(get_pool_item_mirrors))
+        .route("/projects/{id}/pool/duplicates", get(list_pool_duplicates))
+        .route("/projects/{id}/pool/duplicates/merge", post(merge_pool_items))
+        .route("/projects/{id}/inputs", get(list_inputs))
+        .route("/projects/{id}/inputs/order", post(reorder_inputs))
         .route("/projects/{id}/inputs/url", post(add_input_url))
+        .route("/projects/{id}/inputs/{artifact_id}", patch(update_input))
         .route("/projects/{id}/media/local", post(import_local_video))
+        .route("/projects/{id}/media/local/batch", post(import_local_videos_batch))
+        .route("/projects/{id}/media/local/dir", post(import_local_directory))
+        .route("/projects/{id}/uploads", post(create_upload))
+        .route(
+            "/projects/{id}/uploads/{upload_id}",
+            get(get_upload).patch(append_upload_chunk).delete(abort_upload),
+        )
+        .route("/projects/{id}/uploads/{upload_id}/complete", post(complete_upload))
         .route("/projects/{id}/media/remote", post(import_remote_media))
+        .route("/projects/{id}/media/remote/playlist", post(resolve_remote_playlist_entries))
+        .route("/projects/{id}/media/remote/playlist/download", post(download_remote_playlist_entries))
+        .route("/projects/{id}/remote_sources", get(list_remote_sources))
+        .route("/projects/{id}/remote_sources/{extractor}/{source_id}", get(get_remote_source))
+        .route(
+            "/projects/{id}/remote_sources/{extractor}/{source_id}/refresh",
+            post(refresh_remote_source),
+        )
         .route("/projects/{id}/pipeline/ffmpeg", post(ffmpeg_pipeline))
+        .route("/projects/{id}/media/extract", post(extract_media))
+        .route("/projects/{id}/media/{artifact_id}/probe", get(probe_media))
+        .route("/projects/{id}/media/{artifact_id}/proxy", post(create_proxy))
+        .route("/projects/{id}/pipeline/shots", post(detect_shots))
+        .route("/projects/{id}/runs", get(list_runs))
+        .route("/projects/{id}/events/stream", get(stream_project_events))
+        .route("/projects/{id}/runs/{run_id}", get(get_run))
+        .route("/projects/{id}/runs/{run_id}/cancel", post(cancel_run))
         .route("/projects/{id}/exports/report", post(generate_report))
         .route("/projects/{id}/exports/zip/estimate", post(estimate_export_zip))
         .route("/projects/{id}/exports/zip", post(export_zip))
@@ -150,6 +207,10 @@ struct AppState {
     ffprobe: bool,
     ytdlp: bool,
     ytdlp_cmd: String,
+    progress: broadcast::Sender<ProgressEvent>,
+    active_runs: Arc<Mutex<HashMap<String, Arc<RunCancel>>>>,
+    /// Upload sessions with a chunk being written right now.
+    active_uploads: Arc<Mutex<HashSet<String>>>,
 }
 
 fn detect_ffmpeg() -> bool {
@@ -243,7 +304,7 @@ fn url_domain(url: &str) -> Option<String> {
 
     let without_scheme = trimmed.split("://").nth(1).unwrap_or(trimmed);
     let host_port = without_scheme.split('/').next().unwrap_or(without_scheme);
-    let host_port = host_port.split('@').last().unwrap_or(host_port);
+    let host_port = host_port.rsplit('@').next().unwrap_or(host_port);
     let host = host_port.split(':').next().unwrap_or(host_port);
     let host = host.trim();
     if host.is_empty() {
@@ -353,16 +414,11 @@ fn update_profile_after_export(
     data_dir: &FsPath,
     project_id: &str,
     ts: i64,
-    include_original_video: bool,
-    include_report: bool,
-    include_manifest: bool,
-    include_clips: bool,
-    include_audio: bool,
-    include_thumbnails: bool,
+    opts: &ExportZipOptions,
 ) -> anyhow::Result<()> {
     let kind_counts: Vec<ProfileMemoryCount> = {
         let mut stmt = conn.prepare(
-            "SELECT kind, COUNT(*) FROM pool_items WHERE project_id = ?1 AND selected = 1 GROUP BY kind ORDER BY kind ASC",
+            "SELECT kind, COUNT(*) FROM pool_items WHERE project_id = ?1 AND selected = 1 AND deleted_at_ms IS NULL\n             GROUP BY kind ORDER BY kind ASC",
         )?;
         let rows = stmt.query_map([project_id], |row| Ok(ProfileMemoryCount { key: row.get(0)?, count: row.get(1)? }))?;
         rows.filter_map(Result::ok).collect()
@@ -392,12 +448,25 @@ fn update_profile_after_export(
             parts.push(format!("selected={selected}"));
         }
     }
-    parts.push(format!("include_original_video={include_original_video}"));
-    parts.push(format!("include_report={include_report}"));
-    parts.push(format!("include_manifest={include_manifest}"));
-    parts.push(format!("include_clips={include_clips}"));
-    parts.push(format!("include_audio={include_audio}"));
-    parts.push(format!("include_thumbnails={include_thumbnails}"));
+    parts.push(format!("include_original_video={}", opts.include_original_video));
+    if opts.include_original_video {
+        let input_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM artifacts WHERE project_id = ?1 AND kind = 'input_video'",
+            [project_id],
+            |r| r.get(0),
+        )?;
+        parts.push(format!("inputs={} of {input_count}", opts.inputs.describe()));
+    }
+    parts.push(format!("include_report={}", opts.include_report));
+    parts.push(format!("include_manifest={}", opts.include_manifest));
+    parts.push(format!("include_clips={}", opts.include_clips));
+    parts.push(format!("include_audio={}", opts.include_audio));
+    parts.push(format!("include_thumbnails={}", opts.include_thumbnails));
+    parts.push(format!("include_previews={}", opts.include_previews));
+    if opts.include_pool_assets {
+        let missing = serde_json::to_value(opts.missing_pool_assets)?;
+        parts.push(format!("include_pool_assets=true (missing={})", missing.as_str().unwrap_or("skip")));
+    }
 
     let session_summary = truncate_with_ellipsis(&format!("Exported; {}", parts.join("; ")), 400);
 
@@ -462,6 +531,16 @@ CREATE TABLE IF NOT EXISTS runs (
 );
 CREATE INDEX IF NOT EXISTS idx_runs_project_id ON runs(project_id);
 
+CREATE TABLE IF NOT EXISTS run_artifacts (
+  run_id TEXT NOT NULL,
+  artifact_id TEXT NOT NULL,
+  project_id TEXT NOT NULL,
+  PRIMARY KEY(run_id, artifact_id),
+  FOREIGN KEY(run_id) REFERENCES runs(id),
+  FOREIGN KEY(artifact_id) REFERENCES artifacts(id)
+);
+CREATE INDEX IF NOT EXISTS idx_run_artifacts_project_id ON run_artifacts(project_id);
+
 CREATE TABLE IF NOT EXISTS artifacts (
   id TEXT PRIMARY KEY,
   project_id TEXT NOT NULL,
@@ -503,6 +582,22 @@ CREATE TABLE IF NOT EXISTS pool_items (
 CREATE UNIQUE INDEX IF NOT EXISTS idx_pool_items_dedup ON pool_items(project_id, dedup_key);
 CREATE INDEX IF NOT EXISTS idx_pool_items_project_id ON pool_items(project_id);
 
+CREATE TABLE IF NOT EXISTS pool_item_mirrors (
+  project_id TEXT NOT NULL,
+  item_id TEXT NOT NULL,
+  merged_item_id TEXT NOT NULL,
+  title TEXT,
+  source_url TEXT,
+  license TEXT,
+  dedup_key TEXT NOT NULL,
+  asset_sha256 TEXT,
+  created_at_ms INTEGER NOT NULL,
+  PRIMARY KEY(project_id, merged_item_id),
+  FOREIGN KEY(project_id) REFERENCES projects(id)
+);
+CREATE INDEX IF NOT EXISTS idx_pool_item_mirrors_item ON pool_item_mirrors(project_id, item_id);
+CREATE INDEX IF NOT EXISTS idx_pool_item_mirrors_dedup ON pool_item_mirrors(project_id, dedup_key);
+
 CREATE TABLE IF NOT EXISTS profile (
   id INTEGER PRIMARY KEY CHECK (id = 1),
   summary TEXT NOT NULL,
@@ -555,10 +650,108 @@ CREATE TABLE IF NOT EXISTS chat_messages (
 );
 CREATE INDEX IF NOT EXISTS idx_chat_messages_chat_id ON chat_messages(chat_id);
 CREATE INDEX IF NOT EXISTS idx_chat_messages_project_id ON chat_messages(project_id);
+
+CREATE TABLE IF NOT EXISTS upload_sessions (
+  id TEXT PRIMARY KEY,
+  project_id TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  file_name TEXT NOT NULL,
+  mime TEXT,
+  total_bytes INTEGER NOT NULL,
+  created_at_ms INTEGER NOT NULL,
+  updated_at_ms INTEGER NOT NULL,
+  FOREIGN KEY(project_id) REFERENCES projects(id)
+);
+CREATE INDEX IF NOT EXISTS idx_upload_sessions_project_id ON upload_sessions(project_id);
+
+CREATE TABLE IF NOT EXISTS remote_sources (
+  extractor TEXT NOT NULL,
+  id TEXT NOT NULL,
+  title TEXT,
+  uploader TEXT,
+  channel TEXT,
+  upload_date TEXT,
+  duration_s REAL,
+  view_count INTEGER,
+  like_count INTEGER,
+  tags_json TEXT,
+  chapters_json TEXT,
+  webpage_url TEXT NOT NULL,
+  availability TEXT,
+  first_seen_at_ms INTEGER NOT NULL,
+  updated_at_ms INTEGER NOT NULL,
+  refreshed_at_ms INTEGER,
+  PRIMARY KEY(extractor, id)
+);
+
+CREATE TABLE IF NOT EXISTS remote_source_links (
+  extractor TEXT NOT NULL,
+  source_id TEXT NOT NULL,
+  project_id TEXT NOT NULL,
+  artifact_id TEXT NOT NULL,
+  created_at_ms INTEGER NOT NULL,
+  PRIMARY KEY(extractor, source_id, artifact_id),
+  FOREIGN KEY(project_id) REFERENCES projects(id)
+);
+CREATE INDEX IF NOT EXISTS idx_remote_source_links_project_id ON remote_source_links(project_id);
+
+CREATE TABLE IF NOT EXISTS remote_source_changes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  extractor TEXT NOT NULL,
+  source_id TEXT NOT NULL,
+  ts_ms INTEGER NOT NULL,
+  field TEXT NOT NULL,
+  old_json TEXT,
+  new_json TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_remote_source_changes_source ON remote_source_changes(extractor, source_id);
         "#,
     )
     .context("failed to init sqlite schema")?;
 
+    // `runs` predates the job system; add its columns in place for existing databases.
+    ensure_column(&conn, "runs", "kind", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(&conn, "runs", "started_at_ms", "INTEGER")?;
+    ensure_column(&conn, "runs", "finished_at_ms", "INTEGER")?;
+    ensure_column(&conn, "runs", "error", "TEXT")?;
+    ensure_column(&conn, "runs", "request_json", "TEXT")?;
+    ensure_column(&conn, "runs", "result_json", "TEXT")?;
+    ensure_column(&conn, "artifacts", "content_hash", "TEXT")?;
+    ensure_column(&conn, "artifacts", "content_hash_stat", "TEXT")?;
+    ensure_column(&conn, "artifacts", "source_artifact_id", "TEXT")?;
+    ensure_column(&conn, "artifacts", "label", "TEXT")?;
+    ensure_column(&conn, "artifacts", "is_primary", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(&conn, "artifacts", "sort_order", "INTEGER")?;
+    ensure_column(&conn, "pool_items", "asset_artifact_id", "TEXT")?;
+    ensure_column(&conn, "pool_items", "asset_path", "TEXT")?;
+    ensure_column(&conn, "pool_items", "asset_mime", "TEXT")?;
+    ensure_column(&conn, "pool_items", "asset_bytes", "INTEGER")?;
+    ensure_column(&conn, "pool_items", "asset_sha256", "TEXT")?;
+    ensure_column(&conn, "pool_items", "asset_error", "TEXT")?;
+    ensure_column(&conn, "pool_items", "asset_phash", "TEXT")?;
+    ensure_column(&conn, "pool_items", "updated_at_ms", "INTEGER")?;
+    ensure_column(&conn, "pool_items", "deleted_at_ms", "INTEGER")?;
+
+    // Jobs do not survive a restart; anything still queued/running was interrupted.
+    conn.execute(
+        "UPDATE runs SET status = 'failed', finished_at_ms = ?1, error = 'interrupted by toolserver restart'\n         WHERE status IN ('queued', 'running')",
+        [now_ms()],
+    )
+    .context("failed to recover interrupted runs")?;
+
+    Ok(())
+}
+
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> anyhow::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let exists = stmt
+        .query_map([], |r| r.get::<_, String>(1))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))
+            .with_context(|| format!("failed to add column {table}.{column}"))?;
+    }
     Ok(())
 }
 
@@ -567,6 +760,7 @@ enum AppError {
     BadRequest(String),
     NotFound(String),
     PreconditionFailed(String),
+    Conflict(String),
     Internal(anyhow::Error),
 }
 
@@ -576,12 +770,24 @@ impl From<anyhow::Error> for AppError {
     }
 }
 
+impl AppError {
+    fn message(&self) -> String {
+        match self {
+            Self::BadRequest(msg) | Self::NotFound(msg) | Self::PreconditionFailed(msg) | Self::Conflict(msg) => {
+                msg.clone()
+            }
+            Self::Internal(err) => err.to_string(),
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             Self::PreconditionFailed(msg) => (StatusCode::PRECONDITION_FAILED, msg),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
             Self::Internal(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
         };
         (status, Json(serde_json::json!({ "ok": false, "error": message }))).into_response()
@@ -735,10 +941,14 @@ async fn delete_project(State(state): State<AppState>, Path(project_id): Path<St
 
         let tx = conn.transaction()?;
 
+        tx.execute("DELETE FROM upload_sessions WHERE project_id = ?1", [&project_id])?;
+        tx.execute("DELETE FROM remote_source_links WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM chat_messages WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM chats WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM pool_items WHERE project_id = ?1", [&project_id])?;
+        tx.execute("DELETE FROM pool_item_mirrors WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM artifacts WHERE project_id = ?1", [&project_id])?;
+        tx.execute("DELETE FROM run_artifacts WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM runs WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM consents WHERE project_id = ?1", [&project_id])?;
         tx.execute("DELETE FROM project_settings WHERE project_id = ?1", [&project_id])?;
@@ -829,10 +1039,14 @@ async fn batch_delete_projects(
                     continue;
                 }
 
+                tx.execute("DELETE FROM upload_sessions WHERE project_id = ?1", [project_id])?;
+                tx.execute("DELETE FROM remote_source_links WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM chat_messages WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM chats WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM pool_items WHERE project_id = ?1", [project_id])?;
+                tx.execute("DELETE FROM pool_item_mirrors WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM artifacts WHERE project_id = ?1", [project_id])?;
+                tx.execute("DELETE FROM run_artifacts WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM runs WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM consents WHERE project_id = ?1", [project_id])?;
                 tx.execute("DELETE FROM project_settings WHERE project_id = ?1", [project_id])?;
@@ -1590,13 +1804,78 @@ struct PoolItemResponse {
     data_json: Option<String>,
     selected: bool,
     created_at_ms: i64,
+    /// Local copy of the item's media under `assets/`, once materialized.
+    asset: Option<PoolItemAsset>,
+    /// Why the last materialization attempt failed.
+    asset_error: Option<String>,
+    /// Bumped by every change; PATCH/DELETE can pass it back to detect concurrent edits.
+    updated_at_ms: i64,
+    /// Set while the item is soft-deleted (hidden, restorable until the next zip export).
+    deleted_at_ms: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct PoolItemAsset {
+    artifact_id: String,
+    path: String,
+    mime: String,
+    bytes: u64,
+    sha256: String,
+    /// Perceptual hash (16 hex digits) of image assets, for near-duplicate detection.
+    phash: Option<String>,
+}
+
+/// Columns read by `pool_item_from_row`, in order.
+const POOL_ITEM_COLUMNS: &str = "id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms, \
+     asset_artifact_id, asset_path, asset_mime, asset_bytes, asset_sha256, asset_error, asset_phash, \
+     COALESCE(updated_at_ms, created_at_ms), deleted_at_ms";
+
+fn pool_item_from_row(row: &rusqlite::Row) -> rusqlite::Result<PoolItemResponse> {
+    let asset = match (
+        row.get::<_, Option<String>>(10)?,
+        row.get::<_, Option<String>>(11)?,
+        row.get::<_, Option<String>>(12)?,
+        row.get::<_, Option<i64>>(13)?,
+        row.get::<_, Option<String>>(14)?,
+    ) {
+        (Some(artifact_id), Some(path), Some(mime), Some(bytes), Some(sha256)) => Some(PoolItemAsset {
+            artifact_id,
+            path,
+            mime,
+            bytes: bytes.max(0) as u64,
+            sha256,
+            phash: row.get(16)?,
+        }),
+        _ => None,
+    };
+    Ok(PoolItemResponse {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        kind: row.get(2)?,
+        title: row.get(3)?,
+        source_url: row.get(4)?,
+        license: row.get(5)?,
+        dedup_key: row.get(6)?,
+        data_json: row.get(7)?,
+        selected: row.get::<_, i64>(8)? != 0,
+        created_at_ms: row.get(9)?,
+        asset,
+        asset_error: row.get(15)?,
+        updated_at_ms: row.get(17)?,
+        deleted_at_ms: row.get(18)?,
+    })
 }
 
-async fn list_pool_items(State(state): State<AppState>, Path(project_id): Path<String>) -> AppResult<Json<Vec<PoolItemResponse>>> {
+async fn list_pool_items(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Query(q): Query<ListPoolItemsQuery>,
+) -> AppResult<Json<Vec<PoolItemResponse>>> {
     if project_id.trim().is_empty() {
         return Err(AppError::BadRequest("missing project id".to_string()));
     }
 
+    let include_deleted = q.include_deleted.unwrap_or(false);
     let db_path = state.db_path.clone();
     let items = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Vec<PoolItemResponse>>> {
         let conn = Connection::open(&db_path)?;
@@ -1610,21 +1889,10 @@ async fn list_pool_items(State(state): State<AppState>, Path(project_id): Path<S
         }
 
         let mut stmt = conn.prepare(
-            "SELECT id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms\n             FROM pool_items WHERE project_id = ?1 ORDER BY created_at_ms DESC LIMIT 500",
+            &format!("SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND (?2 OR deleted_at_ms IS NULL)\n             ORDER BY created_at_ms DESC LIMIT 500"),
         )?;
-        let rows = stmt.query_map([&project_id], |row| {
-            Ok(PoolItemResponse {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                kind: row.get(2)?,
-                title: row.get(3)?,
-                source_url: row.get(4)?,
-                license: row.get(5)?,
-                dedup_key: row.get(6)?,
-                data_json: row.get(7)?,
-                selected: row.get::<_, i64>(8)? != 0,
-                created_at_ms: row.get(9)?,
-            })
+        let rows = stmt.query_map(params![&project_id, include_deleted], |row| {
+            pool_item_from_row(row)
         })?;
 
         Ok(Some(rows.filter_map(Result::ok).collect()))
@@ -1638,6 +1906,12 @@ async fn list_pool_items(State(state): State<AppState>, Path(project_id): Path<S
     }
 }
 
+#[derive(Deserialize, Default)]
+struct ListPoolItemsQuery {
+    /// Also list soft-deleted items (to offer a restore).
+    include_deleted: Option<bool>,
+}
+
 #[derive(Deserialize)]
 struct AddPoolItemRequest {
     kind: String,
@@ -1648,6 +1922,9 @@ struct AddPoolItemRequest {
     dedup_key: Option<String>,
     data: Option<serde_json::Value>,
     selected: Option<bool>,
+    /// Also download the item's media into `assets/`; a failed fetch is reported in `asset_error`
+    /// and does not fail the add.
+    materialize: Option<bool>,
 }
 
 fn normalize_url_for_dedup(url: &str) -> String {
@@ -1657,6 +1934,90 @@ fn normalize_url_for_dedup(url: &str) -> String {
     no_trailing.to_lowercase()
 }
 
+/// Fields of a pool item as written by `upsert_pool_item`.
+struct NewPoolItem<'a> {
+    kind: &'a str,
+    title: Option<&'a str>,
+    source_url: Option<&'a str>,
+    license: Option<&'a str>,
+    dedup_key: &'a str,
+    data_json: Option<&'a str>,
+    selected: bool,
+}
+
+/// Inserts a pool item, or updates the one with the same `dedup_key`, logs `pool_item_upsert`
+/// and returns the stored row.
+fn upsert_pool_item(conn: &Connection, project_id: &str, item: &NewPoolItem) -> anyhow::Result<PoolItemResponse> {
+    let id = Uuid::new_v4().to_string();
+    let created_at_ms = now_ms();
+
+    // A source that was merged into another item stays merged.
+    let canonical_id: Option<String> = conn
+        .query_row(
+            "SELECT item_id FROM pool_item_mirrors WHERE project_id = ?1 AND dedup_key = ?2",
+            params![project_id, item.dedup_key],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if let Some(canonical) = canonical_id.and_then(|c| read_pool_item(conn, project_id, &c).transpose()) {
+        let mut canonical = canonical?;
+        // Re-adding a source revives a soft-deleted item, mirrors included.
+        if canonical.deleted_at_ms.is_some() {
+            let ts = created_at_ms.max(canonical.updated_at_ms + 1);
+            conn.execute(
+                "UPDATE pool_items SET deleted_at_ms = NULL, updated_at_ms = ?1 WHERE project_id = ?2 AND id = ?3",
+                params![ts, project_id, &canonical.id],
+            )?;
+            canonical.deleted_at_ms = None;
+            canonical.updated_at_ms = ts;
+        }
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_item_upsert', ?3)",
+            params![
+                project_id,
+                created_at_ms,
+                serde_json::json!({ "kind": item.kind, "dedup_key": item.dedup_key, "source_url": item.source_url, "mirror_of": &canonical.id })
+                    .to_string()
+            ],
+        )?;
+        return Ok(canonical);
+    }
+    conn.execute(
+        "INSERT INTO pool_items (id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms, updated_at_ms)\n         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)\n         ON CONFLICT(project_id, dedup_key) DO UPDATE SET kind = excluded.kind, title = excluded.title, source_url = excluded.source_url, license = excluded.license, data_json = excluded.data_json, selected = excluded.selected,\n           updated_at_ms = MAX(excluded.updated_at_ms, COALESCE(pool_items.updated_at_ms, pool_items.created_at_ms) + 1), deleted_at_ms = NULL",
+        params![
+            &id,
+            project_id,
+            item.kind,
+            item.title,
+            item.source_url,
+            item.license,
+            item.dedup_key,
+            item.data_json,
+            if item.selected { 1 } else { 0 },
+            created_at_ms
+        ],
+    )?;
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_item_upsert', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({ "kind": item.kind, "dedup_key": item.dedup_key, "source_url": item.source_url }).to_string()
+        ],
+    )?;
+
+    let mut stmt = conn.prepare(
+        &format!("SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND dedup_key = ?2 LIMIT 1"),
+    )?;
+    let mut rows = stmt.query(params![project_id, item.dedup_key])?;
+    let Some(row) = rows.next()? else {
+        return Err(anyhow::anyhow!("failed to read back pool item"));
+    };
+
+    Ok(pool_item_from_row(row)?)
+}
+
 async fn add_pool_item(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
@@ -1686,17 +2047,17 @@ async fn add_pool_item(
         .or_else(|| source_url.as_ref().map(|u| format!("url:{}", normalize_url_for_dedup(u))))
         .unwrap_or_else(|| format!("random:{}", Uuid::new_v4()));
 
-    let data_json = if let Some(v) = req.data {
-        Some(v.to_string())
-    } else if let Some(u) = &source_url {
-        Some(serde_json::json!({ "url": u }).to_string())
-    } else {
-        None
+    let data_json = match req.data {
+        Some(v) => Some(v.to_string()),
+        None => source_url.as_ref().map(|u| serde_json::json!({ "url": u }).to_string()),
     };
 
     let selected = req.selected.unwrap_or(true);
+    let materialize = req.materialize.unwrap_or(false);
+    let limits = PoolAssetLimits::new(None, None)?;
 
     let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
     let item = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<PoolItemResponse>> {
         let conn = Connection::open(&db_path)?;
 
@@ -1708,53 +2069,24 @@ async fn add_pool_item(
             return Ok(None);
         }
 
-        let id = Uuid::new_v4().to_string();
-        let created_at_ms = now_ms();
-        conn.execute(
-            "INSERT INTO pool_items (id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms)\n             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)\n             ON CONFLICT(project_id, dedup_key) DO UPDATE SET kind = excluded.kind, title = excluded.title, source_url = excluded.source_url, license = excluded.license, data_json = excluded.data_json, selected = excluded.selected",
-            params![
-                &id,
-                &project_id,
-                &kind,
-                title.as_deref(),
-                source_url.as_deref(),
-                license.as_deref(),
-                &dedup_key,
-                data_json.as_deref(),
-                if selected { 1 } else { 0 },
-                created_at_ms
-            ],
-        )?;
-
-        conn.execute(
-            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_item_upsert', ?3)",
-            params![
-                &project_id,
-                created_at_ms,
-                serde_json::json!({ "kind": &kind, "dedup_key": &dedup_key, "source_url": source_url.as_deref() }).to_string()
-            ],
-        )?;
-
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms\n             FROM pool_items WHERE project_id = ?1 AND dedup_key = ?2 LIMIT 1",
+        let item = upsert_pool_item(
+            &conn,
+            &project_id,
+            &NewPoolItem {
+                kind: &kind,
+                title: title.as_deref(),
+                source_url: source_url.as_deref(),
+                license: license.as_deref(),
+                dedup_key: &dedup_key,
+                data_json: data_json.as_deref(),
+                selected,
+            },
         )?;
-        let mut rows = stmt.query(params![&project_id, &dedup_key])?;
-        let Some(row) = rows.next()? else {
-            return Err(anyhow::anyhow!("failed to read back pool item"));
-        };
-
-        Ok(Some(PoolItemResponse {
-            id: row.get(0)?,
-            project_id: row.get(1)?,
-            kind: row.get(2)?,
-            title: row.get(3)?,
-            source_url: row.get(4)?,
-            license: row.get(5)?,
-            dedup_key: row.get(6)?,
-            data_json: row.get(7)?,
-            selected: row.get::<_, i64>(8)? != 0,
-            created_at_ms: row.get(9)?,
-        }))
+        if materialize {
+            materialize_pool_asset(&conn, &data_dir, &item, None, &limits)?.ok();
+            return read_pool_item(&conn, &project_id, &item.id);
+        }
+        Ok(Some(item))
     })
     .await
     .context("add_pool_item task failed")??;
@@ -1768,6 +2100,8 @@ async fn add_pool_item(
 #[derive(Deserialize)]
 struct SetPoolItemSelectedRequest {
     selected: bool,
+    /// Same as `updated_at_ms` of PATCH.
+    updated_at_ms: Option<i64>,
 }
 
 async fn set_pool_item_selected(
@@ -1783,7 +2117,7 @@ async fn set_pool_item_selected(
     }
 
     let db_path = state.db_path.clone();
-    let item = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<PoolItemResponse>> {
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<PoolItemResponse, AppError>> {
         let conn = Connection::open(&db_path)?;
 
         let exists: bool = conn
@@ -1791,751 +2125,1359 @@ async fn set_pool_item_selected(
             .optional()?
             .is_some();
         if !exists {
-            return Ok(None);
+            return Ok(Err(AppError::NotFound("project not found".to_string())));
         }
 
+        let tx = conn.unchecked_transaction()?;
+        let Some(item) = read_pool_item(&tx, &project_id, &item_id)?.filter(|i| i.deleted_at_ms.is_none()) else {
+            return Ok(Err(AppError::NotFound("pool item not found".to_string())));
+        };
+        if let Err(e) = check_pool_item_version(&item, req.updated_at_ms) {
+            return Ok(Err(e));
+        }
         let selected = req.selected;
-        conn.execute(
-            "UPDATE pool_items SET selected = ?1 WHERE project_id = ?2 AND id = ?3",
-            params![if selected { 1 } else { 0 }, &project_id, &item_id],
-        )?;
+        if item.selected == selected {
+            return Ok(Ok(item));
+        }
 
-        let updated_at_ms = now_ms();
-        conn.execute(
+        let ts = now_ms();
+        tx.execute(
+            "UPDATE pool_items SET selected = ?1, updated_at_ms = ?2 WHERE project_id = ?3 AND id = ?4",
+            params![if selected { 1 } else { 0 }, ts.max(item.updated_at_ms + 1), &project_id, &item_id],
+        )?;
+        tx.execute(
             "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_item_selected', ?3)",
             params![
                 &project_id,
-                updated_at_ms,
+                ts,
                 serde_json::json!({ "item_id": &item_id, "selected": selected }).to_string()
             ],
         )?;
-
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms\n             FROM pool_items WHERE project_id = ?1 AND id = ?2 LIMIT 1",
-        )?;
-        let mut rows = stmt.query(params![&project_id, &item_id])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(PoolItemResponse {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                kind: row.get(2)?,
-                title: row.get(3)?,
-                source_url: row.get(4)?,
-                license: row.get(5)?,
-                dedup_key: row.get(6)?,
-                data_json: row.get(7)?,
-                selected: row.get::<_, i64>(8)? != 0,
-                created_at_ms: row.get(9)?,
-            }));
-        }
-
-        Ok(None)
+        let item = read_pool_item(&tx, &project_id, &item_id)?.context("pool item disappeared")?;
+        tx.commit()?;
+        Ok(Ok(item))
     })
     .await
     .context("set_pool_item_selected task failed")??;
 
-    match item {
-        Some(v) => Ok(Json(v)),
-        None => Err(AppError::NotFound("pool item not found".to_string())),
-    }
+    Ok(Json(result?))
 }
 
-#[derive(Serialize, Clone)]
-struct ArtifactResponse {
-    id: String,
-    project_id: String,
-    kind: String,
-    path: String,
-    created_at_ms: i64,
+const POOL_ASSET_MAX_BYTES: u64 = 50 * 1024 * 1024;
+const POOL_ASSET_TIMEOUT_S: u64 = 60;
+const POOL_ASSET_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_ASSET_MAX_REDIRECTS: u32 = 5;
+/// Bytes buffered before the content type is decided.
+const POOL_ASSET_SNIFF_BYTES: usize = 512;
+
+/// Limits of one materialization; requests may only tighten the defaults.
+struct PoolAssetLimits {
+    max_bytes: u64,
+    timeout: Duration,
 }
 
-async fn list_artifacts(State(state): State<AppState>, Path(project_id): Path<String>) -> AppResult<Json<Vec<ArtifactResponse>>> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
+impl PoolAssetLimits {
+    fn new(max_bytes: Option<u64>, timeout_s: Option<u64>) -> AppResult<Self> {
+        let max_bytes = max_bytes.unwrap_or(POOL_ASSET_MAX_BYTES);
+        if max_bytes == 0 || max_bytes > POOL_ASSET_MAX_BYTES {
+            return Err(AppError::BadRequest(format!("max_bytes must be between 1 and {POOL_ASSET_MAX_BYTES}")));
+        }
+        let timeout_s = timeout_s.unwrap_or(POOL_ASSET_TIMEOUT_S);
+        if timeout_s == 0 || timeout_s > POOL_ASSET_TIMEOUT_S {
+            return Err(AppError::BadRequest(format!("timeout_s must be between 1 and {POOL_ASSET_TIMEOUT_S}")));
+        }
+        Ok(Self {
+            max_bytes,
+            timeout: Duration::from_secs(timeout_s),
+        })
     }
+}
 
-    let db_path = state.db_path.clone();
-    let artifacts = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Vec<ArtifactResponse>>> {
-        let conn = Connection::open(&db_path)?;
+/// Media type of a body from its first bytes, as `(mime, ext)`. Servers often send
+/// `application/octet-stream` or `text/html` for images, so the header is not trusted.
+fn sniff_media_type(head: &[u8]) -> Option<(&'static str, &'static str)> {
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+    if at(0, b"\x89PNG\r\n\x1a\n") {
+        return Some(("image/png", "png"));
+    }
+    if at(0, b"\xff\xd8\xff") {
+        return Some(("image/jpeg", "jpg"));
+    }
+    if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        return Some(("image/gif", "gif"));
+    }
+    if at(0, b"RIFF") && at(8, b"WEBP") {
+        return Some(("image/webp", "webp"));
+    }
+    if at(0, b"RIFF") && at(8, b"WAVE") {
+        return Some(("audio/wav", "wav"));
+    }
+    if at(4, b"ftyp") {
+        let brand = head.get(8..12).unwrap_or_default();
+        return Some(match brand {
+            b"avif" | b"avis" => ("image/avif", "avif"),
+            b"heic" | b"heix" | b"mif1" | b"msf1" => ("image/heic", "heic"),
+            b"qt  " => ("video/quicktime", "mov"),
+            b"M4A " => ("audio/mp4", "m4a"),
+            _ => ("video/mp4", "mp4"),
+        });
+    }
+    if at(0, b"\x1a\x45\xdf\xa3") {
+        let is_webm = head.windows(4).any(|w| w == b"webm");
+        return Some(if is_webm { ("video/webm", "webm") } else { ("video/x-matroska", "mkv") });
+    }
+    if at(0, b"OggS") {
+        return Some(("audio/ogg", "ogg"));
+    }
+    if at(0, b"fLaC") {
+        return Some(("audio/flac", "flac"));
+    }
+    if at(0, b"ID3") || (head.len() >= 2 && head[0] == 0xff && head[1] & 0xe0 == 0xe0) {
+        return Some(("audio/mpeg", "mp3"));
+    }
+    None
+}
 
-        let exists: bool =
-            conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
-                .optional()?
-                .is_some();
-        if !exists {
-            return Ok(None);
-        }
+/// The URL to fetch for a pool item: an explicit media URL in its data, else its source URL.
+fn pool_item_media_url(item: &PoolItemResponse) -> Option<String> {
+    let data: Option<serde_json::Value> = item.data_json.as_deref().and_then(|d| serde_json::from_str(d).ok());
+    let from_data = data.as_ref().and_then(|d| {
+        ["media_url", "image_url", "url"]
+            .iter()
+            .find_map(|k| json_string(d, k).filter(|u| u.starts_with("http://") || u.starts_with("https://")))
+    });
+    from_data.or_else(|| item.source_url.clone())
+}
 
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, kind, path, created_at_ms FROM artifacts WHERE project_id = ?1 ORDER BY created_at_ms DESC LIMIT 200",
-        )?;
-        let rows = stmt.query_map([&project_id], |row| {
-            Ok(ArtifactResponse {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                kind: row.get(2)?,
-                path: row.get(3)?,
-                created_at_ms: row.get(4)?,
-            })
-        })?;
+struct DownloadedAsset {
+    mime: &'static str,
+    ext: &'static str,
+    bytes: u64,
+    sha256: String,
+}
 
-        Ok(Some(rows.filter_map(Result::ok).collect()))
-    })
-    .await
-    .context("list_artifacts task failed")??;
+/// Downloads `url` to `dest`, hashing on the way. `Ok(Err(..))` is a fetch failure worth showing
+/// to the user (HTTP error, not media, too large, timeout); `Err` is a local I/O problem.
+fn download_pool_asset(
+    url: &str,
+    dest: &FsPath,
+    limits: &PoolAssetLimits,
+) -> anyhow::Result<Result<DownloadedAsset, String>> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(POOL_ASSET_CONNECT_TIMEOUT)
+        .timeout(limits.timeout)
+        .redirects(POOL_ASSET_MAX_REDIRECTS)
+        .user_agent(concat!("vidunpack-toolserver/", env!("CARGO_PKG_VERSION")))
+        .build();
+    let resp = match agent.get(url).call() {
+        Ok(r) => r,
+        Err(ureq::Error::Status(code, r)) => return Ok(Err(format!("HTTP {code} {}", r.status_text()))),
+        Err(ureq::Error::Transport(t)) => return Ok(Err(format!("request failed: {t}"))),
+    };
+    if let Some(len) = resp.header("content-length").and_then(|v| v.trim().parse::<u64>().ok()) {
+        if len > limits.max_bytes {
+            return Ok(Err(format!("asset is {len} bytes, over the {} byte limit", limits.max_bytes)));
+        }
+    }
 
-    match artifacts {
-        Some(a) => Ok(Json(a)),
-        None => Err(AppError::NotFound("project not found".to_string())),
+    let part = dest.with_extension("part");
+    let outcome = (|| -> anyhow::Result<Result<DownloadedAsset, String>> {
+        let mut reader = resp.into_reader();
+        let mut out = std::fs::File::create(&part).with_context(|| format!("failed to create {}", part.display()))?;
+        let mut hasher = Sha256::new();
+        let mut head: Vec<u8> = Vec::with_capacity(POOL_ASSET_SNIFF_BYTES);
+        let mut media: Option<(&'static str, &'static str)> = None;
+        let mut total: u64 = 0;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Ok(Err(format!("download interrupted: {e}"))),
+            };
+            total += n as u64;
+            if total > limits.max_bytes {
+                return Ok(Err(format!("asset is over the {} byte limit", limits.max_bytes)));
+            }
+            if media.is_none() {
+                let take = n.min(POOL_ASSET_SNIFF_BYTES - head.len().min(POOL_ASSET_SNIFF_BYTES));
+                head.extend_from_slice(&buf[..take]);
+                if head.len() >= POOL_ASSET_SNIFF_BYTES || n == 0 {
+                    media = sniff_media_type(&head);
+                    if media.is_none() {
+                        let text = String::from_utf8_lossy(&head).trim_start().to_ascii_lowercase();
+                        return Ok(Err(if text.starts_with("<!doctype html") || text.starts_with("<html") {
+                            "url returned an HTML page, not media; link the image/video file itself".to_string()
+                        } else if head.is_empty() {
+                            "url returned an empty body".to_string()
+                        } else {
+                            "unsupported content type (expected an image, video or audio file)".to_string()
+                        }));
+                    }
+                }
+            }
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            out.write_all(&buf[..n])?;
+        }
+        out.sync_all()?;
+        let (mime, ext) = media.unwrap_or(("application/octet-stream", "bin"));
+        Ok(Ok(DownloadedAsset {
+            mime,
+            ext,
+            bytes: total,
+            sha256: hex_lower(&hasher.finalize()),
+        }))
+    })();
+    if !matches!(outcome, Ok(Ok(_))) {
+        let _ = std::fs::remove_file(&part);
     }
+    outcome
 }
 
-#[derive(Deserialize)]
-struct CreateTextArtifactRequest {
-    kind: String,
-    out_path: String,
-    content: String,
-}
+/// Fetches a pool item's media into the project's `assets/` folder and links the artifact to the
+/// item. Failures are stored on the item (`asset_error`) and returned as `Ok(Err(..))`.
+fn materialize_pool_asset(
+    conn: &Connection,
+    data_dir: &FsPath,
+    item: &PoolItemResponse,
+    url: Option<&str>,
+    limits: &PoolAssetLimits,
+) -> anyhow::Result<Result<PoolItemAsset, String>> {
+    let project_id = item.project_id.as_str();
+    let url = url.map(|u| u.to_string()).or_else(|| pool_item_media_url(item));
+    let fetched = match url.as_deref() {
+        Some(u) if u.starts_with("http://") || u.starts_with("https://") => {
+            let assets_dir = data_dir.join(format!("projects/{project_id}/assets"));
+            std::fs::create_dir_all(&assets_dir)?;
+            let stem = assets_dir.join(sanitize_file_name(&item.id));
+            download_pool_asset(u, &stem, limits)?.map(|r| (stem, r))
+        }
+        Some(_) => Err("only http(s) urls can be materialized".to_string()),
+        None => Err("pool item has no url to materialize".to_string()),
+    };
 
-fn sanitize_out_path(out_path: &str) -> Option<String> {
-    let normalized = out_path.replace('\\', "/");
-    let parts = normalized
-        .split('/')
-        .map(str::trim)
-        .filter(|s| !s.is_empty())
-        .filter(|s| *s != "." && *s != "..")
-        .map(sanitize_file_name)
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>();
+    let (stem, DownloadedAsset { mime, ext, bytes, sha256 }) = match fetched {
+        Ok(v) => v,
+        Err(error) => {
+            conn.execute(
+                "UPDATE pool_items SET asset_error = ?1 WHERE project_id = ?2 AND id = ?3",
+                params![&error, project_id, &item.id],
+            )?;
+            conn.execute(
+                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'pool_asset_failed', ?3)",
+                params![
+                    project_id,
+                    now_ms(),
+                    serde_json::json!({ "item_id": &item.id, "url": &url, "error": &error }).to_string()
+                ],
+            )?;
+            return Ok(Err(error));
+        }
+    };
 
-    if parts.is_empty() {
-        None
-    } else {
-        Some(parts.join("/"))
+    let abs = stem.with_extension(ext);
+    std::fs::rename(stem.with_extension("part"), &abs)?;
+    let rel = abs.strip_prefix(data_dir).unwrap_or(&abs).display().to_string();
+    let created_at_ms = now_ms();
+
+    // A re-fetch that changed type leaves the previous copy behind; drop it with its artifact.
+    if let Some(old) = item.asset.as_ref().filter(|a| a.path != rel) {
+        let _ = std::fs::remove_file(data_dir.join(&old.path));
+        conn.execute(
+            "DELETE FROM artifacts WHERE project_id = ?1 AND id = ?2",
+            params![project_id, &old.artifact_id],
+        )?;
     }
+
+    let artifact = ensure_artifact(conn, project_id, "pool_asset", &rel, created_at_ms)?;
+    store_content_hash(conn, &artifact.id, &sha256, &file_stat_key(&abs)?)?;
+    conn.execute(
+        "UPDATE pool_items SET asset_artifact_id = ?1, asset_path = ?2, asset_mime = ?3, asset_bytes = ?4, asset_sha256 = ?5, asset_error = NULL,\n           asset_phash = NULL, updated_at_ms = ?6\n         WHERE project_id = ?7 AND id = ?8",
+        params![
+            &artifact.id,
+            &rel,
+            mime,
+            bytes as i64,
+            &sha256,
+            created_at_ms.max(item.updated_at_ms + 1),
+            project_id,
+            &item.id
+        ],
+    )?;
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_asset_materialized', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({
+                "item_id": &item.id,
+                "url": &url,
+                "path": &rel,
+                "mime": mime,
+                "bytes": bytes,
+                "sha256": &sha256,
+            })
+            .to_string()
+        ],
+    )?;
+
+    let mut asset = PoolItemAsset {
+        artifact_id: artifact.id,
+        path: rel,
+        mime: mime.to_string(),
+        bytes,
+        sha256,
+        phash: None,
+    };
+    let hashed = PoolItemResponse {
+        asset: Some(asset.clone()),
+        ..item.clone()
+    };
+    asset.phash = store_pool_item_phash(conn, data_dir, &hashed)?;
+    Ok(Ok(asset))
 }
 
-async fn create_text_artifact(
+fn read_pool_item(conn: &Connection, project_id: &str, item_id: &str) -> anyhow::Result<Option<PoolItemResponse>> {
+    conn.query_row(
+        &format!("SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND id = ?2"),
+        params![project_id, item_id],
+        pool_item_from_row,
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+#[derive(Deserialize, Default)]
+struct MaterializePoolAssetRequest {
+    /// Overrides the URL picked from the item (`data.media_url`, `data.image_url`, `data.url`,
+    /// then `source_url`).
+    url: Option<String>,
+    max_bytes: Option<u64>,
+    timeout_s: Option<u64>,
+}
+
+async fn materialize_pool_item_asset(
     State(state): State<AppState>,
-    Path(project_id): Path<String>,
-    Json(req): Json<CreateTextArtifactRequest>,
-) -> AppResult<Json<ArtifactResponse>> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
+    Path((project_id, item_id)): Path<(String, String)>,
+    body: Option<Json<MaterializePoolAssetRequest>>,
+) -> AppResult<Json<PoolItemResponse>> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    let limits = PoolAssetLimits::new(req.max_bytes, req.timeout_s)?;
+    let url = req.url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<PoolItemResponse, AppError>> {
+        let conn = Connection::open(&db_path)?;
+        let Some(item) = read_pool_item(&conn, &project_id, &item_id)? else {
+            return Ok(Err(AppError::NotFound("pool item not found".to_string())));
+        };
+        if item.deleted_at_ms.is_some() {
+            return Ok(Err(AppError::Conflict("pool item is deleted; restore it first".to_string())));
+        }
+        if let Err(error) = materialize_pool_asset(&conn, &data_dir, &item, url.as_deref(), &limits)? {
+            return Ok(Err(AppError::BadRequest(format!("failed to materialize asset: {error}"))));
+        }
+        let item = read_pool_item(&conn, &project_id, &item_id)?.context("pool item disappeared")?;
+        Ok(Ok(item))
+    })
+    .await
+    .context("materialize_pool_item_asset task failed")??;
+
+    Ok(Json(result?))
+}
+
+const MAX_POOL_KIND_CHARS: usize = 64;
+const MAX_POOL_TITLE_CHARS: usize = 500;
+const MAX_POOL_LICENSE_CHARS: usize = 200;
+const MAX_POOL_URL_CHARS: usize = 2048;
+
+#[derive(Deserialize)]
+struct UpdatePoolItemRequest {
+    kind: Option<String>,
+    /// For the optional text fields `null` or an empty string clears them; leaving a field out
+    /// keeps it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    source_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    license: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    data: Option<Option<serde_json::Value>>,
+    selected: Option<bool>,
+    /// The `updated_at_ms` the client last saw; the update is refused if the item changed since.
+    updated_at_ms: Option<i64>,
+}
+
+fn trimmed_field(value: Option<Option<String>>, name: &str, max_chars: usize) -> AppResult<Option<Option<String>>> {
+    let value = value.map(|v| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()));
+    if let Some(Some(v)) = value.as_ref() {
+        if v.chars().count() > max_chars {
+            return Err(AppError::BadRequest(format!("{name} is longer than {max_chars} characters")));
+        }
     }
+    Ok(value)
+}
 
-    let kind = req.kind.trim().to_string();
-    if kind.is_empty() {
-        return Err(AppError::BadRequest("missing kind".to_string()));
+/// `Err(Conflict)` unless the item still has the `updated_at_ms` the client based its change on.
+fn check_pool_item_version(item: &PoolItemResponse, expected: Option<i64>) -> Result<(), AppError> {
+    match expected {
+        Some(expected) if expected != item.updated_at_ms => Err(AppError::Conflict(format!(
+            "pool item was modified (updated_at_ms {} != {expected}); reload it and retry",
+            item.updated_at_ms
+        ))),
+        _ => Ok(()),
     }
+}
 
-    let out_path = req.out_path.trim().to_string();
-    let Some(safe_out_path) = sanitize_out_path(&out_path) else {
-        return Err(AppError::BadRequest("invalid out_path".to_string()));
+async fn update_pool_item(
+    State(state): State<AppState>,
+    Path((project_id, item_id)): Path<(String, String)>,
+    Json(req): Json<UpdatePoolItemRequest>,
+) -> AppResult<Json<PoolItemResponse>> {
+    let kind = match req.kind.map(|k| k.trim().to_string()) {
+        Some(k) if k.is_empty() => return Err(AppError::BadRequest("kind cannot be empty".to_string())),
+        Some(k) if k.chars().count() > MAX_POOL_KIND_CHARS => {
+            return Err(AppError::BadRequest(format!("kind is longer than {MAX_POOL_KIND_CHARS} characters")))
+        }
+        k => k,
     };
+    let title = trimmed_field(req.title, "title", MAX_POOL_TITLE_CHARS)?;
+    let license = trimmed_field(req.license, "license", MAX_POOL_LICENSE_CHARS)?;
+    let source_url = trimmed_field(req.source_url, "source_url", MAX_POOL_URL_CHARS)?;
+    if let Some(Some(u)) = source_url.as_ref() {
+        if !(u.starts_with("http://") || u.starts_with("https://")) {
+            return Err(AppError::BadRequest("source_url must start with http:// or https://".to_string()));
+        }
+    }
+    let data_json = match req.data {
+        Some(Some(v)) if !v.is_object() => return Err(AppError::BadRequest("data must be a JSON object".to_string())),
+        Some(v) => Some(v.map(|v| v.to_string())),
+        None => None,
+    };
+    let selected = req.selected;
+    let expected = req.updated_at_ms;
 
-    let content = req.content;
-
-    let data_dir = state.data_dir.clone();
     let db_path = state.db_path.clone();
-
-    let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<ArtifactResponse>> {
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<PoolItemResponse, AppError>> {
         let conn = Connection::open(&db_path)?;
+        let tx = conn.unchecked_transaction()?;
+        let Some(item) = read_pool_item(&tx, &project_id, &item_id)? else {
+            return Ok(Err(AppError::NotFound("pool item not found".to_string())));
+        };
+        if item.deleted_at_ms.is_some() {
+            return Ok(Err(AppError::Conflict("pool item is deleted; restore it first".to_string())));
+        }
+        if let Err(e) = check_pool_item_version(&item, expected) {
+            return Ok(Err(e));
+        }
 
-        let exists: bool = conn
-            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
-            .optional()?
-            .is_some();
-        if !exists {
-            return Ok(None);
+        // A URL-derived dedup key follows the URL, so re-adding the new URL finds this item.
+        let dedup_key = match source_url.as_ref() {
+            Some(Some(u)) if item.dedup_key.starts_with("url:") => format!("url:{}", normalize_url_for_dedup(u)),
+            _ => item.dedup_key.clone(),
+        };
+        if dedup_key != item.dedup_key {
+            let taken = tx
+                .query_row(
+                    "SELECT id FROM pool_items WHERE project_id = ?1 AND dedup_key = ?2 AND id != ?3",
+                    params![&project_id, &dedup_key, &item_id],
+                    |r| r.get::<_, String>(0),
+                )
+                .optional()?;
+            if let Some(other) = taken {
+                return Ok(Err(AppError::Conflict(format!("another pool item already has this url: {other}"))));
+            }
         }
 
-        let rel_path = format!("projects/{}/out/{}", project_id, safe_out_path);
-        let abs_path = data_dir.join(&rel_path);
-        if let Some(parent) = abs_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        let mut changed: Vec<&str> = Vec::new();
+        let mut updated = item.clone();
+        if let Some(k) = kind.filter(|k| *k != item.kind) {
+            updated.kind = k;
+            changed.push("kind");
+        }
+        if let Some(t) = title.filter(|t| *t != item.title) {
+            updated.title = t;
+            changed.push("title");
+        }
+        if let Some(u) = source_url.filter(|u| *u != item.source_url) {
+            updated.source_url = u;
+            changed.push("source_url");
+        }
+        if let Some(l) = license.filter(|l| *l != item.license) {
+            updated.license = l;
+            changed.push("license");
+        }
+        if let Some(d) = data_json.filter(|d| *d != item.data_json) {
+            updated.data_json = d;
+            changed.push("data");
+        }
+        if let Some(s) = selected.filter(|s| *s != item.selected) {
+            updated.selected = s;
+            changed.push("selected");
+        }
+        if changed.is_empty() {
+            return Ok(Ok(item));
         }
-        std::fs::write(&abs_path, content.as_bytes())?;
 
-        let created_at_ms = now_ms();
-        let artifact = ensure_artifact(&conn, &project_id, &kind, &rel_path, created_at_ms)?;
+        // Strictly increasing, so two edits within one millisecond still conflict.
+        let ts = now_ms().max(item.updated_at_ms + 1);
+        tx.execute(
+            "UPDATE pool_items SET kind = ?1, title = ?2, source_url = ?3, license = ?4, data_json = ?5, selected = ?6, dedup_key = ?7,\n               updated_at_ms = ?8\n             WHERE project_id = ?9 AND id = ?10",
+            params![
+                &updated.kind,
+                &updated.title,
+                &updated.source_url,
+                &updated.license,
+                &updated.data_json,
+                if updated.selected { 1 } else { 0 },
+                &dedup_key,
+                ts,
+                &project_id,
+                &item_id
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_item_updated', ?3)",
+            params![
+                &project_id,
+                ts,
+                serde_json::json!({ "item_id": &item_id, "fields": &changed }).to_string()
+            ],
+        )?;
+        tx.commit()?;
 
-        conn.execute(
-            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'text_artifact', ?3)",
+        let item = read_pool_item(&conn, &project_id, &item_id)?.context("pool item disappeared")?;
+        Ok(Ok(item))
+    })
+    .await
+    .context("update_pool_item task failed")??;
+
+    Ok(Json(result?))
+}
+
+/// Version check of DELETE and restore, passed as `?updated_at_ms=`.
+#[derive(Deserialize)]
+struct PoolItemVersionQuery {
+    /// Same as `updated_at_ms` of PATCH.
+    updated_at_ms: Option<i64>,
+}
+
+/// Soft-deletes a pool item: it disappears from listings and exports but can be restored until the
+/// next zip export purges it for good.
+async fn delete_pool_item(
+    State(state): State<AppState>,
+    Path((project_id, item_id)): Path<(String, String)>,
+    Query(q): Query<PoolItemVersionQuery>,
+) -> AppResult<Json<PoolItemResponse>> {
+    set_pool_item_deleted(&state, project_id, item_id, q.updated_at_ms, true).await
+}
+
+async fn restore_pool_item(
+    State(state): State<AppState>,
+    Path((project_id, item_id)): Path<(String, String)>,
+    Query(q): Query<PoolItemVersionQuery>,
+) -> AppResult<Json<PoolItemResponse>> {
+    set_pool_item_deleted(&state, project_id, item_id, q.updated_at_ms, false).await
+}
+
+async fn set_pool_item_deleted(
+    state: &AppState,
+    project_id: String,
+    item_id: String,
+    expected: Option<i64>,
+    deleted: bool,
+) -> AppResult<Json<PoolItemResponse>> {
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<PoolItemResponse, AppError>> {
+        let conn = Connection::open(&db_path)?;
+        let tx = conn.unchecked_transaction()?;
+        let Some(item) = read_pool_item(&tx, &project_id, &item_id)? else {
+            return Ok(Err(AppError::NotFound("pool item not found".to_string())));
+        };
+        if let Err(e) = check_pool_item_version(&item, expected) {
+            return Ok(Err(e));
+        }
+        if item.deleted_at_ms.is_some() == deleted {
+            return Ok(Ok(item));
+        }
+
+        let ts = now_ms().max(item.updated_at_ms + 1);
+        tx.execute(
+            "UPDATE pool_items SET deleted_at_ms = ?1, updated_at_ms = ?2 WHERE project_id = ?3 AND id = ?4",
+            params![deleted.then_some(ts), ts, &project_id, &item_id],
+        )?;
+        tx.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', ?3, ?4)",
             params![
                 &project_id,
-                created_at_ms,
-                serde_json::json!({ "kind": &kind, "path": &rel_path }).to_string()
+                ts,
+                if deleted { "pool_item_deleted" } else { "pool_item_restored" },
+                serde_json::json!({ "item_id": &item_id, "kind": &item.kind, "title": &item.title }).to_string()
             ],
         )?;
+        tx.commit()?;
 
-        Ok(Some(artifact))
+        let item = read_pool_item(&conn, &project_id, &item_id)?.context("pool item disappeared")?;
+        Ok(Ok(item))
     })
     .await
-    .context("create_text_artifact task failed")??;
+    .context("set_pool_item_deleted task failed")??;
 
-    match artifact {
-        Some(a) => Ok(Json(a)),
-        None => Err(AppError::NotFound("project not found".to_string())),
+    Ok(Json(result?))
+}
+
+const MAX_BULK_POOL_IDS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PoolBulkAction {
+    Select,
+    Deselect,
+    Invert,
+    /// Soft delete, as `DELETE /pool/items/{item_id}`.
+    Delete,
+}
+
+/// Every given criterion must match; an empty filter matches every (not deleted) item.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct PoolItemFilter {
+    kind: Option<String>,
+    /// Host of `source_url`; subdomains match too (`example.com` matches `i.example.com`).
+    domain: Option<String>,
+    has_license: Option<bool>,
+    created_after_ms: Option<i64>,
+}
+
+impl PoolItemFilter {
+    fn matches(&self, item: &PoolItemResponse) -> bool {
+        if let Some(kind) = self.kind.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            if !item.kind.eq_ignore_ascii_case(kind) {
+                return false;
+            }
+        }
+        if let Some(domain) = self.domain.as_deref().map(|d| d.trim().trim_start_matches('.').to_lowercase()) {
+            let host = item.source_url.as_deref().and_then(url_domain);
+            let hit = host.is_some_and(|h| h == domain || h.ends_with(&format!(".{domain}")));
+            if !hit {
+                return false;
+            }
+        }
+        if let Some(has_license) = self.has_license {
+            if item.license.is_some() != has_license {
+                return false;
+            }
+        }
+        if let Some(after) = self.created_after_ms {
+            if item.created_at_ms <= after {
+                return false;
+            }
+        }
+        true
     }
 }
 
+#[derive(Deserialize)]
+struct BulkPoolItemsRequest {
+    action: PoolBulkAction,
+    /// Target these items...
+    ids: Option<Vec<String>>,
+    /// ...or every item matching this filter (exactly one of the two).
+    filter: Option<PoolItemFilter>,
+}
+
+enum PoolBulkTarget {
+    Ids(Vec<String>),
+    Filter(PoolItemFilter),
+}
+
 #[derive(Serialize)]
-struct UploadFileArtifactResponse {
-    artifact: ArtifactResponse,
-    bytes: u64,
-    file_name: String,
-    mime: Option<String>,
+struct BulkPoolItemsResponse {
+    action: PoolBulkAction,
+    matched: usize,
+    /// Items whose state actually changed (e.g. `select` skips already selected items).
+    affected_ids: Vec<String>,
+    /// Requested ids left alone because the item is soft-deleted (restore it first).
+    deleted_ids: Vec<String>,
 }
 
-async fn upload_file_artifact(
+/// Applies one selection action (or a soft delete) to many pool items in a single transaction,
+/// logged as one `pool_items_bulk` event when anything changed.
+async fn bulk_pool_items(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-    mut multipart: Multipart,
-) -> AppResult<Json<UploadFileArtifactResponse>> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
-    }
+    Json(req): Json<BulkPoolItemsRequest>,
+) -> AppResult<Json<BulkPoolItemsResponse>> {
+    let action = req.action;
+    let target = match (req.ids, req.filter) {
+        (Some(ids), None) => {
+            let mut unique: Vec<String> = Vec::with_capacity(ids.len());
+            for id in ids.into_iter().map(|id| id.trim().to_string()).filter(|id| !id.is_empty()) {
+                if !unique.contains(&id) {
+                    unique.push(id);
+                }
+            }
+            if unique.is_empty() {
+                return Err(AppError::BadRequest("ids must list at least one pool item".to_string()));
+            }
+            if unique.len() > MAX_BULK_POOL_IDS {
+                return Err(AppError::BadRequest(format!("at most {MAX_BULK_POOL_IDS} ids per request")));
+            }
+            PoolBulkTarget::Ids(unique)
+        }
+        (None, Some(filter)) => PoolBulkTarget::Filter(filter),
+        _ => return Err(AppError::BadRequest("give either ids or filter".to_string())),
+    };
 
-    // Ensure project exists before writing files.
     let db_path = state.db_path.clone();
-    let project_id_check = project_id.clone();
-    let exists = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<BulkPoolItemsResponse, AppError>> {
         let conn = Connection::open(&db_path)?;
-        Ok(conn
-            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_check], |_row| Ok(()))
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
             .optional()?
-            .is_some())
+            .is_some();
+        if !exists {
+            return Ok(Err(AppError::NotFound("project not found".to_string())));
+        }
+
+        apply_pool_bulk(&conn, &project_id, action, &target)
     })
     .await
-    .context("upload_file_artifact db preflight failed")??;
-    if !exists {
-        return Err(AppError::NotFound("project not found".to_string()));
-    }
+    .context("bulk_pool_items task failed")??;
 
-    while let Some(field) = multipart.next_field().await.map_err(|e| AppError::BadRequest(e.to_string()))? {
-        let name = field.name().unwrap_or_default().to_string();
-        if name != "file" {
-            continue;
+    Ok(Json(result?))
+}
+
+/// Applies `action` to the targeted items in one transaction and logs one `pool_items_bulk` event
+/// when anything changed.
+fn apply_pool_bulk(
+    conn: &Connection,
+    project_id: &str,
+    action: PoolBulkAction,
+    target: &PoolBulkTarget,
+) -> anyhow::Result<Result<BulkPoolItemsResponse, AppError>> {
+    let tx = conn.unchecked_transaction()?;
+    // Deleted rows are loaded too, so an id of a soft-deleted item is told apart from a foreign one.
+    let items: Vec<PoolItemResponse> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 ORDER BY created_at_ms ASC"
+        ))?;
+        let rows = stmt.query_map([project_id], pool_item_from_row)?;
+        rows.filter_map(Result::ok).collect()
+    };
+    let mut deleted_ids: Vec<String> = Vec::new();
+    let targets: Vec<&PoolItemResponse> = match target {
+        PoolBulkTarget::Ids(ids) => {
+            if let Some(unknown) = ids.iter().find(|id| !items.iter().any(|i| &i.id == *id)) {
+                return Ok(Err(AppError::BadRequest(format!("not a pool item of this project: {unknown}"))));
+            }
+            deleted_ids = items
+                .iter()
+                .filter(|i| i.deleted_at_ms.is_some() && ids.contains(&i.id))
+                .map(|i| i.id.clone())
+                .collect();
+            items
+                .iter()
+                .filter(|i| i.deleted_at_ms.is_none() && ids.contains(&i.id))
+                .collect()
         }
+        PoolBulkTarget::Filter(filter) => items
+            .iter()
+            .filter(|i| i.deleted_at_ms.is_none() && filter.matches(i))
+            .collect(),
+    };
 
-        let file_name_raw = field.file_name().unwrap_or("upload").to_string();
-        let file_name_safe = sanitize_file_name(&file_name_raw);
-        let file_name = if file_name_safe.trim_matches('_').is_empty() {
-            "upload".to_string()
-        } else {
-            file_name_safe
+    let ts = now_ms();
+    let mut affected_ids: Vec<String> = Vec::new();
+    for item in &targets {
+        let version = ts.max(item.updated_at_ms + 1);
+        let changed = match action {
+            PoolBulkAction::Select | PoolBulkAction::Deselect | PoolBulkAction::Invert => {
+                let selected = match action {
+                    PoolBulkAction::Select => true,
+                    PoolBulkAction::Deselect => false,
+                    _ => !item.selected,
+                };
+                selected != item.selected
+                    && tx.execute(
+                        "UPDATE pool_items SET selected = ?1, updated_at_ms = ?2 WHERE project_id = ?3 AND id = ?4",
+                        params![if selected { 1 } else { 0 }, version, project_id, &item.id],
+                    )? > 0
+            }
+            PoolBulkAction::Delete => {
+                tx.execute(
+                    "UPDATE pool_items SET deleted_at_ms = ?1, updated_at_ms = ?1 WHERE project_id = ?2 AND id = ?3",
+                    params![version, project_id, &item.id],
+                )? > 0
+            }
         };
+        if changed {
+            affected_ids.push(item.id.clone());
+        }
+    }
 
-        let mime = field.content_type().map(|m| m.to_string());
-        let mime_for_event = mime.clone();
-        let created_at_ms = now_ms();
-
-        let rel_path = format!("projects/{}/uploads/{}-{}", project_id, created_at_ms, file_name);
-        let abs_path = state.data_dir.join(&rel_path);
-        if let Some(parent) = abs_path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .with_context(|| format!("failed to create upload dir {}", parent.display()))?;
-        }
-
-        let mut f = tokio::fs::File::create(&abs_path)
-            .await
-            .with_context(|| format!("failed to create {}", abs_path.display()))?;
+    // Nothing changed (already in that state, or no matches): no event, so the log stays meaningful.
+    if !affected_ids.is_empty() {
+        tx.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_items_bulk', ?3)",
+            params![
+                project_id,
+                ts,
+                serde_json::json!({
+                    "action": action,
+                    "filter": match target {
+                        PoolBulkTarget::Filter(f) => Some(f),
+                        PoolBulkTarget::Ids(_) => None,
+                    },
+                    "matched": targets.len(),
+                    "item_ids": &affected_ids,
+                    "deleted_ids": &deleted_ids,
+                })
+                .to_string()
+            ],
+        )?;
+    }
+    tx.commit()?;
 
-        let mut bytes: u64 = 0;
-        let mut field = field;
-        while let Some(chunk) = field
-            .chunk()
-            .await
-            .map_err(|e| AppError::BadRequest(e.to_string()))?
-        {
-            bytes = bytes.saturating_add(chunk.len() as u64);
-            f.write_all(&chunk)
-                .await
-                .with_context(|| format!("failed to write {}", abs_path.display()))?;
-        }
-        f.flush().await.ok();
+    Ok(Ok(BulkPoolItemsResponse {
+        action,
+        matched: targets.len(),
+        affected_ids,
+        deleted_ids,
+    }))
+}
 
-        let db_path = state.db_path.clone();
-        let rel_path_db = rel_path.clone();
-        let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<ArtifactResponse> {
-            let conn = Connection::open(&db_path)?;
+/// Drops soft-deleted pool items for good, with their local assets and mirrors. Runs after a zip
+/// export, which is where "restorable until the next export" ends.
+fn purge_deleted_pool_items(conn: &Connection, data_dir: &FsPath, project_id: &str) -> anyhow::Result<usize> {
+    let items: Vec<PoolItemResponse> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND deleted_at_ms IS NOT NULL"
+        ))?;
+        let rows = stmt.query_map([project_id], pool_item_from_row)?;
+        rows.filter_map(Result::ok).collect()
+    };
+    if items.is_empty() {
+        return Ok(0);
+    }
 
-            let artifact = ensure_artifact(&conn, &project_id, "upload", &rel_path_db, created_at_ms)?;
-            conn.execute(
-                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload', ?3)",
-                params![
-                    &project_id,
-                    created_at_ms,
-                    serde_json::json!({ "path": &rel_path_db, "bytes": bytes, "mime": mime_for_event }).to_string()
-                ],
+    let tx = conn.unchecked_transaction()?;
+    for item in &items {
+        if let Some(asset) = item.asset.as_ref() {
+            tx.execute(
+                "DELETE FROM artifacts WHERE project_id = ?1 AND id = ?2",
+                params![project_id, &asset.artifact_id],
             )?;
-            Ok(artifact)
-        })
-        .await
-        .context("upload_file_artifact db task failed")??;
-
-        return Ok(Json(UploadFileArtifactResponse {
-            artifact,
-            bytes,
-            file_name,
-            mime,
-        }));
+        }
+        tx.execute(
+            "DELETE FROM pool_item_mirrors WHERE project_id = ?1 AND item_id = ?2",
+            params![project_id, &item.id],
+        )?;
+        tx.execute("DELETE FROM pool_items WHERE project_id = ?1 AND id = ?2", params![project_id, &item.id])?;
     }
+    tx.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_items_purged', ?3)",
+        params![
+            project_id,
+            now_ms(),
+            serde_json::json!({ "item_ids": items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>() }).to_string()
+        ],
+    )?;
+    tx.commit()?;
 
-    Err(AppError::BadRequest("missing multipart field 'file'".to_string()))
-}
-
-fn content_type_for_path(path: &FsPath) -> &'static str {
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_ascii_lowercase();
-    match ext.as_str() {
-        "mp4" => "video/mp4",
-        "webm" => "video/webm",
-        "mkv" => "video/x-matroska",
-        "mov" => "video/quicktime",
-        "mp3" => "audio/mpeg",
-        "m4a" => "audio/mp4",
-        "wav" => "audio/wav",
-        "aac" => "audio/aac",
-        "flac" => "audio/flac",
-        "ogg" => "audio/ogg",
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "webp" => "image/webp",
-        "gif" => "image/gif",
-        "svg" => "image/svg+xml",
-        "json" => "application/json",
-        "txt" | "log" | "md" => "text/plain; charset=utf-8",
-        "html" => "text/html; charset=utf-8",
-        _ => "application/octet-stream",
+    for asset in items.iter().filter_map(|i| i.asset.as_ref()) {
+        let _ = std::fs::remove_file(data_dir.join(&asset.path));
     }
+    Ok(items.len())
 }
 
-async fn download_artifact_raw(
-    State(state): State<AppState>,
-    Path((project_id, artifact_id)): Path<(String, String)>,
-) -> AppResult<Response> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
+/// Largest Hamming distance (of 64 bits) still reported as a near-duplicate by default.
+const DEFAULT_PHASH_THRESHOLD: u32 = 10;
+const MAX_PHASH_THRESHOLD: u32 = 32;
+
+/// 64-bit difference hash of an image (the first frame of a GIF): ffmpeg scales it to 9x8 gray and
+/// each bit says whether a pixel is brighter than its right neighbour. Re-encoding, resizing and
+/// recompression flip few bits, so re-hosted copies of a meme land within a small distance.
+fn perceptual_hash(abs: &FsPath) -> anyhow::Result<String> {
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(abs)
+        .args(["-frames:v", "1", "-vf", "scale=9:8:flags=area,format=gray", "-f", "rawvideo", "-"])
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("ffmpeg failed: {}", stderr.trim());
     }
-    if artifact_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing artifact id".to_string()));
+    let px = output.stdout;
+    if px.len() < 72 {
+        anyhow::bail!("ffmpeg returned {} bytes for a 9x8 frame", px.len());
     }
+    let mut hash: u64 = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash = (hash << 1) | u64::from(px[y * 9 + x] > px[y * 9 + x + 1]);
+        }
+    }
+    Ok(format!("{hash:016x}"))
+}
 
-    let db_path = state.db_path.clone();
-    let rel_path = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<String>> {
-        let conn = Connection::open(&db_path)?;
-        let path: Option<String> = conn
-            .query_row(
-                "SELECT path FROM artifacts WHERE id = ?1 AND project_id = ?2 LIMIT 1",
-                params![&artifact_id, &project_id],
-                |row| Ok(row.get(0)?),
-            )
-            .optional()?;
-        Ok(path)
-    })
-    .await
-    .context("download_artifact_raw db task failed")??;
+fn phash_distance(a: &str, b: &str) -> Option<u32> {
+    let a = u64::from_str_radix(a, 16).ok()?;
+    let b = u64::from_str_radix(b, 16).ok()?;
+    Some((a ^ b).count_ones())
+}
 
-    let Some(rel_path) = rel_path else {
-        return Err(AppError::NotFound("artifact not found".to_string()));
+/// Hashes a materialized image asset and stores it on the item; `None` for non-images and for
+/// files ffmpeg cannot decode.
+fn store_pool_item_phash(conn: &Connection, data_dir: &FsPath, item: &PoolItemResponse) -> anyhow::Result<Option<String>> {
+    let Some(asset) = item.asset.as_ref().filter(|a| a.mime.starts_with("image/")) else {
+        return Ok(None);
     };
-    if rel_path.starts_with("http://") || rel_path.starts_with("https://") {
-        return Err(AppError::BadRequest("artifact is not a file".to_string()));
-    }
+    let phash = match perceptual_hash(&data_dir.join(&asset.path)) {
+        Ok(h) => h,
+        Err(err) => {
+            tracing::debug!("perceptual hash of {} failed: {err:#}", asset.path);
+            return Ok(None);
+        }
+    };
+    conn.execute(
+        "UPDATE pool_items SET asset_phash = ?1 WHERE project_id = ?2 AND id = ?3",
+        params![&phash, &item.project_id, &item.id],
+    )?;
+    Ok(Some(phash))
+}
 
-    let abs = state.data_dir.join(&rel_path);
-    if !abs.exists() {
-        return Err(AppError::NotFound("file not found".to_string()));
-    }
+#[derive(Deserialize)]
+struct PoolDuplicatesQuery {
+    threshold: Option<u32>,
+}
 
-    let file = tokio::fs::File::open(&abs)
-        .await
-        .with_context(|| format!("failed to open {}", abs.display()))?;
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+#[derive(Serialize)]
+struct PoolDuplicateMember {
+    item: PoolItemResponse,
+    /// Distance to the cluster's first (oldest) item.
+    distance: u32,
+}
 
-    let mut res = Response::new(body);
-    res.headers_mut().insert(
-        header::CONTENT_TYPE,
-        HeaderValue::from_str(content_type_for_path(&abs)).unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
-    );
+#[derive(Serialize)]
+struct PoolDuplicateCluster {
+    items: Vec<PoolDuplicateMember>,
+    max_distance: u32,
+}
 
-    let file_name = abs
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("file");
-    let safe_name = sanitize_file_name(file_name);
-    let disp = format!("inline; filename=\"{}\"", safe_name);
-    res.headers_mut().insert(
-        header::CONTENT_DISPOSITION,
-        HeaderValue::from_str(&disp).unwrap_or_else(|_| HeaderValue::from_static("inline")),
-    );
-    Ok(res)
+#[derive(Serialize)]
+struct PoolDuplicatesResponse {
+    threshold: u32,
+    clusters: Vec<PoolDuplicateCluster>,
+    /// Image assets that could not be hashed (ffmpeg missing or the file does not decode).
+    unhashed: Vec<String>,
 }
 
-#[derive(Deserialize)]
-struct AddInputUrlRequest {
-    url: String,
+/// Groups items whose hashes are within `threshold` of each other (transitively), oldest first.
+fn pool_duplicate_clusters(items: Vec<(PoolItemResponse, String)>, threshold: u32) -> Vec<PoolDuplicateCluster> {
+    let mut parent: Vec<usize> = (0..items.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..items.len() {
+        for j in (i + 1)..items.len() {
+            if phash_distance(&items[i].1, &items[j].1).is_some_and(|d| d <= threshold) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[b.max(a)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: HashMap<usize, usize> = HashMap::new();
+    for i in 0..items.len() {
+        let r = root(&mut parent, i);
+        let g = *group_of.entry(r).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[g].push(i);
+    }
+
+    let mut slots: Vec<Option<(PoolItemResponse, String)>> = items.into_iter().map(Some).collect();
+    groups
+        .into_iter()
+        .filter(|g| g.len() > 1)
+        .map(|g| {
+            let first_hash = slots[g[0]].as_ref().map(|(_, h)| h.clone()).unwrap_or_default();
+            let members: Vec<PoolDuplicateMember> = g
+                .iter()
+                .filter_map(|&i| slots[i].take())
+                .map(|(item, hash)| PoolDuplicateMember {
+                    distance: phash_distance(&first_hash, &hash).unwrap_or(0),
+                    item,
+                })
+                .collect();
+            PoolDuplicateCluster {
+                max_distance: members.iter().map(|m| m.distance).max().unwrap_or(0),
+                items: members,
+            }
+        })
+        .collect()
 }
 
-async fn add_input_url(
+/// Near-duplicate clusters among the project's materialized image assets. Hashes missing from
+/// older materializations are computed (and stored) on the way.
+async fn list_pool_duplicates(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-    Json(req): Json<AddInputUrlRequest>,
-) -> AppResult<Json<ArtifactResponse>> {
-    let url = req.url.trim().to_string();
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
-    }
-    if url.is_empty() {
-        return Err(AppError::BadRequest("missing url".to_string()));
-    }
-    if !(url.starts_with("http://") || url.starts_with("https://")) {
-        return Err(AppError::BadRequest("url must start with http:// or https://".to_string()));
+    Query(q): Query<PoolDuplicatesQuery>,
+) -> AppResult<Json<PoolDuplicatesResponse>> {
+    let threshold = q.threshold.unwrap_or(DEFAULT_PHASH_THRESHOLD);
+    if threshold > MAX_PHASH_THRESHOLD {
+        return Err(AppError::BadRequest(format!("threshold must be at most {MAX_PHASH_THRESHOLD}")));
     }
 
     let db_path = state.db_path.clone();
-    let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<ArtifactResponse>> {
+    let data_dir = state.data_dir.clone();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<PoolDuplicatesResponse>> {
         let conn = Connection::open(&db_path)?;
-
-        let exists: bool =
-            conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
-                .optional()?
-                .is_some();
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+            .optional()?
+            .is_some();
         if !exists {
             return Ok(None);
         }
 
-        // Dedupe: avoid creating multiple identical input_url artifacts.
-        if let Some((existing_id, existing_created_at_ms)) = conn
-            .query_row(
-                "SELECT id, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'input_url' AND path = ?2 ORDER BY created_at_ms DESC LIMIT 1",
-                params![&project_id, &url],
-                |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
-            )
-            .optional()?
-        {
-            return Ok(Some(ArtifactResponse {
-                id: existing_id,
-                project_id,
-                kind: "input_url".to_string(),
-                path: url,
-                created_at_ms: existing_created_at_ms,
-            }));
-        }
-
-        let id = Uuid::new_v4().to_string();
-        let created_at_ms = now_ms();
-        conn.execute(
-            "INSERT INTO artifacts (id, project_id, kind, path, created_at_ms) VALUES (?1, ?2, 'input_url', ?3, ?4)",
-            params![&id, &project_id, &url, created_at_ms],
-        )?;
+        let items: Vec<PoolItemResponse> = {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND asset_mime LIKE 'image/%' AND deleted_at_ms IS NULL ORDER BY created_at_ms ASC, id ASC"
+            ))?;
+            let rows = stmt.query_map([&project_id], pool_item_from_row)?;
+            rows.filter_map(Result::ok).collect()
+        };
 
-        conn.execute(
-            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'input_url_added', ?3)",
-            params![
-                &project_id,
-                created_at_ms,
-                serde_json::json!({ "url": &url }).to_string()
-            ],
-        )?;
+        let mut hashed: Vec<(PoolItemResponse, String)> = Vec::with_capacity(items.len());
+        let mut unhashed: Vec<String> = Vec::new();
+        for mut item in items {
+            let phash = match item.asset.as_ref().and_then(|a| a.phash.clone()) {
+                Some(h) => Some(h),
+                None => store_pool_item_phash(&conn, &data_dir, &item)?,
+            };
+            match phash {
+                Some(h) => {
+                    if let Some(asset) = item.asset.as_mut() {
+                        asset.phash = Some(h.clone());
+                    }
+                    hashed.push((item, h));
+                }
+                None => unhashed.push(item.id),
+            }
+        }
 
-        Ok(Some(ArtifactResponse {
-            id,
-            project_id,
-            kind: "input_url".to_string(),
-            path: url,
-            created_at_ms,
+        Ok(Some(PoolDuplicatesResponse {
+            threshold,
+            clusters: pool_duplicate_clusters(hashed, threshold),
+            unhashed,
         }))
     })
     .await
-    .context("add_input_url task failed")??;
+    .context("list_pool_duplicates task failed")??;
 
-    match artifact {
-        Some(a) => Ok(Json(a)),
+    match result {
+        Some(r) => Ok(Json(r)),
         None => Err(AppError::NotFound("project not found".to_string())),
     }
 }
 
-#[derive(Serialize)]
-struct ImportLocalResponse {
-    artifact: ArtifactResponse,
-    bytes: u64,
-    file_name: String,
+/// Another place the canonical item can be found, kept when duplicates are merged into it.
+#[derive(Serialize, Clone)]
+struct PoolItemMirror {
+    merged_item_id: String,
+    title: Option<String>,
+    source_url: Option<String>,
+    license: Option<String>,
+    dedup_key: String,
+    asset_sha256: Option<String>,
+    created_at_ms: i64,
 }
 
-fn sanitize_file_name(name: &str) -> String {
-    let mut out = String::new();
-    for ch in name.chars() {
-        if ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' || ch == '_' {
-            out.push(ch);
-        } else {
-            out.push('_');
-        }
-    }
-    while out.starts_with('.') {
-        out.remove(0);
-    }
-    if out.is_empty() {
-        "video".to_string()
-    } else {
-        out
-    }
+fn list_pool_item_mirrors(conn: &Connection, project_id: &str, item_id: &str) -> anyhow::Result<Vec<PoolItemMirror>> {
+    let mut stmt = conn.prepare(
+        "SELECT merged_item_id, title, source_url, license, dedup_key, asset_sha256, created_at_ms\n         FROM pool_item_mirrors WHERE project_id = ?1 AND item_id = ?2 ORDER BY created_at_ms ASC, merged_item_id ASC",
+    )?;
+    let rows = stmt.query_map(params![project_id, item_id], |row| {
+        Ok(PoolItemMirror {
+            merged_item_id: row.get(0)?,
+            title: row.get(1)?,
+            source_url: row.get(2)?,
+            license: row.get(3)?,
+            dedup_key: row.get(4)?,
+            asset_sha256: row.get(5)?,
+            created_at_ms: row.get(6)?,
+        })
+    })?;
+    Ok(rows.filter_map(Result::ok).collect())
 }
 
-async fn import_local_video(
-    State(state): State<AppState>,
-    Path(project_id): Path<String>,
-    mut multipart: Multipart,
-) -> AppResult<Json<ImportLocalResponse>> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
-    }
+#[derive(Serialize)]
+struct PoolItemWithMirrors {
+    item: PoolItemResponse,
+    mirrors: Vec<PoolItemMirror>,
+}
 
-    // Ensure project exists first.
-    let project_id_for_check = project_id.clone();
+async fn get_pool_item_mirrors(
+    State(state): State<AppState>,
+    Path((project_id, item_id)): Path<(String, String)>,
+) -> AppResult<Json<PoolItemWithMirrors>> {
     let db_path = state.db_path.clone();
-    let exists = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<PoolItemWithMirrors>> {
         let conn = Connection::open(&db_path)?;
-        Ok(conn
-            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_for_check], |_row| Ok(()))
-            .optional()?
-            .is_some())
+        let Some(item) = read_pool_item(&conn, &project_id, &item_id)? else {
+            return Ok(None);
+        };
+        Ok(Some(PoolItemWithMirrors {
+            mirrors: list_pool_item_mirrors(&conn, &project_id, &item_id)?,
+            item,
+        }))
     })
     .await
-    .context("project existence check failed")??;
-    if !exists {
-        return Err(AppError::NotFound("project not found".to_string()));
-    }
+    .context("get_pool_item_mirrors task failed")??;
 
-    while let Some(mut field) = multipart.next_field().await.context("multipart read failed")? {
-        let field_name = field.name().unwrap_or("");
-        if !field_name.is_empty() && field_name != "file" {
-            continue;
-        }
+    match result {
+        Some(r) => Ok(Json(r)),
+        None => Err(AppError::NotFound("pool item not found".to_string())),
+    }
+}
 
-        let original_name = field.file_name().unwrap_or("video");
-        let sanitized = sanitize_file_name(original_name);
-        let file_name = format!("{}_{}", Uuid::new_v4(), sanitized);
-        let rel_path = format!("projects/{}/media/{}", project_id, file_name);
-        let abs_path = state.data_dir.join(&rel_path);
+#[derive(Deserialize)]
+struct MergePoolItemsRequest {
+    /// The item that survives.
+    canonical_id: String,
+    /// Items folded into it; their sources become mirrors of the canonical item.
+    item_ids: Vec<String>,
+}
 
-        if let Some(parent) = abs_path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .with_context(|| format!("failed to create dir {}", parent.display()))?;
+/// Merges near-duplicates into one canonical item. The merged items (and their local copies) go
+/// away; their URLs, titles and licenses are kept as mirrors, and re-adding one of those URLs
+/// lands on the canonical item instead of creating a new one.
+async fn merge_pool_items(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<MergePoolItemsRequest>,
+) -> AppResult<Json<PoolItemWithMirrors>> {
+    let canonical_id = req.canonical_id.trim().to_string();
+    if canonical_id.is_empty() {
+        return Err(AppError::BadRequest("missing canonical_id".to_string()));
+    }
+    let mut merged_ids: Vec<String> = Vec::new();
+    for id in req.item_ids.iter().map(|id| id.trim()) {
+        if id.is_empty() || id == canonical_id || merged_ids.iter().any(|m| m == id) {
+            continue;
         }
+        merged_ids.push(id.to_string());
+    }
+    if merged_ids.is_empty() {
+        return Err(AppError::BadRequest("item_ids must name at least one item other than canonical_id".to_string()));
+    }
 
-        let mut out = tokio::fs::File::create(&abs_path)
-            .await
-            .with_context(|| format!("failed to create file {}", abs_path.display()))?;
-
-        let mut bytes: u64 = 0;
-        while let Some(chunk) = field.chunk().await.context("multipart chunk read failed")? {
-            out.write_all(&chunk).await.context("write failed")?;
-            bytes = bytes.saturating_add(chunk.len() as u64);
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<PoolItemWithMirrors, AppError>> {
+        let conn = Connection::open(&db_path)?;
+        let Some(mut canonical) = read_pool_item(&conn, &project_id, &canonical_id)?.filter(|c| c.deleted_at_ms.is_none()) else {
+            return Ok(Err(AppError::NotFound("canonical pool item not found".to_string())));
+        };
+        let mut merged: Vec<PoolItemResponse> = Vec::with_capacity(merged_ids.len());
+        for id in &merged_ids {
+            match read_pool_item(&conn, &project_id, id)?.filter(|i| i.deleted_at_ms.is_none()) {
+                Some(item) => merged.push(item),
+                None => return Ok(Err(AppError::NotFound(format!("pool item not found: {id}")))),
+            }
         }
-        out.flush().await.context("flush failed")?;
-
-        let db_path = state.db_path.clone();
-        let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<ArtifactResponse> {
-            let conn = Connection::open(&db_path)?;
-            let id = Uuid::new_v4().to_string();
-            let created_at_ms = now_ms();
-
-            conn.execute(
-                "INSERT INTO artifacts (id, project_id, kind, path, created_at_ms) VALUES (?1, ?2, 'input_video', ?3, ?4)",
-                params![&id, &project_id, &rel_path, created_at_ms],
-            )?;
 
-            conn.execute(
-                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'input_video_imported', ?3)",
+        let ts = now_ms();
+        let mut stale_files: Vec<String> = Vec::new();
+        let tx = conn.unchecked_transaction()?;
+        for item in &merged {
+            tx.execute(
+                "INSERT OR REPLACE INTO pool_item_mirrors (project_id, item_id, merged_item_id, title, source_url, license, dedup_key, asset_sha256, created_at_ms)\n                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
                 params![
                     &project_id,
-                    created_at_ms,
-                    serde_json::json!({ "path": &rel_path, "bytes": bytes }).to_string()
+                    &canonical.id,
+                    &item.id,
+                    &item.title,
+                    &item.source_url,
+                    &item.license,
+                    &item.dedup_key,
+                    item.asset.as_ref().map(|a| &a.sha256),
+                    ts
                 ],
             )?;
+            // Mirrors of a merged item now point at the canonical one.
+            tx.execute(
+                "UPDATE pool_item_mirrors SET item_id = ?1 WHERE project_id = ?2 AND item_id = ?3",
+                params![&canonical.id, &project_id, &item.id],
+            )?;
+            if let Some(asset) = item.asset.as_ref() {
+                if canonical.asset.as_ref().is_none_or(|c| c.path != asset.path) {
+                    tx.execute(
+                        "DELETE FROM artifacts WHERE project_id = ?1 AND id = ?2",
+                        params![&project_id, &asset.artifact_id],
+                    )?;
+                    stale_files.push(asset.path.clone());
+                }
+            }
+            tx.execute("DELETE FROM pool_items WHERE project_id = ?1 AND id = ?2", params![&project_id, &item.id])?;
+        }
 
-            Ok(ArtifactResponse {
-                id,
-                project_id,
-                kind: "input_video".to_string(),
-                path: rel_path,
-                created_at_ms,
-            })
-        })
-        .await
-        .context("import_local_video db task failed")??;
+        // The canonical item inherits what it was missing, and stays selected if any copy was.
+        canonical.title = canonical.title.or_else(|| merged.iter().find_map(|m| m.title.clone()));
+        canonical.license = canonical.license.or_else(|| merged.iter().find_map(|m| m.license.clone()));
+        canonical.source_url = canonical.source_url.or_else(|| merged.iter().find_map(|m| m.source_url.clone()));
+        let selected = canonical.selected || merged.iter().any(|m| m.selected);
+        tx.execute(
+            "UPDATE pool_items SET title = ?1, license = ?2, source_url = ?3, selected = ?4, updated_at_ms = ?5 WHERE project_id = ?6 AND id = ?7",
+            params![
+                &canonical.title,
+                &canonical.license,
+                &canonical.source_url,
+                if selected { 1 } else { 0 },
+                ts.max(canonical.updated_at_ms + 1),
+                &project_id,
+                &canonical.id
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'pool_items_merged', ?3)",
+            params![
+                &project_id,
+                ts,
+                serde_json::json!({ "canonical_id": &canonical.id, "merged_ids": &merged_ids }).to_string()
+            ],
+        )?;
+        tx.commit()?;
 
-        return Ok(Json(ImportLocalResponse {
-            artifact,
-            bytes,
-            file_name,
-        }));
-    }
+        for path in stale_files {
+            let _ = std::fs::remove_file(data_dir.join(path));
+        }
 
-    Err(AppError::BadRequest("missing multipart field 'file'".to_string()))
-}
+        let item = read_pool_item(&conn, &project_id, &canonical_id)?.context("pool item disappeared")?;
+        Ok(Ok(PoolItemWithMirrors {
+            mirrors: list_pool_item_mirrors(&conn, &project_id, &canonical_id)?,
+            item,
+        }))
+    })
+    .await
+    .context("merge_pool_items task failed")??;
 
-#[derive(Deserialize)]
-struct ImportRemoteMediaRequest {
-    url: String,
-    download: Option<bool>,
-    cookies_from_browser: Option<String>,
+    Ok(Json(result?))
 }
 
-#[derive(Serialize)]
-struct RemoteMediaInfoSummary {
-    extractor: String,
+#[derive(Serialize, Clone)]
+struct ArtifactResponse {
     id: String,
-    title: String,
-    duration_s: Option<f64>,
-    webpage_url: String,
-    thumbnail: Option<String>,
-    description: Option<String>,
+    project_id: String,
+    kind: String,
+    path: String,
+    created_at_ms: i64,
 }
 
-#[derive(Serialize)]
-struct ImportRemoteMediaResponse {
-    info: RemoteMediaInfoSummary,
-    info_artifact: ArtifactResponse,
-    input_video: Option<ArtifactResponse>,
-}
+async fn list_artifacts(State(state): State<AppState>, Path(project_id): Path<String>) -> AppResult<Json<Vec<ArtifactResponse>>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
 
-enum ImportRemoteMediaOutcome {
-    Ok(ImportRemoteMediaResponse),
-    NotFound,
-    PreconditionFailed(String),
-}
+    let db_path = state.db_path.clone();
+    let artifacts = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Vec<ArtifactResponse>>> {
+        let conn = Connection::open(&db_path)?;
 
-fn env_trim(key: &str) -> Option<String> {
-    std::env::var(key).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
-}
+        let exists: bool =
+            conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+                .optional()?
+                .is_some();
+        if !exists {
+            return Ok(None);
+        }
 
-fn json_string(v: &serde_json::Value, key: &str) -> Option<String> {
-    v.get(key).and_then(|x| x.as_str()).map(|s| s.to_string())
-}
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, kind, path, created_at_ms FROM artifacts WHERE project_id = ?1 ORDER BY created_at_ms DESC LIMIT 200",
+        )?;
+        let rows = stmt.query_map([&project_id], |row| {
+            Ok(ArtifactResponse {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                kind: row.get(2)?,
+                path: row.get(3)?,
+                created_at_ms: row.get(4)?,
+            })
+        })?;
 
-fn json_f64(v: &serde_json::Value, key: &str) -> Option<f64> {
-    let x = v.get(key)?;
-    if let Some(n) = x.as_f64() {
-        return Some(n);
+        Ok(Some(rows.filter_map(Result::ok).collect()))
+    })
+    .await
+    .context("list_artifacts task failed")??;
+
+    match artifacts {
+        Some(a) => Ok(Json(a)),
+        None => Err(AppError::NotFound("project not found".to_string())),
     }
-    x.as_str().and_then(|s| s.parse::<f64>().ok())
 }
 
-fn clean_one_line(s: &str) -> String {
-    s.split_whitespace().collect::<Vec<_>>().join(" ").trim().to_string()
+#[derive(Deserialize)]
+struct CreateTextArtifactRequest {
+    kind: String,
+    out_path: String,
+    content: String,
 }
 
-fn is_cookie_db_copy_error(msg: &str) -> bool {
-    let lower = msg.to_ascii_lowercase();
-    lower.contains("could not copy") && lower.contains("cookie database")
-}
+fn sanitize_out_path(out_path: &str) -> Option<String> {
+    let normalized = out_path.replace('\\', "/");
+    let parts = normalized
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter(|s| *s != "." && *s != "..")
+        .map(sanitize_file_name)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
 
-fn cookie_db_copy_help(browser: &str) -> String {
-    format!(
-        "yt-dlp failed to read cookies from browser ({browser}).\n\n\
-Common cause on Windows: the browser is running and yt-dlp cannot copy the cookies database.\n\n\
-Fix:\n\
-1) Fully close the browser ({browser}) and retry (including background processes).\n\
-2) Or switch to another browser in Settings (edge/firefox).\n\n\
-Ref: yt-dlp issue 7271"
-    )
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
 }
 
-fn truncate_chars(s: &str, max_chars: usize) -> String {
-    let trimmed = s.trim();
-    if trimmed.chars().count() <= max_chars {
-        return trimmed.to_string();
-    }
-    let mut out: String = trimmed.chars().take(max_chars).collect();
-    out.push('…');
-    out
-}
-
-fn pick_downloaded_file(out_dir: &FsPath, file_base: &str) -> anyhow::Result<Option<PathBuf>> {
-    let mut candidates: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
-    let rd = match std::fs::read_dir(out_dir) {
-        Ok(v) => v,
-        Err(_) => return Ok(None),
-    };
-    for entry in rd {
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        let name = match path.file_name().and_then(|s| s.to_str()) {
-            Some(s) => s,
-            None => continue,
-        };
-        if !name.starts_with(file_base) {
-            continue;
-        }
-        let meta = match entry.metadata() {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
-        let modified = meta.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-        candidates.push((modified, path));
-    }
-    candidates.sort_by_key(|(ts, _)| *ts);
-    Ok(candidates.pop().map(|(_, p)| p))
-}
-
-async fn import_remote_media(
+async fn create_text_artifact(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-    Json(req): Json<ImportRemoteMediaRequest>,
-) -> AppResult<Json<ImportRemoteMediaResponse>> {
+    Json(req): Json<CreateTextArtifactRequest>,
+) -> AppResult<Json<ArtifactResponse>> {
     if project_id.trim().is_empty() {
         return Err(AppError::BadRequest("missing project id".to_string()));
     }
 
-    let url = req.url.trim().to_string();
-    if url.is_empty() {
-        return Err(AppError::BadRequest("missing url".to_string()));
-    }
-    if !(url.starts_with("http://") || url.starts_with("https://")) {
-        return Err(AppError::BadRequest("url must start with http:// or https://".to_string()));
-    }
-
-    if !state.ytdlp {
-        return Err(AppError::PreconditionFailed(
-            "yt-dlp not found; install yt-dlp (and restart toolserver) to enable URL resolve/download".to_string(),
-        ));
+    let kind = req.kind.trim().to_string();
+    if kind.is_empty() {
+        return Err(AppError::BadRequest("missing kind".to_string()));
     }
 
-    let download = req.download.unwrap_or(false);
-    if download && !state.ffmpeg {
-        return Err(AppError::PreconditionFailed(
-            "ffmpeg not found on PATH.\n\nRemote downloads often require ffmpeg to merge video+audio into a single mp4.\n\nInstall ffmpeg and restart toolserver.\n\nWindows (winget): winget install Gyan.FFmpeg\nmacOS (brew):    brew install ffmpeg\nUbuntu/Debian:   sudo apt-get install ffmpeg".to_string(),
-        ));
-    }
+    let out_path = req.out_path.trim().to_string();
+    let Some(safe_out_path) = sanitize_out_path(&out_path) else {
+        return Err(AppError::BadRequest("invalid out_path".to_string()));
+    };
 
-    let cookies_from_browser = req
-        .cookies_from_browser
-        .as_ref()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .or_else(|| env_trim("YTDLP_COOKIES_FROM_BROWSER"));
+    let content = req.content;
 
     let data_dir = state.data_dir.clone();
     let db_path = state.db_path.clone();
-    let ytdlp_cmd = state.ytdlp_cmd.clone();
 
-    let outcome = tokio::task::spawn_blocking(move || -> anyhow::Result<ImportRemoteMediaOutcome> {
+    let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<ArtifactResponse>> {
         let conn = Connection::open(&db_path)?;
 
         let exists: bool = conn
@@ -2543,398 +3485,7187 @@ async fn import_remote_media(
             .optional()?
             .is_some();
         if !exists {
-            return Ok(ImportRemoteMediaOutcome::NotFound);
+            return Ok(None);
         }
 
-        let consented: bool = conn
-            .query_row("SELECT consented FROM consents WHERE project_id = ?1", [&project_id], |r| {
-                Ok(r.get::<_, i64>(0)? != 0)
-            })
-            .optional()?
-            .unwrap_or(false);
-        if !consented {
-            return Ok(ImportRemoteMediaOutcome::PreconditionFailed(
-                "consent required: save URL and confirm consent first".to_string(),
-            ));
+        let rel_path = format!("projects/{}/out/{}", project_id, safe_out_path);
+        let abs_path = data_dir.join(&rel_path);
+        if let Some(parent) = abs_path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
-
-        let resolve_once = |cookies: Option<&str>| -> anyhow::Result<serde_json::Value> {
-            let mut cmd = Command::new(&ytdlp_cmd);
-            cmd.args(["--dump-single-json", "--skip-download", "--no-playlist", "--no-warnings"]);
-            if let Some(c) = cookies {
-                cmd.args(["--cookies-from-browser", c]);
-            }
-            cmd.arg(&url);
-            let output = run_cmd_output(&mut cmd)?;
-            let stdout = String::from_utf8(output.stdout)?;
-            Ok(serde_json::from_str(stdout.trim())?)
-        };
-
-        // Resolve URL to yt-dlp info JSON (works for bilibili + other supported sites).
-        //
-        // Important: Do NOT force `--cookies-from-browser` for resolve. On Windows, Chrome/Edge
-        // frequently fail with "Could not copy ... cookie database" when the browser is running.
-        // We first try without cookies (public metadata), then retry with cookies only if needed.
-        let info_json: serde_json::Value = match resolve_once(None) {
-            Ok(v) => v,
-            Err(no_cookie_err) => match cookies_from_browser.as_ref() {
-                Some(c) => match resolve_once(Some(c)) {
-                    Ok(v) => v,
-                    Err(cookie_err) => {
-                        if is_cookie_db_copy_error(&cookie_err.to_string()) {
-                            return Ok(ImportRemoteMediaOutcome::PreconditionFailed(cookie_db_copy_help(c)));
-                        }
-                        return Err(cookie_err).context(format!("yt-dlp resolve failed without cookies: {no_cookie_err}"));
-                    }
-                },
-                None => return Err(no_cookie_err),
-            },
-        };
+        std::fs::write(&abs_path, content.as_bytes())?;
 
         let created_at_ms = now_ms();
-        let safe_out_path = sanitize_out_path(&format!("ytdlp/info-{created_at_ms}.json"))
-            .ok_or_else(|| anyhow::anyhow!("failed to build safe out_path"))?;
-        let rel_info_path = format!("projects/{}/out/{}", project_id, safe_out_path);
-        let abs_info_path = data_dir.join(&rel_info_path);
-        if let Some(parent) = abs_info_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        std::fs::write(&abs_info_path, serde_json::to_vec_pretty(&info_json)?)?;
-        let info_artifact = ensure_artifact(&conn, &project_id, "ytdlp_info", &rel_info_path, created_at_ms)?;
+        let artifact = ensure_artifact(&conn, &project_id, &kind, &rel_path, created_at_ms)?;
 
         conn.execute(
-            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_resolve', ?3)",
-            params![&project_id, created_at_ms, serde_json::json!({ "url": &url }).to_string()],
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'text_artifact', ?3)",
+            params![
+                &project_id,
+                created_at_ms,
+                serde_json::json!({ "kind": &kind, "path": &rel_path }).to_string()
+            ],
         )?;
 
-        let extractor = json_string(&info_json, "extractor")
-            .or_else(|| json_string(&info_json, "extractor_key"))
-            .unwrap_or_else(|| "unknown".to_string());
-        let id = json_string(&info_json, "id").unwrap_or_else(|| "unknown".to_string());
-        let title = json_string(&info_json, "title").unwrap_or_else(|| "untitled".to_string());
-        let webpage_url = json_string(&info_json, "webpage_url").unwrap_or_else(|| url.clone());
-        let duration_s = json_f64(&info_json, "duration");
-        let thumbnail = json_string(&info_json, "thumbnail")
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty());
-        let description = json_string(&info_json, "description")
-            .map(|s| truncate_chars(&clean_one_line(&s), 280))
-            .filter(|s| !s.is_empty());
-
-        let mut input_video: Option<ArtifactResponse> = None;
-
-        if download {
-            let out_dir_rel = format!("projects/{}/media/remote", project_id);
-            let out_dir_abs = data_dir.join(&out_dir_rel);
-            std::fs::create_dir_all(&out_dir_abs)?;
-
-            let file_base = {
-                let ex = sanitize_file_name(&extractor);
-                let vid = sanitize_file_name(&id);
-                let base = format!("{ex}-{vid}");
-                if base.trim_matches('_').is_empty() {
-                    format!("remote-{created_at_ms}")
-                } else {
-                    base
-                }
-            };
-
-            let out_template = out_dir_abs.join(format!("{file_base}.%(ext)s"));
-            let out_template_str = out_template.display().to_string();
-
-            let base_args = [
-                "--no-playlist",
-                "--restrict-filenames",
-                "--no-warnings",
-                "--no-progress",
-                "--merge-output-format",
-                "mp4",
-                "-o",
-                &out_template_str,
-            ];
-
-            let run_download = |cookies: Option<&str>| -> anyhow::Result<()> {
-                let mut dl = Command::new(&ytdlp_cmd);
-                dl.args(base_args);
-                if let Some(c) = cookies {
-                    dl.args(["--cookies-from-browser", c]);
-                }
-                dl.arg(&url);
-                run_cmd(&mut dl)
-            };
-
-            if let Some(c) = cookies_from_browser.as_ref() {
-                match run_download(Some(c)) {
-                    Ok(()) => {}
-                    Err(err) => {
-                        if is_cookie_db_copy_error(&err.to_string()) {
-                            // Best-effort fallback: public videos might still download without cookies.
-                            if run_download(None).is_err() {
-                                return Ok(ImportRemoteMediaOutcome::PreconditionFailed(cookie_db_copy_help(c)));
-                            }
-                        } else {
-                            return Err(err);
-                        }
-                    }
-                }
-            } else {
-                run_download(None)?;
-            }
-
-            let expected = out_dir_abs.join(format!("{file_base}.mp4"));
-            let downloaded_abs = if expected.exists() {
-                expected
-            } else {
-                pick_downloaded_file(&out_dir_abs, &file_base)?
-                    .ok_or_else(|| anyhow::anyhow!("download finished but output file not found"))?
-            };
-
-            let rel_video_path = downloaded_abs
-                .strip_prefix(&data_dir)
-                .unwrap_or(&downloaded_abs)
-                .display()
-                .to_string();
-            let video_artifact = ensure_artifact(&conn, &project_id, "input_video", &rel_video_path, created_at_ms)?;
-            input_video = Some(video_artifact);
-
-            conn.execute(
-                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_download', ?3)",
-                params![
-                    &project_id,
-                    created_at_ms,
-                    serde_json::json!({ "url": &url, "path": &rel_video_path }).to_string()
-                ],
-            )?;
-        }
-
-        Ok(ImportRemoteMediaOutcome::Ok(ImportRemoteMediaResponse {
-            info: RemoteMediaInfoSummary {
-                extractor,
-                id,
-                title,
-                duration_s,
-                webpage_url,
-                thumbnail,
-                description,
-            },
-            info_artifact,
-            input_video,
-        }))
+        Ok(Some(artifact))
     })
     .await
-    .context("import_remote_media task failed")??;
+    .context("create_text_artifact task failed")??;
 
-    match outcome {
-        ImportRemoteMediaOutcome::Ok(r) => Ok(Json(r)),
-        ImportRemoteMediaOutcome::NotFound => Err(AppError::NotFound("project not found".to_string())),
-        ImportRemoteMediaOutcome::PreconditionFailed(msg) => Err(AppError::PreconditionFailed(msg)),
+    match artifact {
+        Some(a) => Ok(Json(a)),
+        None => Err(AppError::NotFound("project not found".to_string())),
     }
 }
 
-async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
-    Json(HealthResponse {
-        ok: true,
-        service: "toolserver",
-        data_dir: state.data_dir.display().to_string(),
-        ffmpeg: state.ffmpeg,
-        ffprobe: state.ffprobe,
-        ytdlp: state.ytdlp,
-        db_path: state.db_path.display().to_string(),
-    })
-}
-
-#[derive(Serialize)]
-struct ProfileResponse {
-    profile: ProfileMemory,
-    profile_rel_path: String,
-    profile_abs_path: String,
-}
-
-#[derive(Debug, Clone, Serialize)]
-struct FeedbackItem {
-    url: String,
-    kind: String,
-    rating: i64,
-    anchor: bool,
-    created_at_ms: i64,
-    updated_at_ms: i64,
-}
-
 #[derive(Serialize)]
-struct ProjectFeedbackResponse {
-    ok: bool,
-    project_id: String,
-    items: Vec<FeedbackItem>,
+struct UploadFileArtifactResponse {
+    artifact: ArtifactResponse,
+    bytes: u64,
+    file_name: String,
+    mime: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct UpsertProjectFeedbackRequest {
-    url: String,
-    kind: Option<String>,
-    action: String, // like | dislike | clear | anchor | unanchor
-}
+async fn upload_file_artifact(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    mut multipart: Multipart,
+) -> AppResult<Json<UploadFileArtifactResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
 
-async fn get_profile(State(state): State<AppState>) -> AppResult<Json<ProfileResponse>> {
+    // Ensure project exists before writing files.
     let db_path = state.db_path.clone();
-    let data_dir = state.data_dir.clone();
-
-    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<ProfileResponse> {
+    let project_id_check = project_id.clone();
+    let exists = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
         let conn = Connection::open(&db_path)?;
-        let mut profile = load_profile(&conn)?;
-        if profile.prompt.trim().is_empty() {
-            profile.prompt = build_profile_prompt(&profile);
-        }
-        Ok(ProfileResponse {
-            profile,
-            profile_rel_path: profile_file_name().to_string(),
-            profile_abs_path: data_dir.join(profile_file_name()).display().to_string(),
-        })
+        Ok(conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_check], |_row| Ok(()))
+            .optional()?
+            .is_some())
     })
     .await
-    .context("get_profile task failed")??;
+    .context("upload_file_artifact db preflight failed")??;
+    if !exists {
+        return Err(AppError::NotFound("project not found".to_string()));
+    }
 
-    Ok(Json(resp))
-}
+    while let Some(field) = multipart.next_field().await.map_err(|e| AppError::BadRequest(e.to_string()))? {
+        let name = field.name().unwrap_or_default().to_string();
+        if name != "file" {
+            continue;
+        }
 
-async fn reset_profile(State(state): State<AppState>) -> AppResult<Json<ProfileResponse>> {
-    let db_path = state.db_path.clone();
-    let data_dir = state.data_dir.clone();
+        let file_name_raw = field.file_name().unwrap_or("upload").to_string();
+        let file_name_safe = sanitize_file_name(&file_name_raw);
+        let file_name = if file_name_safe.trim_matches('_').is_empty() {
+            "upload".to_string()
+        } else {
+            file_name_safe
+        };
 
-    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<ProfileResponse> {
-        let conn = Connection::open(&db_path)?;
-        conn.execute("DELETE FROM profile WHERE id = 1", [])?;
+        let mime = field.content_type().map(|m| m.to_string());
+        let mime_for_event = mime.clone();
+        let created_at_ms = now_ms();
 
-        let file_abs = data_dir.join(profile_file_name());
-        if file_abs.exists() {
-            let _ = std::fs::remove_file(&file_abs);
+        let rel_path = format!("projects/{}/uploads/{}-{}", project_id, created_at_ms, file_name);
+        let abs_path = state.data_dir.join(&rel_path);
+        if let Some(parent) = abs_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("failed to create upload dir {}", parent.display()))?;
         }
 
-        Ok(ProfileResponse {
-            profile: ProfileMemory::default(),
-            profile_rel_path: profile_file_name().to_string(),
-            profile_abs_path: file_abs.display().to_string(),
-        })
-    })
-    .await
-    .context("reset_profile task failed")??;
+        let mut f = tokio::fs::File::create(&abs_path)
+            .await
+            .with_context(|| format!("failed to create {}", abs_path.display()))?;
 
-    Ok(Json(resp))
+        let mut bytes: u64 = 0;
+        let mut field = field;
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| AppError::BadRequest(e.to_string()))?
+        {
+            bytes = bytes.saturating_add(chunk.len() as u64);
+            f.write_all(&chunk)
+                .await
+                .with_context(|| format!("failed to write {}", abs_path.display()))?;
+        }
+        f.flush().await.ok();
+
+        let db_path = state.db_path.clone();
+        let rel_path_db = rel_path.clone();
+        let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<ArtifactResponse> {
+            let conn = Connection::open(&db_path)?;
+
+            let artifact = ensure_artifact(&conn, &project_id, "upload", &rel_path_db, created_at_ms)?;
+            conn.execute(
+                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload', ?3)",
+                params![
+                    &project_id,
+                    created_at_ms,
+                    serde_json::json!({ "path": &rel_path_db, "bytes": bytes, "mime": mime_for_event }).to_string()
+                ],
+            )?;
+            Ok(artifact)
+        })
+        .await
+        .context("upload_file_artifact db task failed")??;
+
+        return Ok(Json(UploadFileArtifactResponse {
+            artifact,
+            bytes,
+            file_name,
+            mime,
+        }));
+    }
+
+    Err(AppError::BadRequest("missing multipart field 'file'".to_string()))
+}
+
+fn content_type_for_path(path: &FsPath) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "wav" => "audio/wav",
+        "aac" => "audio/aac",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "json" => "application/json",
+        "txt" | "log" | "md" => "text/plain; charset=utf-8",
+        "html" => "text/html; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Deserialize)]
+struct RawArtifactQuery {
+    /// Serve the artifact's browser-compatible `proxy_video` instead, when one exists.
+    playback: Option<bool>,
+}
+
+/// Strong validator for a file on disk: size plus modification time, like most static file servers.
+fn file_etag(len: u64, modified: SystemTime) -> String {
+    let mtime_ns = modified.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    format!("\"{len:x}-{mtime_ns:x}\"")
+}
+
+/// `If-None-Match` check; `W/` prefixes are ignored as RFC 9110 asks for this weak comparison.
+fn etag_matches(header_value: &str, etag: &str) -> bool {
+    header_value
+        .split(',')
+        .map(|t| t.trim())
+        .any(|t| t == "*" || t.trim_start_matches("W/") == etag)
+}
+
+/// Parses a single `bytes=` range against a file of `len` bytes into an inclusive `(start, end)`.
+///
+/// `Ok(None)` means "ignore the header and send the whole file" (multiple ranges, other units,
+/// garbage); `Err(())` means the range cannot be satisfied (416).
+fn parse_byte_range(value: &str, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return Ok(None);
+    };
+    let (start, end) = (start.trim(), end.trim());
+    if start.is_empty() {
+        // Suffix range: the last N bytes.
+        let Ok(n) = end.parse::<u64>() else {
+            return Ok(None);
+        };
+        if n == 0 || len == 0 {
+            return Err(());
+        }
+        return Ok(Some((len.saturating_sub(n), len - 1)));
+    }
+    let Ok(start) = start.parse::<u64>() else {
+        return Ok(None);
+    };
+    let end = if end.is_empty() {
+        len.saturating_sub(1)
+    } else {
+        match end.parse::<u64>() {
+            Ok(e) if e >= start => e.min(len.saturating_sub(1)),
+            _ => return Ok(None),
+        }
+    };
+    if start >= len {
+        return Err(());
+    }
+    Ok(Some((start, end)))
+}
+
+/// Streams a file with `Accept-Ranges`, `ETag` and `Last-Modified`, answering conditional GETs with
+/// 304 and `Range`/`If-Range` requests with 206 (or 416 when the range is past the end).
+async fn serve_file(headers: &HeaderMap, abs: &FsPath, content_type: &str, disposition: &str) -> AppResult<Response> {
+    let meta = tokio::fs::metadata(abs)
+        .await
+        .with_context(|| format!("failed to stat {}", abs.display()))?;
+    let len = meta.len();
+    let modified = meta.modified().unwrap_or(UNIX_EPOCH);
+    let etag = file_etag(len, modified);
+    let last_modified = httpdate::fmt_http_date(modified);
+
+    let header_str = |name: header::HeaderName| headers.get(name).and_then(|v| v.to_str().ok());
+    let mut validators = HeaderMap::new();
+    validators.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(v) = HeaderValue::from_str(&etag) {
+        validators.insert(header::ETAG, v);
+    }
+    if let Ok(v) = HeaderValue::from_str(&last_modified) {
+        validators.insert(header::LAST_MODIFIED, v);
+    }
+
+    // HTTP dates have second precision, so compare on whole seconds.
+    let modified_s = modified.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let not_modified_since = |value: &str| {
+        httpdate::parse_http_date(value)
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .is_some_and(|t| modified_s <= t.as_secs())
+    };
+    let not_modified = match header_str(header::IF_NONE_MATCH) {
+        Some(v) => etag_matches(v, &etag),
+        None => header_str(header::IF_MODIFIED_SINCE).is_some_and(not_modified_since),
+    };
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, validators).into_response());
+    }
+
+    // `If-Range` only honors the range when the client's copy is still current (strong match).
+    let range_valid = match header_str(header::IF_RANGE) {
+        None => true,
+        Some(v) if v.starts_with('"') => v == etag,
+        Some(v) => httpdate::parse_http_date(v).is_ok_and(|_| v == last_modified),
+    };
+    let range = match header_str(header::RANGE).filter(|_| range_valid) {
+        Some(v) => match parse_byte_range(v, len) {
+            Ok(r) => r,
+            Err(()) => {
+                let mut res = (StatusCode::RANGE_NOT_SATISFIABLE, validators).into_response();
+                if let Ok(v) = HeaderValue::from_str(&format!("bytes */{len}")) {
+                    res.headers_mut().insert(header::CONTENT_RANGE, v);
+                }
+                return Ok(res);
+            }
+        },
+        None => None,
+    };
+
+    let mut file = tokio::fs::File::open(abs)
+        .await
+        .with_context(|| format!("failed to open {}", abs.display()))?;
+    let (status, body_len) = match range {
+        Some((start, end)) => {
+            file.seek(std::io::SeekFrom::Start(start)).await.context("seek failed")?;
+            if let Ok(v) = HeaderValue::from_str(&format!("bytes {start}-{end}/{len}")) {
+                validators.insert(header::CONTENT_RANGE, v);
+            }
+            (StatusCode::PARTIAL_CONTENT, end - start + 1)
+        }
+        None => (StatusCode::OK, len),
+    };
+    let body = Body::from_stream(ReaderStream::new(file.take(body_len)));
+
+    let mut res = Response::new(body);
+    *res.status_mut() = status;
+    res.headers_mut().extend(validators);
+    res.headers_mut().insert(header::CONTENT_LENGTH, HeaderValue::from(body_len));
+    res.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(content_type).unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
+    );
+    res.headers_mut().insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(disposition).unwrap_or_else(|_| HeaderValue::from_static("inline")),
+    );
+    Ok(res)
+}
+
+async fn download_artifact_raw(
+    State(state): State<AppState>,
+    Path((project_id, artifact_id)): Path<(String, String)>,
+    Query(query): Query<RawArtifactQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    if artifact_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing artifact id".to_string()));
+    }
+
+    let playback = query.playback.unwrap_or(false);
+    let db_path = state.db_path.clone();
+    let rel_path = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<String>> {
+        let conn = Connection::open(&db_path)?;
+        if playback {
+            let proxy: Option<String> = conn
+                .query_row(
+                    "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'proxy_video' AND source_artifact_id = ?2\n                     ORDER BY created_at_ms DESC LIMIT 1",
+                    params![&project_id, &artifact_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if proxy.is_some() {
+                return Ok(proxy);
+            }
+        }
+        let path: Option<String> = conn
+            .query_row(
+                "SELECT path FROM artifacts WHERE id = ?1 AND project_id = ?2 LIMIT 1",
+                params![&artifact_id, &project_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(path)
+    })
+    .await
+    .context("download_artifact_raw db task failed")??;
+
+    let Some(rel_path) = rel_path else {
+        return Err(AppError::NotFound("artifact not found".to_string()));
+    };
+    if rel_path.starts_with("http://") || rel_path.starts_with("https://") {
+        return Err(AppError::BadRequest("artifact is not a file".to_string()));
+    }
+
+    let abs = state.data_dir.join(&rel_path);
+    if !abs.exists() {
+        return Err(AppError::NotFound("file not found".to_string()));
+    }
+
+    let file_name = abs
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file");
+    let safe_name = sanitize_file_name(file_name);
+    let disp = format!("inline; filename=\"{}\"", safe_name);
+    serve_file(&headers, &abs, content_type_for_path(&abs), &disp).await
+}
+
+#[derive(Deserialize)]
+struct AddInputUrlRequest {
+    url: String,
+}
+
+async fn add_input_url(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<AddInputUrlRequest>,
+) -> AppResult<Json<ArtifactResponse>> {
+    let url = req.url.trim().to_string();
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    if url.is_empty() {
+        return Err(AppError::BadRequest("missing url".to_string()));
+    }
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(AppError::BadRequest("url must start with http:// or https://".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<ArtifactResponse>> {
+        let conn = Connection::open(&db_path)?;
+
+        let exists: bool =
+            conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+                .optional()?
+                .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        // Dedupe: avoid creating multiple identical input_url artifacts.
+        if let Some((existing_id, existing_created_at_ms)) = conn
+            .query_row(
+                "SELECT id, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'input_url' AND path = ?2 ORDER BY created_at_ms DESC LIMIT 1",
+                params![&project_id, &url],
+                |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
+            )
+            .optional()?
+        {
+            return Ok(Some(ArtifactResponse {
+                id: existing_id,
+                project_id,
+                kind: "input_url".to_string(),
+                path: url,
+                created_at_ms: existing_created_at_ms,
+            }));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let created_at_ms = now_ms();
+        conn.execute(
+            "INSERT INTO artifacts (id, project_id, kind, path, created_at_ms) VALUES (?1, ?2, 'input_url', ?3, ?4)",
+            params![&id, &project_id, &url, created_at_ms],
+        )?;
+
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'input_url_added', ?3)",
+            params![
+                &project_id,
+                created_at_ms,
+                serde_json::json!({ "url": &url }).to_string()
+            ],
+        )?;
+
+        Ok(Some(ArtifactResponse {
+            id,
+            project_id,
+            kind: "input_url".to_string(),
+            path: url,
+            created_at_ms,
+        }))
+    })
+    .await
+    .context("add_input_url task failed")??;
+
+    match artifact {
+        Some(a) => Ok(Json(a)),
+        None => Err(AppError::NotFound("project not found".to_string())),
+    }
+}
+
+const MAX_INPUT_LABEL_CHARS: usize = 200;
+
+/// An `input_video` artifact as seen by multi-video projects.
+#[derive(Serialize, Clone)]
+struct InputVideoResponse {
+    #[serde(flatten)]
+    artifact: ArtifactResponse,
+    label: Option<String>,
+    primary: bool,
+    position: usize,
+}
+
+/// Input videos in their project order: explicit `sort_order` first, then import order. When no
+/// video carries the primary flag, the most recently imported one is reported as primary, which
+/// is what every consumer picked before inputs could be chosen.
+fn list_input_videos(conn: &Connection, project_id: &str) -> anyhow::Result<Vec<InputVideoResponse>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, kind, path, created_at_ms, label, is_primary FROM artifacts\n         WHERE project_id = ?1 AND kind = 'input_video'\n         ORDER BY sort_order IS NULL, sort_order ASC, created_at_ms ASC, id ASC",
+    )?;
+    let rows = stmt.query_map([project_id], |row| {
+        Ok(InputVideoResponse {
+            artifact: ArtifactResponse {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                kind: row.get(2)?,
+                path: row.get(3)?,
+                created_at_ms: row.get(4)?,
+            },
+            label: row.get(5)?,
+            primary: row.get::<_, i64>(6)? != 0,
+            position: 0,
+        })
+    })?;
+    let mut inputs: Vec<InputVideoResponse> = rows.filter_map(Result::ok).collect();
+    for (i, input) in inputs.iter_mut().enumerate() {
+        input.position = i;
+    }
+    if !inputs.is_empty() && !inputs.iter().any(|i| i.primary) {
+        let latest = inputs
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, v)| (v.artifact.created_at_ms, *i))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        inputs[latest].primary = true;
+    }
+    Ok(inputs)
+}
+
+fn set_primary_input(conn: &Connection, project_id: &str, artifact_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "UPDATE artifacts SET is_primary = CASE WHEN id = ?2 THEN 1 ELSE 0 END WHERE project_id = ?1 AND kind = 'input_video'",
+        params![project_id, artifact_id],
+    )?;
+    Ok(())
+}
+
+async fn list_inputs(State(state): State<AppState>, Path(project_id): Path<String>) -> AppResult<Json<Vec<InputVideoResponse>>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let inputs = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Vec<InputVideoResponse>>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+        Ok(Some(list_input_videos(&conn, &project_id)?))
+    })
+    .await
+    .context("list_inputs task failed")??;
+
+    match inputs {
+        Some(i) => Ok(Json(i)),
+        None => Err(AppError::NotFound("project not found".to_string())),
+    }
+}
+
+#[derive(Deserialize)]
+struct ReorderInputsRequest {
+    /// Input video ids in the desired order; ids left out keep their relative order after these.
+    order: Vec<String>,
+    primary: Option<String>,
+}
+
+async fn reorder_inputs(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<ReorderInputsRequest>,
+) -> AppResult<Json<Vec<InputVideoResponse>>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let order: Vec<String> = req.order.iter().map(|s| s.trim().to_string()).collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    if let Some(dup) = order.iter().find(|id| !seen.insert(id.to_string())) {
+        return Err(AppError::BadRequest(format!("duplicate input id: {dup}")));
+    }
+    let primary = req.primary.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<Vec<InputVideoResponse>, AppError>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(Err(AppError::NotFound("project not found".to_string())));
+        }
+
+        let current = list_input_videos(&conn, &project_id)?;
+        let known: HashSet<&str> = current.iter().map(|i| i.artifact.id.as_str()).collect();
+        if let Some(unknown) = order.iter().chain(primary.iter()).find(|id| !known.contains(id.as_str())) {
+            return Ok(Err(AppError::BadRequest(format!("not an input video of this project: {unknown}"))));
+        }
+
+        let mut ids: Vec<&str> = order.iter().map(String::as_str).collect();
+        ids.extend(current.iter().map(|i| i.artifact.id.as_str()).filter(|id| !seen.contains(*id)));
+
+        let tx = conn.unchecked_transaction()?;
+        for (i, id) in ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE artifacts SET sort_order = ?1 WHERE project_id = ?2 AND id = ?3",
+                params![i as i64, &project_id, id],
+            )?;
+        }
+        if let Some(primary) = primary.as_deref() {
+            set_primary_input(&tx, &project_id, primary)?;
+        }
+        tx.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'input_videos_reordered', ?3)",
+            params![
+                &project_id,
+                now_ms(),
+                serde_json::json!({ "order": &ids, "primary": &primary }).to_string()
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(Ok(list_input_videos(&conn, &project_id)?))
+    })
+    .await
+    .context("reorder_inputs task failed")??;
+
+    Ok(Json(result?))
+}
+
+#[derive(Deserialize)]
+struct UpdateInputRequest {
+    /// `null` or an empty string clears the label; leaving the field out keeps it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    label: Option<Option<String>>,
+    /// Only `true` is meaningful: it moves the primary flag to this input.
+    primary: Option<bool>,
+}
+
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+async fn update_input(
+    State(state): State<AppState>,
+    Path((project_id, artifact_id)): Path<(String, String)>,
+    Json(req): Json<UpdateInputRequest>,
+) -> AppResult<Json<InputVideoResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    if req.primary == Some(false) {
+        return Err(AppError::BadRequest(
+            "primary can only be set to true; mark another input as primary instead".to_string(),
+        ));
+    }
+    let label = req
+        .label
+        .map(|l| l.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()));
+    if let Some(Some(l)) = label.as_ref() {
+        if l.chars().count() > MAX_INPUT_LABEL_CHARS {
+            return Err(AppError::BadRequest(format!("label is longer than {MAX_INPUT_LABEL_CHARS} characters")));
+        }
+    }
+    let make_primary = req.primary == Some(true);
+
+    let db_path = state.db_path.clone();
+    let input = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<InputVideoResponse>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM artifacts WHERE project_id = ?1 AND id = ?2 AND kind = 'input_video'",
+                params![&project_id, &artifact_id],
+                |_row| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        if let Some(label) = label.as_ref() {
+            tx.execute(
+                "UPDATE artifacts SET label = ?1 WHERE project_id = ?2 AND id = ?3",
+                params![label, &project_id, &artifact_id],
+            )?;
+        }
+        if make_primary {
+            set_primary_input(&tx, &project_id, &artifact_id)?;
+        }
+        tx.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'input_video_updated', ?3)",
+            params![
+                &project_id,
+                now_ms(),
+                serde_json::json!({ "artifact_id": &artifact_id, "label": &label, "primary": make_primary.then_some(true) })
+                    .to_string()
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(list_input_videos(&conn, &project_id)?
+            .into_iter()
+            .find(|i| i.artifact.id == artifact_id))
+    })
+    .await
+    .context("update_input task failed")??;
+
+    match input {
+        Some(i) => Ok(Json(i)),
+        None => Err(AppError::NotFound("input video not found".to_string())),
+    }
+}
+
+#[derive(Serialize)]
+struct ImportLocalResponse {
+    artifact: ArtifactResponse,
+    bytes: u64,
+    file_name: String,
+    sha256: String,
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    let mut out = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' || ch == '_' {
+            out.push(ch);
+        } else {
+            out.push('_');
+        }
+    }
+    while out.starts_with('.') {
+        out.remove(0);
+    }
+    if out.is_empty() {
+        "video".to_string()
+    } else {
+        out
+    }
+}
+
+/// Inserts the `input_video` artifact row for a fully written file under `media/`, content hash included.
+fn insert_input_video(
+    conn: &Connection,
+    data_dir: &FsPath,
+    project_id: &str,
+    rel_path: &str,
+    content_hash: &str,
+) -> anyhow::Result<ArtifactResponse> {
+    let stat = file_stat_key(&data_dir.join(rel_path))?;
+    let id = Uuid::new_v4().to_string();
+    let created_at_ms = now_ms();
+
+    conn.execute(
+        "INSERT INTO artifacts (id, project_id, kind, path, created_at_ms, content_hash, content_hash_stat)\n         VALUES (?1, ?2, 'input_video', ?3, ?4, ?5, ?6)",
+        params![&id, project_id, rel_path, created_at_ms, content_hash, &stat],
+    )?;
+
+    Ok(ArtifactResponse {
+        id,
+        project_id: project_id.to_string(),
+        kind: "input_video".to_string(),
+        path: rel_path.to_string(),
+        created_at_ms,
+    })
+}
+
+/// `insert_input_video` plus the `input_video_imported` event of a single-file import.
+fn register_input_video(
+    conn: &Connection,
+    data_dir: &FsPath,
+    project_id: &str,
+    rel_path: &str,
+    bytes: u64,
+    content_hash: &str,
+) -> anyhow::Result<ArtifactResponse> {
+    let artifact = insert_input_video(conn, data_dir, project_id, rel_path, content_hash)?;
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'input_video_imported', ?3)",
+        params![
+            project_id,
+            artifact.created_at_ms,
+            serde_json::json!({ "path": rel_path, "bytes": bytes, "sha256": content_hash }).to_string()
+        ],
+    )?;
+    Ok(artifact)
+}
+
+/// Imports the first `file` field only; `POST /projects/{id}/media/local/batch` takes many.
+async fn import_local_video(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    mut multipart: Multipart,
+) -> AppResult<Json<ImportLocalResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    // Ensure project exists first.
+    let project_id_for_check = project_id.clone();
+    let db_path = state.db_path.clone();
+    let exists = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
+        let conn = Connection::open(&db_path)?;
+        Ok(conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_for_check], |_row| Ok(()))
+            .optional()?
+            .is_some())
+    })
+    .await
+    .context("project existence check failed")??;
+    if !exists {
+        return Err(AppError::NotFound("project not found".to_string()));
+    }
+
+    while let Some(mut field) = multipart.next_field().await.context("multipart read failed")? {
+        let field_name = field.name().unwrap_or("");
+        if !field_name.is_empty() && field_name != "file" {
+            continue;
+        }
+
+        let original_name = field.file_name().unwrap_or("video");
+        let sanitized = sanitize_file_name(original_name);
+        let file_name = format!("{}_{}", Uuid::new_v4(), sanitized);
+        let rel_path = format!("projects/{}/media/{}", project_id, file_name);
+        let abs_path = state.data_dir.join(&rel_path);
+
+        if let Some(parent) = abs_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("failed to create dir {}", parent.display()))?;
+        }
+
+        let mut out = tokio::fs::File::create(&abs_path)
+            .await
+            .with_context(|| format!("failed to create file {}", abs_path.display()))?;
+
+        let mut bytes: u64 = 0;
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = field.chunk().await.context("multipart chunk read failed")? {
+            out.write_all(&chunk).await.context("write failed")?;
+            hasher.update(&chunk);
+            bytes = bytes.saturating_add(chunk.len() as u64);
+        }
+        out.flush().await.context("flush failed")?;
+        drop(out);
+        let content_hash = hex_lower(&hasher.finalize());
+        let content_hash_resp = content_hash.clone();
+
+        let db_path = state.db_path.clone();
+        let data_dir = state.data_dir.clone();
+        let artifact = tokio::task::spawn_blocking(move || -> anyhow::Result<ArtifactResponse> {
+            let conn = Connection::open(&db_path)?;
+            register_input_video(&conn, &data_dir, &project_id, &rel_path, bytes, &content_hash)
+        })
+        .await
+        .context("import_local_video db task failed")??;
+
+        return Ok(Json(ImportLocalResponse {
+            artifact,
+            bytes,
+            file_name,
+            sha256: content_hash_resp,
+        }));
+    }
+
+    Err(AppError::BadRequest("missing multipart field 'file'".to_string()))
+}
+
+const MAX_BATCH_FILES: usize = 200;
+const DEFAULT_BATCH_GLOB: &str = "*.{mp4,mov,m4v,mkv,webm,avi,flv,ts}";
+
+/// Outcome for one file of a batch import; exactly one of `artifact`/`error` is set.
+#[derive(Serialize)]
+struct BatchImportItem {
+    /// Client file name (multipart) or path relative to the imported directory.
+    source: String,
+    ok: bool,
+    artifact: Option<ArtifactResponse>,
+    bytes: Option<u64>,
+    sha256: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BatchImportResponse {
+    imported: usize,
+    failed: usize,
+    /// One entry per file, in the order the files were sent or matched.
+    items: Vec<BatchImportItem>,
+}
+
+impl RunArtifacts for BatchImportResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        self.items.iter().filter_map(|i| i.artifact.clone()).collect()
+    }
+}
+
+/// A file already copied into `media/`, waiting for its artifact row.
+struct StagedImport {
+    rel_path: String,
+    bytes: u64,
+    sha256: String,
+}
+
+/// Registers the staged files as `input_video` artifacts in one transaction and logs a single
+/// `input_videos_batch_imported` event covering successes and failures. `files` pairs each source
+/// with its outcome, in input order.
+fn finish_batch_import(
+    conn: &Connection,
+    data_dir: &FsPath,
+    project_id: &str,
+    files: Vec<(String, Result<StagedImport, String>)>,
+    origin: serde_json::Value,
+) -> anyhow::Result<BatchImportResponse> {
+    let tx = conn.unchecked_transaction()?;
+    let mut items: Vec<BatchImportItem> = Vec::with_capacity(files.len());
+    for (source, outcome) in files {
+        items.push(match outcome {
+            Ok(file) => BatchImportItem {
+                source,
+                ok: true,
+                artifact: Some(insert_input_video(&tx, data_dir, project_id, &file.rel_path, &file.sha256)?),
+                bytes: Some(file.bytes),
+                sha256: Some(file.sha256),
+                error: None,
+            },
+            Err(error) => BatchImportItem {
+                source,
+                ok: false,
+                artifact: None,
+                bytes: None,
+                sha256: None,
+                error: Some(error),
+            },
+        });
+    }
+    let imported = items.iter().filter(|i| i.ok).count();
+    let failed = items.len() - imported;
+
+    let files: Vec<serde_json::Value> = items
+        .iter()
+        .map(|i| {
+            serde_json::json!({
+                "source": &i.source,
+                "artifact_id": i.artifact.as_ref().map(|a| &a.id),
+                "path": i.artifact.as_ref().map(|a| &a.path),
+                "bytes": i.bytes,
+                "sha256": &i.sha256,
+                "error": &i.error,
+            })
+        })
+        .collect();
+    tx.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, ?3, 'input_videos_batch_imported', ?4)",
+        params![
+            project_id,
+            now_ms(),
+            if failed > 0 { "warn" } else { "info" },
+            serde_json::json!({ "origin": origin, "imported": imported, "failed": failed, "files": files }).to_string()
+        ],
+    )?;
+    tx.commit()?;
+
+    Ok(BatchImportResponse { imported, failed, items })
+}
+
+/// `POST /projects/{id}/media/local/batch`: every `file` field of the multipart body becomes its
+/// own `input_video`. A file that fails to write is reported and the rest still import; a broken
+/// multipart stream ends the batch with the files read so far.
+async fn import_local_videos_batch(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    mut multipart: Multipart,
+) -> AppResult<Json<BatchImportResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let project_id_for_check = project_id.clone();
+    let db_path = state.db_path.clone();
+    let exists = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
+        let conn = Connection::open(&db_path)?;
+        Ok(conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_for_check], |_row| Ok(()))
+            .optional()?
+            .is_some())
+    })
+    .await
+    .context("project existence check failed")??;
+    if !exists {
+        return Err(AppError::NotFound("project not found".to_string()));
+    }
+
+    let media_dir = state.data_dir.join(format!("projects/{project_id}/media"));
+    tokio::fs::create_dir_all(&media_dir)
+        .await
+        .with_context(|| format!("failed to create dir {}", media_dir.display()))?;
+
+    let mut files: Vec<(String, Result<StagedImport, String>)> = Vec::new();
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => {
+                files.push(("(multipart)".to_string(), Err(format!("multipart read failed: {e}"))));
+                break;
+            }
+        };
+        if field.name().unwrap_or("") != "file" {
+            continue;
+        }
+        let source = field.file_name().unwrap_or("video").to_string();
+        if files.len() >= MAX_BATCH_FILES {
+            files.push((source, Err(format!("batch limit of {MAX_BATCH_FILES} files reached"))));
+            continue;
+        }
+
+        let file_name = format!("{}_{}", Uuid::new_v4(), sanitize_file_name(&source));
+        let rel_path = format!("projects/{project_id}/media/{file_name}");
+        let abs_path = state.data_dir.join(&rel_path);
+        let partial = PartialOutput::new(&abs_path);
+        let mut out = match tokio::fs::File::create(&abs_path).await {
+            Ok(f) => f,
+            Err(e) => {
+                files.push((source, Err(format!("failed to create file: {e}"))));
+                continue;
+            }
+        };
+
+        let mut bytes: u64 = 0;
+        let mut hasher = Sha256::new();
+        let mut error: Option<String> = None;
+        let mut stream_broken = false;
+        loop {
+            match field.chunk().await {
+                Ok(Some(chunk)) => {
+                    if error.is_none() {
+                        if let Err(e) = out.write_all(&chunk).await {
+                            error = Some(format!("write failed: {e}"));
+                        }
+                    }
+                    hasher.update(&chunk);
+                    bytes = bytes.saturating_add(chunk.len() as u64);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    error = Some(format!("upload interrupted: {e}"));
+                    stream_broken = true;
+                    break;
+                }
+            }
+        }
+        if error.is_none() {
+            if let Err(e) = out.flush().await {
+                error = Some(format!("flush failed: {e}"));
+            }
+        }
+        drop(out);
+
+        let outcome = match error {
+            Some(e) => Err(e),
+            None => {
+                partial.keep();
+                Ok(StagedImport {
+                    rel_path,
+                    bytes,
+                    sha256: hex_lower(&hasher.finalize()),
+                })
+            }
+        };
+        files.push((source, outcome));
+        if stream_broken {
+            break;
+        }
+    }
+
+    if files.is_empty() {
+        return Err(AppError::BadRequest("missing multipart field 'file'".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<BatchImportResponse> {
+        let conn = Connection::open(&db_path)?;
+        finish_batch_import(
+            &conn,
+            &data_dir,
+            &project_id,
+            files,
+            serde_json::json!({ "kind": "multipart" }),
+        )
+    })
+    .await
+    .context("import_local_videos_batch db task failed")??;
+
+    Ok(Json(resp))
+}
+
+#[derive(Deserialize)]
+struct ImportDirectoryRequest {
+    /// Absolute directory on the toolserver's machine.
+    dir: String,
+    /// Matched against paths relative to `dir` (`/`-separated): `*` and `?` stay within one path
+    /// segment, `**` spans any number of them, `{a,b}` lists alternatives.
+    /// Default `*.{mp4,mov,m4v,mkv,webm,avi,flv,ts}`.
+    glob: Option<String>,
+    /// Descend into subdirectories (implied by a `**` or `/` in the pattern). A pattern without `/`
+    /// is then matched against file names at any depth.
+    recursive: Option<bool>,
+    wait: Option<bool>,
+}
+
+/// Upper bound on the patterns a glob expands to, so `{a,b}{c,d}...` cannot blow up.
+const MAX_GLOB_PATTERNS: usize = 256;
+
+/// Expands `{a,b}` alternatives into brace-free patterns. Every group is expanded, including
+/// several in a row (`{a,b}/*.{mp4,mkv}`) and nested ones (`*.{mp4,{mkv,webm}}`).
+fn expand_braces(pattern: &str) -> Result<Vec<String>, String> {
+    let Some(open) = pattern.find('{') else {
+        if pattern.contains('}') {
+            return Err(format!("unbalanced '}}' in glob {pattern}"));
+        }
+        return Ok(vec![pattern.to_string()]);
+    };
+    if pattern[..open].contains('}') {
+        return Err(format!("unbalanced '}}' in glob {pattern}"));
+    }
+
+    // Split the first group on its top-level commas.
+    let mut alts: Vec<&str> = Vec::new();
+    let mut close: Option<usize> = None;
+    let mut depth = 0usize;
+    let mut alt_start = open + 1;
+    for (i, c) in pattern[open..].char_indices().map(|(i, c)| (open + i, c)) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    alts.push(&pattern[alt_start..i]);
+                    close = Some(i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => {
+                alts.push(&pattern[alt_start..i]);
+                alt_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return Err(format!("unbalanced '{{' in glob {pattern}"));
+    };
+
+    let (head, rest) = (&pattern[..open], &pattern[close + 1..]);
+    let mut out: Vec<String> = Vec::new();
+    for alt in alts {
+        out.extend(expand_braces(&format!("{head}{alt}{rest}"))?);
+        if out.len() > MAX_GLOB_PATTERNS {
+            return Err(format!("glob expands to more than {MAX_GLOB_PATTERNS} patterns"));
+        }
+    }
+    Ok(out)
+}
+
+/// Wildcard match of a brace-free pattern against a `/`-separated relative path.
+fn glob_match(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((b'*', rest)) if rest.first() == Some(&b'*') => {
+            // `**/` also matches zero directories.
+            let rest = &rest[1..];
+            let after_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=path.len()).any(|i| glob_match(rest, &path[i..]) || glob_match(after_slash, &path[i..]))
+        }
+        Some((b'*', rest)) => (0..=path.len())
+            .take_while(|i| !path[..*i].contains(&b'/'))
+            .any(|i| glob_match(rest, &path[i..])),
+        Some((b'?', rest)) => path.first().is_some_and(|c| *c != b'/') && glob_match(rest, &path[1..]),
+        Some((c, rest)) => path.first().is_some_and(|p| p.eq_ignore_ascii_case(c)) && glob_match(rest, &path[1..]),
+    }
+}
+
+/// Files under `dir` whose relative path matches one of `patterns`, sorted by path.
+fn collect_glob_files(dir: &FsPath, patterns: &[String], recursive: bool) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    let mut stack: Vec<PathBuf> = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        for entry in std::fs::read_dir(&current).with_context(|| format!("failed to read {}", current.display()))? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if recursive {
+                    stack.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            let rel = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let name = rel.rsplit('/').next().unwrap_or(&rel);
+            // Slash-free patterns match the file name at any depth, like `find -name`.
+            let matched = patterns.iter().any(|p| {
+                let target = if recursive && !p.contains('/') { name } else { rel.as_str() };
+                glob_match(p.as_bytes(), target.as_bytes())
+            });
+            if matched {
+                out.push((rel, path));
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// `POST /projects/{id}/media/local/dir`: copies every file under a server-side directory that
+/// matches `glob` into `media/`, one `input_video` each.
+async fn import_local_directory(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<ImportDirectoryRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let dir = PathBuf::from(req.dir.trim());
+    if !dir.is_absolute() {
+        return Err(AppError::BadRequest("dir must be an absolute path".to_string()));
+    }
+    let glob = req
+        .glob
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_BATCH_GLOB.to_string());
+    let recursive = req.recursive.unwrap_or(false) || glob.contains('/') || glob.contains("**");
+    let patterns = expand_braces(&glob).map_err(AppError::BadRequest)?;
+
+    let dir_for_scan = dir.clone();
+    let files = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<Vec<(String, PathBuf)>, AppError>> {
+        if !dir_for_scan.is_dir() {
+            return Ok(Err(AppError::BadRequest(format!("not a directory: {}", dir_for_scan.display()))));
+        }
+        Ok(Ok(collect_glob_files(&dir_for_scan, &patterns, recursive)?))
+    })
+    .await
+    .context("directory scan task failed")??;
+    let files = files?;
+    if files.is_empty() {
+        return Err(AppError::BadRequest(format!("no files in {} match {glob}", dir.display())));
+    }
+    if files.len() > MAX_BATCH_FILES {
+        return Err(AppError::BadRequest(format!(
+            "{} files match; at most {MAX_BATCH_FILES} per batch",
+            files.len()
+        )));
+    }
+
+    let wait = req.wait.unwrap_or(false);
+    let origin = serde_json::json!({ "kind": "directory", "dir": dir.display().to_string(), "glob": &glob, "recursive": recursive });
+    let request_json = origin.clone();
+
+    let data_dir = state.data_dir.clone();
+    dispatch_run(&state, &project_id, "batch_import", request_json, wait, move |job| {
+        Ok(run_directory_import(job, &data_dir, files, origin)?)
+    })
+    .await
+}
+
+fn run_directory_import(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    files: Vec<(String, PathBuf)>,
+    origin: serde_json::Value,
+) -> anyhow::Result<BatchImportResponse> {
+    let project_id = job.project_id.as_str();
+    let media_dir_rel = format!("projects/{project_id}/media");
+    std::fs::create_dir_all(data_dir.join(&media_dir_rel))?;
+
+    let total_bytes: u64 = files.iter().filter_map(|(_, p)| std::fs::metadata(p).ok()).map(|m| m.len()).sum();
+    let started = Instant::now();
+    let mut copied: u64 = 0;
+    let mut outcomes: Vec<(String, Result<StagedImport, String>)> = Vec::with_capacity(files.len());
+    for (source, src_abs) in files {
+        let file_name = src_abs.file_name().and_then(|s| s.to_str()).unwrap_or("video");
+        let rel_path = format!("{media_dir_rel}/{}_{}", Uuid::new_v4(), sanitize_file_name(file_name));
+        let dst_abs = data_dir.join(&rel_path);
+        let partial = PartialOutput::new(&dst_abs);
+
+        // Copy and hash in one pass; a cancel removes the half-copied file via `partial`.
+        let result = (|| -> anyhow::Result<(u64, String)> {
+            let mut src = std::fs::File::open(&src_abs)?;
+            let mut dst = std::fs::File::create(&dst_abs)?;
+            let mut hasher = Sha256::new();
+            let mut buf = vec![0u8; 1024 * 1024];
+            let mut bytes: u64 = 0;
+            loop {
+                job.cancel.check()?;
+                let n = src.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                dst.write_all(&buf[..n])?;
+                hasher.update(&buf[..n]);
+                bytes += n as u64;
+                copied += n as u64;
+                let fraction = if total_bytes > 0 { copied as f64 / total_bytes as f64 } else { 0.0 };
+                job.report(
+                    "batch_import",
+                    Progress {
+                        percent: (total_bytes > 0).then_some(fraction * 100.0),
+                        eta_s: eta_from_fraction(started, fraction),
+                        bytes: Some(copied),
+                        total_bytes: Some(total_bytes),
+                    },
+                );
+            }
+            dst.flush()?;
+            Ok((bytes, hex_lower(&hasher.finalize())))
+        })();
+
+        let outcome = match result {
+            Ok((bytes, sha256)) => {
+                partial.keep();
+                Ok(StagedImport { rel_path, bytes, sha256 })
+            }
+            Err(e) if job.cancel.is_cancelled() => return Err(e),
+            Err(e) => Err(format!("{e:#}")),
+        };
+        outcomes.push((source, outcome));
+    }
+
+    finish_batch_import(&job.conn, data_dir, project_id, outcomes, origin)
+}
+
+/// Sessions not touched for this long are dropped together with their partial file.
+const UPLOAD_SESSION_TTL_MS: i64 = 24 * 60 * 60 * 1000;
+const UPLOAD_GC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Request/response header carrying the byte offset of a chunk (same name as tus.io).
+const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+
+#[derive(Deserialize)]
+struct CreateUploadRequest {
+    file_name: String,
+    total_bytes: u64,
+    /// `input_video` (default) is promoted to `media/` like `POST /media/local`; `upload` goes to
+    /// `uploads/` like `POST /artifacts/upload`.
+    kind: Option<String>,
+    mime: Option<String>,
+}
+
+#[derive(Serialize)]
+struct UploadSessionResponse {
+    id: String,
+    project_id: String,
+    kind: String,
+    file_name: String,
+    mime: Option<String>,
+    total_bytes: u64,
+    /// Bytes received so far; the next chunk must start here.
+    offset: u64,
+    created_at_ms: i64,
+    updated_at_ms: i64,
+    expires_at_ms: i64,
+}
+
+#[derive(Serialize)]
+struct CompleteUploadResponse {
+    artifact: ArtifactResponse,
+    bytes: u64,
+    file_name: String,
+    mime: Option<String>,
+    sha256: String,
+}
+
+fn upload_part_rel(project_id: &str, upload_id: &str) -> String {
+    format!("projects/{project_id}/tmp/upload_{upload_id}.part")
+}
+
+/// Loads a session; its offset is the size of the partial file, so bytes that reached the disk
+/// before a dropped connection are never re-requested.
+fn load_upload_session(
+    conn: &Connection,
+    data_dir: &FsPath,
+    project_id: &str,
+    upload_id: &str,
+) -> anyhow::Result<Option<UploadSessionResponse>> {
+    let Some(mut session) = conn
+        .query_row(
+            "SELECT id, project_id, kind, file_name, mime, total_bytes, created_at_ms, updated_at_ms\n             FROM upload_sessions WHERE id = ?1 AND project_id = ?2",
+            params![upload_id, project_id],
+            |r| {
+                let updated_at_ms: i64 = r.get(7)?;
+                Ok(UploadSessionResponse {
+                    id: r.get(0)?,
+                    project_id: r.get(1)?,
+                    kind: r.get(2)?,
+                    file_name: r.get(3)?,
+                    mime: r.get(4)?,
+                    total_bytes: r.get::<_, i64>(5)? as u64,
+                    offset: 0,
+                    created_at_ms: r.get(6)?,
+                    updated_at_ms,
+                    expires_at_ms: updated_at_ms + UPLOAD_SESSION_TTL_MS,
+                })
+            },
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    session.offset = std::fs::metadata(data_dir.join(upload_part_rel(project_id, upload_id)))
+        .map(|m| m.len())
+        .unwrap_or(0);
+    Ok(Some(session))
+}
+
+/// Marks a session as being written so two concurrent chunks cannot interleave in one file.
+struct UploadWriteGuard {
+    active: Arc<Mutex<HashSet<String>>>,
+    upload_id: String,
+}
+
+impl UploadWriteGuard {
+    fn acquire(active: &Arc<Mutex<HashSet<String>>>, upload_id: &str) -> Option<Self> {
+        let mut set = active.lock().unwrap_or_else(|e| e.into_inner());
+        set.insert(upload_id.to_string()).then(|| Self {
+            active: active.clone(),
+            upload_id: upload_id.to_string(),
+        })
+    }
+}
+
+impl Drop for UploadWriteGuard {
+    fn drop(&mut self) {
+        let mut set = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        set.remove(&self.upload_id);
+    }
+}
+
+async fn create_upload(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<CreateUploadRequest>,
+) -> AppResult<Json<UploadSessionResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let kind = req.kind.as_deref().map(str::trim).unwrap_or("input_video").to_string();
+    if kind != "input_video" && kind != "upload" {
+        return Err(AppError::BadRequest("kind must be input_video or upload".to_string()));
+    }
+    let file_name = sanitize_file_name(req.file_name.trim());
+    if file_name.trim_matches('_').is_empty() {
+        return Err(AppError::BadRequest("missing file_name".to_string()));
+    }
+    if req.total_bytes == 0 || req.total_bytes > i64::MAX as u64 {
+        return Err(AppError::BadRequest("total_bytes must be > 0".to_string()));
+    }
+    let mime = req.mime.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let session = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<UploadSessionResponse>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let created_at_ms = now_ms();
+        let part_abs = data_dir.join(upload_part_rel(&project_id, &id));
+        if let Some(parent) = part_abs.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::File::create(&part_abs).with_context(|| format!("failed to create {}", part_abs.display()))?;
+
+        conn.execute(
+            "INSERT INTO upload_sessions (id, project_id, kind, file_name, mime, total_bytes, created_at_ms, updated_at_ms)\n             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![&id, &project_id, &kind, &file_name, mime.as_deref(), req.total_bytes as i64, created_at_ms],
+        )?;
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload_started', ?3)",
+            params![
+                &project_id,
+                created_at_ms,
+                serde_json::json!({ "upload_id": &id, "kind": &kind, "file_name": &file_name, "total_bytes": req.total_bytes })
+                    .to_string()
+            ],
+        )?;
+
+        load_upload_session(&conn, &data_dir, &project_id, &id)
+    })
+    .await
+    .context("create_upload task failed")??;
+
+    match session {
+        Some(v) => Ok(Json(v)),
+        None => Err(AppError::NotFound("project not found".to_string())),
+    }
+}
+
+async fn get_upload(
+    State(state): State<AppState>,
+    Path((project_id, upload_id)): Path<(String, String)>,
+) -> AppResult<Response> {
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let session = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<UploadSessionResponse>> {
+        let conn = Connection::open(&db_path)?;
+        load_upload_session(&conn, &data_dir, &project_id, &upload_id)
+    })
+    .await
+    .context("get_upload task failed")??;
+
+    match session {
+        Some(v) => Ok(([(UPLOAD_OFFSET_HEADER, v.offset.to_string())], Json(v)).into_response()),
+        None => Err(AppError::NotFound("upload not found".to_string())),
+    }
+}
+
+/// Appends the request body at `Upload-Offset`. Whatever reaches the disk before the connection
+/// drops is kept; the client asks `GET` for the offset and continues from there.
+async fn append_upload_chunk(
+    State(state): State<AppState>,
+    Path((project_id, upload_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Body,
+) -> AppResult<Response> {
+    let offset: u64 = headers
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .ok_or_else(|| AppError::BadRequest("missing or invalid Upload-Offset header".to_string()))?;
+
+    // Hold the guard before reading the offset so a concurrent chunk cannot grow the file between
+    // the check and the write.
+    let Some(_guard) = UploadWriteGuard::acquire(&state.active_uploads, &upload_id) else {
+        return Err(AppError::Conflict("another chunk for this upload is still being written".to_string()));
+    };
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let (pid, uid) = (project_id.clone(), upload_id.clone());
+    let session = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<UploadSessionResponse>> {
+        let conn = Connection::open(&db_path)?;
+        load_upload_session(&conn, &data_dir, &pid, &uid)
+    })
+    .await
+    .context("append_upload_chunk db task failed")??;
+    let Some(session) = session else {
+        return Err(AppError::NotFound("upload not found".to_string()));
+    };
+
+    let part_abs = state.data_dir.join(upload_part_rel(&project_id, &upload_id));
+    let mut out = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(&part_abs)
+        .await
+        .with_context(|| format!("failed to open {}", part_abs.display()))?;
+    let part_len = out
+        .metadata()
+        .await
+        .with_context(|| format!("failed to stat {}", part_abs.display()))?
+        .len();
+    if offset != session.offset || offset != part_len {
+        return Err(AppError::Conflict(format!(
+            "offset mismatch: upload has {part_len} bytes, chunk starts at {offset}"
+        )));
+    }
+    out.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .with_context(|| format!("failed to seek {}", part_abs.display()))?;
+
+    let mut written: u64 = 0;
+    let mut stream = body.into_data_stream();
+    let mut failure: Option<AppError> = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(c) => c,
+            Err(e) => {
+                failure = Some(AppError::BadRequest(format!("upload interrupted: {e}")));
+                break;
+            }
+        };
+        if session.offset + written + chunk.len() as u64 > session.total_bytes {
+            failure = Some(AppError::BadRequest("chunk goes past total_bytes".to_string()));
+            break;
+        }
+        out.write_all(&chunk).await.context("write failed")?;
+        written += chunk.len() as u64;
+    }
+    out.flush().await.context("flush failed")?;
+    drop(out);
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let session = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<UploadSessionResponse>> {
+        let conn = Connection::open(&db_path)?;
+        conn.execute(
+            "UPDATE upload_sessions SET updated_at_ms = ?1 WHERE id = ?2 AND project_id = ?3",
+            params![now_ms(), &upload_id, &project_id],
+        )?;
+        load_upload_session(&conn, &data_dir, &project_id, &upload_id)
+    })
+    .await
+    .context("append_upload_chunk db task failed")??;
+
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    match session {
+        Some(v) => Ok(([(UPLOAD_OFFSET_HEADER, v.offset.to_string())], Json(v)).into_response()),
+        None => Err(AppError::NotFound("upload not found".to_string())),
+    }
+}
+
+/// Hashes the finished partial file and moves it out of `tmp/` into the project, registering it
+/// exactly like a single-request upload of the same kind.
+async fn complete_upload(
+    State(state): State<AppState>,
+    Path((project_id, upload_id)): Path<(String, String)>,
+) -> AppResult<Json<CompleteUploadResponse>> {
+    let Some(_guard) = UploadWriteGuard::acquire(&state.active_uploads, &upload_id) else {
+        return Err(AppError::Conflict("a chunk for this upload is still being written".to_string()));
+    };
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<CompleteUploadResponse, AppError>> {
+        let conn = Connection::open(&db_path)?;
+        let Some(session) = load_upload_session(&conn, &data_dir, &project_id, &upload_id)? else {
+            return Ok(Err(AppError::NotFound("upload not found".to_string())));
+        };
+        if session.offset != session.total_bytes {
+            return Ok(Err(AppError::Conflict(format!(
+                "upload incomplete: {} of {} bytes received",
+                session.offset, session.total_bytes
+            ))));
+        }
+
+        let part_abs = data_dir.join(upload_part_rel(&project_id, &upload_id));
+        let content_hash = sha256_file(&part_abs)?;
+        let created_at_ms = now_ms();
+        let rel_path = if session.kind == "input_video" {
+            format!("projects/{}/media/{}_{}", project_id, Uuid::new_v4(), session.file_name)
+        } else {
+            format!("projects/{}/uploads/{}-{}", project_id, created_at_ms, session.file_name)
+        };
+        let abs_path = data_dir.join(&rel_path);
+        if let Some(parent) = abs_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&part_abs, &abs_path)
+            .with_context(|| format!("failed to move upload to {}", abs_path.display()))?;
+
+        let artifact = if session.kind == "input_video" {
+            register_input_video(&conn, &data_dir, &project_id, &rel_path, session.total_bytes, &content_hash)?
+        } else {
+            let artifact = ensure_artifact(&conn, &project_id, "upload", &rel_path, created_at_ms)?;
+            store_content_hash(&conn, &artifact.id, &content_hash, &file_stat_key(&abs_path)?)?;
+            conn.execute(
+                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload', ?3)",
+                params![
+                    &project_id,
+                    created_at_ms,
+                    serde_json::json!({ "path": &rel_path, "bytes": session.total_bytes, "mime": &session.mime }).to_string()
+                ],
+            )?;
+            artifact
+        };
+
+        conn.execute("DELETE FROM upload_sessions WHERE id = ?1", [&upload_id])?;
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload_completed', ?3)",
+            params![
+                &project_id,
+                created_at_ms,
+                serde_json::json!({ "upload_id": &upload_id, "artifact_id": &artifact.id, "bytes": session.total_bytes }).to_string()
+            ],
+        )?;
+
+        Ok(Ok(CompleteUploadResponse {
+            artifact,
+            bytes: session.total_bytes,
+            file_name: session.file_name,
+            mime: session.mime,
+            sha256: content_hash,
+        }))
+    })
+    .await
+    .context("complete_upload task failed")???;
+
+    Ok(Json(resp))
+}
+
+async fn abort_upload(
+    State(state): State<AppState>,
+    Path((project_id, upload_id)): Path<(String, String)>,
+) -> AppResult<Json<serde_json::Value>> {
+    let Some(_guard) = UploadWriteGuard::acquire(&state.active_uploads, &upload_id) else {
+        return Err(AppError::Conflict("a chunk for this upload is still being written".to_string()));
+    };
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let removed = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
+        let conn = Connection::open(&db_path)?;
+        let removed = conn.execute(
+            "DELETE FROM upload_sessions WHERE id = ?1 AND project_id = ?2",
+            params![&upload_id, &project_id],
+        )? > 0;
+        if removed {
+            let _ = std::fs::remove_file(data_dir.join(upload_part_rel(&project_id, &upload_id)));
+            conn.execute(
+                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload_aborted', ?3)",
+                params![&project_id, now_ms(), serde_json::json!({ "upload_id": &upload_id }).to_string()],
+            )?;
+        }
+        Ok(removed)
+    })
+    .await
+    .context("abort_upload task failed")??;
+
+    if !removed {
+        return Err(AppError::NotFound("upload not found".to_string()));
+    }
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+/// Drops upload sessions idle for longer than `UPLOAD_SESSION_TTL_MS`, plus `tmp/upload_*.part`
+/// files whose session row is gone (e.g. the database was restored from a backup).
+fn gc_upload_sessions(conn: &Connection, data_dir: &FsPath) -> anyhow::Result<usize> {
+    let cutoff = now_ms() - UPLOAD_SESSION_TTL_MS;
+    let stale: Vec<(String, String, i64)> = {
+        let mut stmt = conn.prepare("SELECT id, project_id, updated_at_ms FROM upload_sessions WHERE updated_at_ms < ?1")?;
+        let rows = stmt.query_map([cutoff], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+        rows.filter_map(Result::ok).collect()
+    };
+    for (id, project_id, updated_at_ms) in &stale {
+        let _ = std::fs::remove_file(data_dir.join(upload_part_rel(project_id, id)));
+        conn.execute("DELETE FROM upload_sessions WHERE id = ?1", [id])?;
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'upload_expired', ?3)",
+            params![
+                project_id,
+                now_ms(),
+                serde_json::json!({ "upload_id": id, "last_activity_ms": updated_at_ms }).to_string()
+            ],
+        )?;
+    }
+
+    let mut orphans = 0;
+    let cutoff_time = UNIX_EPOCH + Duration::from_millis(cutoff.max(0) as u64);
+    let Ok(projects) = std::fs::read_dir(data_dir.join("projects")) else {
+        return Ok(stale.len());
+    };
+    for project in projects.flatten() {
+        let Ok(entries) = std::fs::read_dir(project.path().join("tmp")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(upload_id) = name.strip_prefix("upload_").and_then(|s| s.strip_suffix(".part")) else {
+                continue;
+            };
+            let old = entry.metadata().and_then(|m| m.modified()).is_ok_and(|t| t < cutoff_time);
+            let known: bool = conn
+                .query_row("SELECT 1 FROM upload_sessions WHERE id = ?1", [upload_id], |_row| Ok(()))
+                .optional()?
+                .is_some();
+            if old && !known && std::fs::remove_file(entry.path()).is_ok() {
+                orphans += 1;
+            }
+        }
+    }
+    Ok(stale.len() + orphans)
+}
+
+async fn run_upload_gc(db_path: PathBuf, data_dir: PathBuf) {
+    loop {
+        let (db, dir) = (db_path.clone(), data_dir.clone());
+        match tokio::task::spawn_blocking(move || gc_upload_sessions(&Connection::open(&db)?, &dir)).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(n)) => tracing::info!("removed {n} stale upload(s)"),
+            Ok(Err(e)) => tracing::warn!("upload gc failed: {e:#}"),
+            Err(e) => tracing::warn!("upload gc task failed: {e}"),
+        }
+        tokio::time::sleep(UPLOAD_GC_INTERVAL).await;
+    }
+}
+
+#[derive(Deserialize)]
+struct ImportRemoteMediaRequest {
+    url: String,
+    download: Option<bool>,
+    cookies_from_browser: Option<String>,
+    /// After downloading, transcode a `proxy_video` if the file does not play in browsers.
+    proxy: Option<bool>,
+    /// Raw yt-dlp format selector (`-f`); mutually exclusive with `preset`.
+    format: Option<String>,
+    preset: Option<RemoteFormatPreset>,
+    /// Also fetch subtitles and danmaku into `timed_text` artifacts (plus a `danmaku_heatmap`).
+    subtitles: Option<bool>,
+    wait: Option<bool>,
+}
+
+const MAX_FORMAT_SELECTOR_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RemoteFormatPreset {
+    /// At most 480p, which is plenty for analysis and much cheaper to download and process.
+    #[serde(rename = "analysis_480p")]
+    Analysis480p,
+    Best,
+    AudioOnly,
+}
+
+impl RemoteFormatPreset {
+    fn selector(self) -> &'static str {
+        match self {
+            Self::Analysis480p => "bv*[height<=480]+ba/b[height<=480]/wv*+ba/w",
+            Self::Best => "bv*+ba/b",
+            Self::AudioOnly => "ba/b",
+        }
+    }
+}
+
+/// Resolves the request's preset or raw selector into the `-f` argument, if any.
+fn remote_format_selector(format: Option<&str>, preset: Option<RemoteFormatPreset>) -> Result<Option<String>, String> {
+    let format = format.map(str::trim).filter(|s| !s.is_empty());
+    match (format, preset) {
+        (Some(_), Some(_)) => Err("use either format or preset, not both".to_string()),
+        (None, Some(p)) => Ok(Some(p.selector().to_string())),
+        (Some(f), None) => {
+            if f.chars().count() > MAX_FORMAT_SELECTOR_CHARS {
+                return Err(format!("format is longer than {MAX_FORMAT_SELECTOR_CHARS} characters"));
+            }
+            if f.starts_with('-') || f.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err("format must be a yt-dlp format selector such as 'bv*+ba/b'".to_string());
+            }
+            Ok(Some(f.to_string()))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// One entry of yt-dlp's `formats`, reduced to what is useful for choosing a download.
+#[derive(Serialize, Clone)]
+struct RemoteFormat {
+    format_id: String,
+    ext: Option<String>,
+    width: Option<u64>,
+    height: Option<u64>,
+    fps: Option<f64>,
+    vcodec: Option<String>,
+    acodec: Option<String>,
+    tbr_kbps: Option<f64>,
+    filesize: Option<u64>,
+    filesize_approx: bool,
+    note: Option<String>,
+    protocol: Option<String>,
+}
+
+impl RemoteFormat {
+    fn from_info(v: &serde_json::Value) -> Option<Self> {
+        let format_id = json_string(v, "format_id")?;
+        let note = json_string(v, "format_note").filter(|s| !s.trim().is_empty());
+        let ext = json_string(v, "ext");
+        // Storyboards are thumbnail mosaics, not downloadable media.
+        if ext.as_deref() == Some("mhtml") || note.as_deref().is_some_and(|n| n.to_ascii_lowercase().contains("storyboard")) {
+            return None;
+        }
+        let codec = |key: &str| json_string(v, key).filter(|c| !c.is_empty() && c != "none");
+        let exact_size = v.get("filesize").and_then(|x| x.as_u64());
+        let approx_size = v.get("filesize_approx").and_then(|x| x.as_u64());
+        Some(Self {
+            format_id,
+            ext,
+            width: v.get("width").and_then(|x| x.as_u64()),
+            height: v.get("height").and_then(|x| x.as_u64()),
+            fps: json_f64(v, "fps"),
+            vcodec: codec("vcodec"),
+            acodec: codec("acodec"),
+            tbr_kbps: json_f64(v, "tbr"),
+            filesize: exact_size.or(approx_size),
+            filesize_approx: exact_size.is_none() && approx_size.is_some(),
+            note,
+            protocol: json_string(v, "protocol"),
+        })
+    }
+}
+
+fn remote_formats(info_json: &serde_json::Value) -> Vec<RemoteFormat> {
+    info_json
+        .get("formats")
+        .and_then(|f| f.as_array())
+        .map(|formats| formats.iter().filter_map(RemoteFormat::from_info).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize)]
+struct RemoteMediaInfoSummary {
+    extractor: String,
+    id: String,
+    title: String,
+    duration_s: Option<f64>,
+    webpage_url: String,
+    thumbnail: Option<String>,
+    description: Option<String>,
+    formats: Vec<RemoteFormat>,
+    /// Format yt-dlp picked for the requested selector (`137+140` when merging streams).
+    selected_format_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ImportRemoteMediaResponse {
+    info: RemoteMediaInfoSummary,
+    info_artifact: ArtifactResponse,
+    input_video: Option<ArtifactResponse>,
+    /// Set instead of `input_video` when the chosen format has no video stream.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_audio: Option<ArtifactResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_video: Option<ArtifactResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timed_text: Option<RemoteTimedText>,
+}
+
+impl RunArtifacts for ImportRemoteMediaResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        let mut out = vec![self.info_artifact.clone()];
+        out.extend(self.input_video.iter().cloned());
+        out.extend(self.input_audio.iter().cloned());
+        out.extend(self.proxy_video.iter().cloned());
+        if let Some(timed_text) = &self.timed_text {
+            out.extend(timed_text.tracks.iter().map(|t| t.artifact.clone()));
+            out.extend(timed_text.danmaku_heatmap.iter().cloned());
+        }
+        out
+    }
+}
+
+enum ImportRemoteMediaOutcome<T = Box<ImportRemoteMediaResponse>> {
+    Ok(T),
+    PreconditionFailed(String),
+}
+
+fn env_trim(key: &str) -> Option<String> {
+    std::env::var(key).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn json_string(v: &serde_json::Value, key: &str) -> Option<String> {
+    v.get(key).and_then(|x| x.as_str()).map(|s| s.to_string())
+}
+
+fn json_f64(v: &serde_json::Value, key: &str) -> Option<f64> {
+    let x = v.get(key)?;
+    if let Some(n) = x.as_f64() {
+        return Some(n);
+    }
+    x.as_str().and_then(|s| s.parse::<f64>().ok())
+}
+
+fn clean_one_line(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ").trim().to_string()
+}
+
+fn is_cookie_db_copy_error(msg: &str) -> bool {
+    let lower = msg.to_ascii_lowercase();
+    lower.contains("could not copy") && lower.contains("cookie database")
+}
+
+fn cookie_db_copy_help(browser: &str) -> String {
+    format!(
+        "yt-dlp failed to read cookies from browser ({browser}).\n\n\
+Common cause on Windows: the browser is running and yt-dlp cannot copy the cookies database.\n\n\
+Fix:\n\
+1) Fully close the browser ({browser}) and retry (including background processes).\n\
+2) Or switch to another browser in Settings (edge/firefox).\n\n\
+Ref: yt-dlp issue 7271"
+    )
+}
+
+const MAX_COOKIES_FILE_BYTES: usize = 1024 * 1024;
+/// Persistent cookies expiring within this window are flagged in the report.
+const COOKIE_EXPIRY_WARN_MS: i64 = 7 * 24 * 3600 * 1000;
+/// Login cookies whose expiry decides whether authenticated downloads keep working.
+const AUTH_COOKIE_NAMES: &[&str] = &[
+    "SESSDATA",
+    "bili_jct",
+    "DedeUserID",
+    "LOGIN_INFO",
+    "SID",
+    "SAPISID",
+    "__Secure-1PSID",
+    "__Secure-3PSID",
+    "sessionid",
+];
+
+/// How yt-dlp authenticates: the uploaded Netscape cookies.txt (see `/ytdlp/cookies`) or a
+/// browser profile to read cookies from.
+enum YtdlpCookies {
+    File(PathBuf),
+    Browser(String),
+}
+
+impl YtdlpCookies {
+    fn apply(&self, cmd: &mut Command) {
+        match self {
+            Self::File(path) => {
+                cmd.arg("--cookies").arg(path);
+            }
+            Self::Browser(browser) => {
+                cmd.args(["--cookies-from-browser", browser]);
+            }
+        }
+    }
+}
+
+/// Kept outside `projects/` so exports and project deletion never touch it.
+fn cookies_file_path(data_dir: &FsPath) -> PathBuf {
+    data_dir.join("secrets").join("ytdlp_cookies.txt")
+}
+
+/// An uploaded cookies.txt wins over `cookies_from_browser`: it exists precisely because reading
+/// browser cookies is unreliable.
+fn ytdlp_cookies(data_dir: &FsPath, requested_browser: Option<&String>) -> Option<YtdlpCookies> {
+    let file = cookies_file_path(data_dir);
+    if file.is_file() {
+        return Some(YtdlpCookies::File(file));
+    }
+    cookies_from_browser_or_env(requested_browser).map(YtdlpCookies::Browser)
+}
+
+struct NetscapeCookie {
+    domain: String,
+    name: String,
+    /// `None` for session cookies.
+    expires_at_ms: Option<i64>,
+}
+
+/// Parses a Netscape cookie file: 7 tab-separated fields per cookie; `#` lines are comments
+/// except the `#HttpOnly_` domain prefix. Errors carry 1-based line numbers.
+fn parse_netscape_cookies(text: &str) -> Result<Vec<NetscapeCookie>, Vec<String>> {
+    let mut cookies: Vec<NetscapeCookie> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim_end_matches('\r');
+        let line = match line.strip_prefix("#HttpOnly_") {
+            Some(rest) => rest,
+            None if line.trim().is_empty() || line.starts_with('#') => continue,
+            None => line,
+        };
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            errors.push(format!("line {}: expected 7 tab-separated fields, found {}", i + 1, fields.len()));
+            continue;
+        }
+        let flag = |v: &str| v.eq_ignore_ascii_case("TRUE") || v.eq_ignore_ascii_case("FALSE");
+        if fields[0].trim().is_empty() || !flag(fields[1]) || !flag(fields[3]) {
+            errors.push(format!("line {}: invalid domain or TRUE/FALSE flag", i + 1));
+            continue;
+        }
+        let Ok(expires) = fields[4].trim().parse::<i64>() else {
+            errors.push(format!("line {}: expiry must be a unix timestamp", i + 1));
+            continue;
+        };
+        cookies.push(NetscapeCookie {
+            domain: fields[0].trim().trim_start_matches('.').to_ascii_lowercase(),
+            name: fields[5].to_string(),
+            expires_at_ms: (expires > 0).then(|| expires.saturating_mul(1000)),
+        });
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    if cookies.is_empty() {
+        return Err(vec!["no cookies found; export cookies.txt in Netscape format".to_string()]);
+    }
+    Ok(cookies)
+}
+
+/// Last two labels of a cookie domain (`www.bilibili.com` -> `bilibili.com`).
+fn cookie_site(domain: &str) -> String {
+    let labels: Vec<&str> = domain.split('.').filter(|l| !l.is_empty()).collect();
+    labels[labels.len().saturating_sub(2)..].join(".")
+}
+
+#[derive(Serialize)]
+struct CookieSiteReport {
+    site: String,
+    cookies: usize,
+    session: usize,
+    expired: usize,
+    /// Earliest expiry among the site's login cookies (or all persistent cookies when none of
+    /// the known login cookies are present).
+    expires_at_ms: Option<i64>,
+    expires_soon: bool,
+    auth_cookies: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CookiesStatusResponse {
+    present: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_at_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cookies: Option<usize>,
+    sites: Vec<CookieSiteReport>,
+    warnings: Vec<String>,
+}
+
+fn cookies_report(cookies: &[NetscapeCookie], updated_at_ms: Option<i64>) -> CookiesStatusResponse {
+    let now = now_ms();
+    let mut by_site: BTreeMap<String, Vec<&NetscapeCookie>> = BTreeMap::new();
+    for c in cookies {
+        by_site.entry(cookie_site(&c.domain)).or_default().push(c);
+    }
+
+    let mut warnings: Vec<String> = Vec::new();
+    let sites: Vec<CookieSiteReport> = by_site
+        .into_iter()
+        .map(|(site, list)| {
+            let auth: Vec<&&NetscapeCookie> = list.iter().filter(|c| AUTH_COOKIE_NAMES.contains(&c.name.as_str())).collect();
+            let relevant: Vec<&&NetscapeCookie> = if auth.is_empty() { list.iter().collect() } else { auth.clone() };
+            let expires_at_ms = relevant.iter().filter_map(|c| c.expires_at_ms).min();
+            let expired = list.iter().filter(|c| c.expires_at_ms.is_some_and(|e| e <= now)).count();
+            let expires_soon = expires_at_ms.is_some_and(|e| e - now < COOKIE_EXPIRY_WARN_MS);
+            if !auth.is_empty() && auth.iter().all(|c| c.expires_at_ms.is_some_and(|e| e <= now)) {
+                warnings.push(format!("{site}: login cookies have expired; export a fresh cookies.txt"));
+            } else if !auth.is_empty() && expires_soon {
+                warnings.push(format!("{site}: login cookies expire within 7 days"));
+            }
+            let mut auth_cookies: Vec<String> = auth.iter().map(|c| c.name.clone()).collect();
+            auth_cookies.sort();
+            auth_cookies.dedup();
+            CookieSiteReport {
+                site,
+                cookies: list.len(),
+                session: list.iter().filter(|c| c.expires_at_ms.is_none()).count(),
+                expired,
+                expires_at_ms,
+                expires_soon,
+                auth_cookies,
+            }
+        })
+        .collect();
+
+    CookiesStatusResponse {
+        present: true,
+        updated_at_ms,
+        cookies: Some(cookies.len()),
+        sites,
+        warnings,
+    }
+}
+
+fn write_private_file(path: &FsPath, bytes: &[u8]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+    // Write next to the target and rename, so a half-written file is never picked up by yt-dlp.
+    let tmp = path.with_extension("tmp");
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    let mut f = opts.open(&tmp).with_context(|| format!("failed to create {}", tmp.display()))?;
+    f.write_all(bytes)?;
+    f.sync_all()?;
+    drop(f);
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn read_cookies_status(path: &FsPath) -> anyhow::Result<CookiesStatusResponse> {
+    if !path.is_file() {
+        return Ok(CookiesStatusResponse {
+            present: false,
+            updated_at_ms: None,
+            cookies: None,
+            sites: Vec::new(),
+            warnings: Vec::new(),
+        });
+    }
+    let updated_at_ms = std::fs::metadata(path)?
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64);
+    let text = std::fs::read_to_string(path)?;
+    // yt-dlp rewrites the jar after each run, so re-validate instead of trusting the upload.
+    match parse_netscape_cookies(&text) {
+        Ok(cookies) => Ok(cookies_report(&cookies, updated_at_ms)),
+        Err(errors) => Ok(CookiesStatusResponse {
+            present: true,
+            updated_at_ms,
+            cookies: None,
+            sites: Vec::new(),
+            warnings: errors,
+        }),
+    }
+}
+
+async fn get_ytdlp_cookies(State(state): State<AppState>) -> AppResult<Json<CookiesStatusResponse>> {
+    let path = cookies_file_path(&state.data_dir);
+    let status = tokio::task::spawn_blocking(move || read_cookies_status(&path))
+        .await
+        .context("get_ytdlp_cookies task failed")??;
+    Ok(Json(status))
+}
+
+/// Stores a Netscape cookies.txt (raw request body) for yt-dlp's `--cookies`.
+async fn upload_ytdlp_cookies(State(state): State<AppState>, body: String) -> AppResult<Json<CookiesStatusResponse>> {
+    if body.len() > MAX_COOKIES_FILE_BYTES {
+        return Err(AppError::BadRequest(format!(
+            "cookies file is larger than {} KiB",
+            MAX_COOKIES_FILE_BYTES / 1024
+        )));
+    }
+    let text = body.trim_start_matches('\u{feff}').to_string();
+    let cookies = parse_netscape_cookies(&text).map_err(|errors| {
+        let shown: Vec<String> = errors.iter().take(10).cloned().collect();
+        let more = errors.len().saturating_sub(shown.len());
+        let mut msg = format!("not a Netscape cookies.txt:\n{}", shown.join("\n"));
+        if more > 0 {
+            msg.push_str(&format!("\n(+{more} more)"));
+        }
+        AppError::BadRequest(msg)
+    })?;
+
+    let path = cookies_file_path(&state.data_dir);
+    let count = cookies.len();
+    let report = tokio::task::spawn_blocking(move || -> anyhow::Result<CookiesStatusResponse> {
+        // yt-dlp refuses files without the Netscape header line.
+        let mut contents = text;
+        if !contents.starts_with("# Netscape HTTP Cookie File") && !contents.starts_with("# HTTP Cookie File") {
+            contents.insert_str(0, "# Netscape HTTP Cookie File\n");
+        }
+        write_private_file(&path, contents.as_bytes())?;
+        Ok(cookies_report(&cookies, Some(now_ms())))
+    })
+    .await
+    .context("upload_ytdlp_cookies task failed")??;
+
+    tracing::info!("stored yt-dlp cookies.txt ({count} cookies)");
+    Ok(Json(report))
+}
+
+async fn delete_ytdlp_cookies(State(state): State<AppState>) -> AppResult<Json<serde_json::Value>> {
+    let path = cookies_file_path(&state.data_dir);
+    let removed = match tokio::fs::remove_file(&path).await {
+        Ok(()) => true,
+        Err(e) if e.kind() == ErrorKind::NotFound => false,
+        Err(e) => return Err(AppError::Internal(anyhow::Error::new(e).context("failed to delete cookies file"))),
+    };
+    if removed {
+        tracing::info!("deleted yt-dlp cookies.txt");
+    }
+    Ok(Json(serde_json::json!({ "ok": true, "removed": removed })))
+}
+
+fn truncate_chars(s: &str, max_chars: usize) -> String {
+    let trimmed = s.trim();
+    if trimmed.chars().count() <= max_chars {
+        return trimmed.to_string();
+    }
+    let mut out: String = trimmed.chars().take(max_chars).collect();
+    out.push('…');
+    out
+}
+
+fn pick_downloaded_file(out_dir: &FsPath, file_base: &str) -> anyhow::Result<Option<PathBuf>> {
+    let mut candidates: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
+    let rd = match std::fs::read_dir(out_dir) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    for entry in rd {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = match path.file_name().and_then(|s| s.to_str()) {
+            Some(s) => s,
+            None => continue,
+        };
+        if !name.starts_with(file_base) {
+            continue;
+        }
+        let meta = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let modified = meta.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+        candidates.push((modified, path));
+    }
+    candidates.sort_by_key(|(ts, _)| *ts);
+    Ok(candidates.pop().map(|(_, p)| p))
+}
+
+/// Removes yt-dlp leftovers of an aborted download: `.part`/`.ytdl` files, fragments, and the
+/// per-format streams (`{base}.f137.mp4`) that are only kept until merging.
+fn remove_partial_downloads(out_dir: &FsPath, file_base: &str) {
+    let Ok(rd) = std::fs::read_dir(out_dir) else {
+        return;
+    };
+    let prefix = format!("{file_base}.");
+    for entry in rd.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(rest) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let is_format_stream = rest
+            .strip_prefix('f')
+            .and_then(|r| r.split_once('.'))
+            .is_some_and(|(format_id, _)| !format_id.is_empty() && format_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+        let partial = rest.ends_with(".part")
+            || rest.ends_with(".ytdl")
+            || rest.contains(".part-Frag")
+            || rest.contains(".temp.")
+            || is_format_stream;
+        if partial {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Project + consent check shared by the remote import endpoints, so background runs are only
+/// queued for valid requests.
+async fn remote_import_preflight(state: &AppState, project_id: &str, download: bool) -> AppResult<()> {
+    if !state.ytdlp {
+        return Err(AppError::PreconditionFailed(
+            "yt-dlp not found; install yt-dlp (and restart toolserver) to enable URL resolve/download".to_string(),
+        ));
+    }
+    if download && !state.ffmpeg {
+        return Err(AppError::PreconditionFailed(
+            "ffmpeg not found on PATH.\n\nRemote downloads often require ffmpeg to merge video+audio into a single mp4.\n\nInstall ffmpeg and restart toolserver.\n\nWindows (winget): winget install Gyan.FFmpeg\nmacOS (brew):    brew install ffmpeg\nUbuntu/Debian:   sudo apt-get install ffmpeg".to_string(),
+        ));
+    }
+
+    let db_path = state.db_path.clone();
+    let project_id_check = project_id.to_string();
+    let consented = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<bool>> {
+        let conn = Connection::open(&db_path)?;
+
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_check], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let consented: bool = conn
+            .query_row("SELECT consented FROM consents WHERE project_id = ?1", [&project_id_check], |r| {
+                Ok(r.get::<_, i64>(0)? != 0)
+            })
+            .optional()?
+            .unwrap_or(false);
+        Ok(Some(consented))
+    })
+    .await
+    .context("import_remote_media preflight failed")??;
+    match consented {
+        None => Err(AppError::NotFound("project not found".to_string())),
+        Some(false) => Err(AppError::PreconditionFailed(
+            "consent required: save URL and confirm consent first".to_string(),
+        )),
+        Some(true) => Ok(()),
+    }
+}
+
+fn validate_remote_url(url: &str) -> AppResult<String> {
+    let url = url.trim().to_string();
+    if url.is_empty() {
+        return Err(AppError::BadRequest("missing url".to_string()));
+    }
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(AppError::BadRequest("url must start with http:// or https://".to_string()));
+    }
+    Ok(url)
+}
+
+fn cookies_from_browser_or_env(requested: Option<&String>) -> Option<String> {
+    requested
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .or_else(|| env_trim("YTDLP_COOKIES_FROM_BROWSER"))
+}
+
+async fn import_remote_media(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<ImportRemoteMediaRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let url = validate_remote_url(&req.url)?;
+    let download = req.download.unwrap_or(false);
+    let cookies = ytdlp_cookies(&state.data_dir, req.cookies_from_browser.as_ref());
+    let format = remote_format_selector(req.format.as_deref(), req.preset).map_err(AppError::BadRequest)?;
+    let subtitles = req.subtitles.unwrap_or(false);
+
+    remote_import_preflight(&state, &project_id, download).await?;
+
+    let proxy = download && req.proxy.unwrap_or(false);
+    if proxy && !state.ffprobe {
+        return Err(AppError::PreconditionFailed(
+            "ffprobe not found on PATH; please install ffmpeg and restart".to_string(),
+        ));
+    }
+
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({
+        "url": &url,
+        "download": download,
+        "proxy": proxy,
+        "format": &format,
+        "preset": req.preset,
+        "subtitles": subtitles,
+    });
+
+    let data_dir = state.data_dir.clone();
+    let ytdlp_cmd = state.ytdlp_cmd.clone();
+    dispatch_run(&state, &project_id, "import_remote_media", request_json, wait, move |job| {
+        let opts = RemoteDownloadOptions {
+            download,
+            subtitles,
+            format: format.as_deref(),
+            cookies: cookies.as_ref(),
+        };
+        match run_import_remote_media(job, &data_dir, &ytdlp_cmd, &url, &opts)? {
+            ImportRemoteMediaOutcome::Ok(mut r) => {
+                if let (true, Some(video)) = (proxy, &r.input_video) {
+                    r.proxy_video = ensure_proxy(job, &data_dir, &video.id, false)?.proxy;
+                }
+                Ok(*r)
+            }
+            ImportRemoteMediaOutcome::PreconditionFailed(msg) => Err(AppError::PreconditionFailed(msg)),
+        }
+    })
+    .await
+}
+
+/// Resolves `url` to yt-dlp info JSON (works for bilibili + other supported sites).
+///
+/// Important: Do NOT force `--cookies-from-browser` for resolve. On Windows, Chrome/Edge
+/// frequently fail with "Could not copy ... cookie database" when the browser is running.
+/// We first try without cookies (public metadata), then retry with cookies only if needed.
+/// An uploaded cookies.txt has no such problem and is always used.
+fn resolve_remote_info(
+    job: &JobCtx,
+    ytdlp_cmd: &str,
+    url: &str,
+    playlist_args: &[&str],
+    cookies: Option<&YtdlpCookies>,
+) -> anyhow::Result<ImportRemoteMediaOutcome<serde_json::Value>> {
+    let resolve_once = |cookies: Option<&YtdlpCookies>| -> anyhow::Result<serde_json::Value> {
+        let mut cmd = Command::new(ytdlp_cmd);
+        cmd.args(["--dump-single-json", "--skip-download", "--no-warnings"]);
+        cmd.args(playlist_args);
+        if let Some(c) = cookies {
+            c.apply(&mut cmd);
+        }
+        cmd.arg(url);
+        let mut stdout = String::new();
+        run_cmd_streaming(&mut cmd, &job.cancel, |line| {
+            stdout.push_str(line);
+            stdout.push('\n');
+        })?;
+        Ok(serde_json::from_str(stdout.trim())?)
+    };
+
+    if let Some(file @ YtdlpCookies::File(_)) = cookies {
+        return Ok(ImportRemoteMediaOutcome::Ok(resolve_once(Some(file))?));
+    }
+    match resolve_once(None) {
+        Ok(v) => Ok(ImportRemoteMediaOutcome::Ok(v)),
+        Err(no_cookie_err) => match cookies {
+            Some(c) => match resolve_once(Some(c)) {
+                Ok(v) => Ok(ImportRemoteMediaOutcome::Ok(v)),
+                Err(cookie_err) => {
+                    if let (YtdlpCookies::Browser(browser), true) = (c, is_cookie_db_copy_error(&cookie_err.to_string())) {
+                        return Ok(ImportRemoteMediaOutcome::PreconditionFailed(cookie_db_copy_help(browser)));
+                    }
+                    Err(cookie_err).context(format!("yt-dlp resolve failed without cookies: {no_cookie_err}"))
+                }
+            },
+            None => Err(no_cookie_err),
+        },
+    }
+}
+
+/// Stores resolved info JSON under `out/ytdlp/` as a `ytdlp_info` artifact.
+fn save_remote_info(
+    conn: &Connection,
+    data_dir: &FsPath,
+    project_id: &str,
+    prefix: &str,
+    info_json: &serde_json::Value,
+    created_at_ms: i64,
+) -> anyhow::Result<ArtifactResponse> {
+    let safe_out_path = sanitize_out_path(&format!("ytdlp/{prefix}-{created_at_ms}.json"))
+        .ok_or_else(|| anyhow::anyhow!("failed to build safe out_path"))?;
+    let rel_info_path = format!("projects/{}/out/{}", project_id, safe_out_path);
+    let abs_info_path = data_dir.join(&rel_info_path);
+    if let Some(parent) = abs_info_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&abs_info_path, serde_json::to_vec_pretty(info_json)?)?;
+    ensure_artifact(conn, project_id, "ytdlp_info", &rel_info_path, created_at_ms)
+}
+
+fn remote_extractor(info_json: &serde_json::Value) -> String {
+    json_string(info_json, "extractor")
+        .or_else(|| json_string(info_json, "extractor_key"))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn remote_file_base(extractor: &str, id: &str, created_at_ms: i64) -> String {
+    let ex = sanitize_file_name(extractor);
+    let vid = sanitize_file_name(id);
+    let base = format!("{ex}-{vid}");
+    if base.trim_matches('_').is_empty() {
+        format!("remote-{created_at_ms}")
+    } else {
+        base
+    }
+}
+
+/// Downloads a single video (never a playlist) into `out_dir_abs` as `{file_base}.{ext}`, merged
+/// to mp4. Download progress is mapped into `span` (percent of the whole run), so batches can
+/// report one continuous bar.
+fn download_remote_video(
+    job: &JobCtx,
+    ytdlp_cmd: &str,
+    url: &str,
+    out_dir_abs: &FsPath,
+    file_base: &str,
+    opts: &RemoteDownloadOptions,
+    span: (f64, f64),
+) -> anyhow::Result<ImportRemoteMediaOutcome<PathBuf>> {
+    std::fs::create_dir_all(out_dir_abs)?;
+
+    let out_template = out_dir_abs.join(format!("{file_base}.%(ext)s"));
+    let out_template_str = out_template.display().to_string();
+    // One machine-readable line per progress tick: downloaded, total, total estimate, eta.
+    let progress_template = format!(
+        "download:{YTDLP_PROGRESS_PREFIX} %(progress.downloaded_bytes)s %(progress.total_bytes)s %(progress.total_bytes_estimate)s %(progress.eta)s"
+    );
+
+    let base_args = [
+        "--no-playlist",
+        "--restrict-filenames",
+        "--no-warnings",
+        "--newline",
+        "--progress-template",
+        &progress_template,
+        "--merge-output-format",
+        "mp4",
+        "-o",
+        &out_template_str,
+    ];
+
+    let run_download = |cookies: Option<&YtdlpCookies>| -> anyhow::Result<()> {
+        let mut dl = Command::new(ytdlp_cmd);
+        dl.args(base_args);
+        if let Some(f) = opts.format {
+            dl.args(["-f", f]);
+        }
+        if let Some(c) = cookies {
+            c.apply(&mut dl);
+        }
+        dl.arg(url);
+        let res = run_cmd_streaming(&mut dl, &job.cancel, |line| {
+            if let Some((downloaded, total, eta_s)) = parse_ytdlp_progress_line(line) {
+                job.report(
+                    "download",
+                    Progress {
+                        percent: total.map(|t| span.0 + (span.1 - span.0) * downloaded as f64 / t as f64),
+                        eta_s,
+                        bytes: Some(downloaded),
+                        total_bytes: total,
+                    },
+                );
+            }
+        });
+        if res.is_err() && job.cancel.is_cancelled() {
+            remove_partial_downloads(out_dir_abs, file_base);
+        }
+        res
+    };
+
+    if let Some(c) = opts.cookies {
+        match run_download(Some(c)) {
+            Ok(()) => {}
+            Err(err) => {
+                if let (YtdlpCookies::Browser(browser), true) = (c, is_cookie_db_copy_error(&err.to_string())) {
+                    // Best-effort fallback: public videos might still download without cookies.
+                    if run_download(None).is_err() {
+                        return Ok(ImportRemoteMediaOutcome::PreconditionFailed(cookie_db_copy_help(browser)));
+                    }
+                } else {
+                    return Err(err);
+                }
+            }
+        }
+    } else {
+        run_download(None)?;
+    }
+
+    let expected = out_dir_abs.join(format!("{file_base}.mp4"));
+    let downloaded_abs = if expected.exists() {
+        expected
+    } else {
+        pick_downloaded_file(out_dir_abs, file_base)?
+            .ok_or_else(|| anyhow::anyhow!("download finished but output file not found"))?
+    };
+    Ok(ImportRemoteMediaOutcome::Ok(downloaded_abs))
+}
+
+struct RemoteDownloadOptions<'a> {
+    download: bool,
+    subtitles: bool,
+    format: Option<&'a str>,
+    cookies: Option<&'a YtdlpCookies>,
+}
+
+fn run_import_remote_media(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    ytdlp_cmd: &str,
+    url: &str,
+    opts: &RemoteDownloadOptions,
+) -> anyhow::Result<ImportRemoteMediaOutcome> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+    let cookies = opts.cookies;
+
+    // Resolving with the same `-f` makes yt-dlp report the format it is going to download (and
+    // fail early when nothing matches).
+    let mut resolve_args = vec!["--no-playlist"];
+    if let Some(f) = opts.format {
+        resolve_args.extend(["-f", f]);
+    }
+    let info_json = match resolve_remote_info(job, ytdlp_cmd, url, &resolve_args, cookies)? {
+        ImportRemoteMediaOutcome::Ok(v) => v,
+        ImportRemoteMediaOutcome::PreconditionFailed(msg) => return Ok(ImportRemoteMediaOutcome::PreconditionFailed(msg)),
+    };
+
+    let created_at_ms = now_ms();
+    let info_artifact = save_remote_info(conn, data_dir, project_id, "info", &info_json, created_at_ms)?;
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_resolve', ?3)",
+        params![project_id, created_at_ms, serde_json::json!({ "url": url }).to_string()],
+    )?;
+
+    let extractor = remote_extractor(&info_json);
+    let id = json_string(&info_json, "id").unwrap_or_else(|| "unknown".to_string());
+    let title = json_string(&info_json, "title").unwrap_or_else(|| "untitled".to_string());
+    let webpage_url = json_string(&info_json, "webpage_url").unwrap_or_else(|| url.to_string());
+    let duration_s = json_f64(&info_json, "duration");
+    let thumbnail = json_string(&info_json, "thumbnail")
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let description = json_string(&info_json, "description")
+        .map(|s| truncate_chars(&clean_one_line(&s), 280))
+        .filter(|s| !s.is_empty());
+    let formats = remote_formats(&info_json);
+    let selected_format_id = json_string(&info_json, "format_id");
+    // A merged selection lists its parts in `requested_formats`; otherwise the top level is the format.
+    let has_video = match info_json.get("requested_formats").and_then(|f| f.as_array()) {
+        Some(parts) => parts.iter().any(|f| json_string(f, "vcodec").is_some_and(|c| c != "none")),
+        None => json_string(&info_json, "vcodec").is_none_or(|c| c != "none"),
+    };
+
+    let mut input_video: Option<ArtifactResponse> = None;
+    let mut input_audio: Option<ArtifactResponse> = None;
+
+    if opts.download {
+        let out_dir_rel = format!("projects/{}/media/remote", project_id);
+        let out_dir_abs = data_dir.join(&out_dir_rel);
+        // Copies in different formats (a 480p analysis copy next to the best source) must not
+        // overwrite each other, so an explicit selection is part of the file name.
+        let file_base = match (opts.format, selected_format_id.as_deref()) {
+            (Some(_), Some(format_id)) => {
+                format!("{}-f{}", remote_file_base(&extractor, &id, created_at_ms), sanitize_file_name(format_id))
+            }
+            _ => remote_file_base(&extractor, &id, created_at_ms),
+        };
+
+        let downloaded_abs =
+            match download_remote_video(job, ytdlp_cmd, url, &out_dir_abs, &file_base, opts, (0.0, 100.0))? {
+                ImportRemoteMediaOutcome::Ok(p) => p,
+                ImportRemoteMediaOutcome::PreconditionFailed(msg) => {
+                    return Ok(ImportRemoteMediaOutcome::PreconditionFailed(msg))
+                }
+            };
+
+        let rel_video_path = downloaded_abs
+            .strip_prefix(data_dir)
+            .unwrap_or(&downloaded_abs)
+            .display()
+            .to_string();
+        let kind = if has_video { "input_video" } else { "input_audio" };
+        let artifact = ensure_artifact(conn, project_id, kind, &rel_video_path, created_at_ms)?;
+        let content_hash = artifact_content_hash(conn, &artifact.id, &downloaded_abs)?;
+        if has_video {
+            input_video = Some(artifact);
+        } else {
+            input_audio = Some(artifact);
+        }
+
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_download', ?3)",
+            params![
+                project_id,
+                created_at_ms,
+                serde_json::json!({
+                    "url": url,
+                    "path": &rel_video_path,
+                    "sha256": &content_hash,
+                    "format": opts.format,
+                    "format_id": &selected_format_id,
+                })
+                .to_string()
+            ],
+        )?;
+    }
+
+    let linked: Vec<&ArtifactResponse> =
+        std::iter::once(&info_artifact).chain(input_video.iter()).chain(input_audio.iter()).collect();
+    catalog_remote_source(conn, project_id, &info_json, url, &linked)?;
+
+    // Subtitles are a bonus: a failure here is logged but does not fail the import.
+    let mut timed_text: Option<RemoteTimedText> = None;
+    if opts.subtitles {
+        let file_base = remote_file_base(&extractor, &id, created_at_ms);
+        match fetch_remote_timed_text(
+            job,
+            data_dir,
+            ytdlp_cmd,
+            url,
+            &file_base,
+            duration_s,
+            cookies,
+        ) {
+            Ok(t) => {
+                // Linked to the downloaded media so the pipeline can find them (see `danmaku_peaks`).
+                if let Some(media) = input_video.as_ref().or(input_audio.as_ref()) {
+                    for artifact in t.tracks.iter().map(|t| &t.artifact).chain(t.danmaku_heatmap.iter()) {
+                        conn.execute(
+                            "UPDATE artifacts SET source_artifact_id = ?1 WHERE id = ?2",
+                            params![&media.id, &artifact.id],
+                        )?;
+                    }
+                }
+                timed_text = Some(t);
+            }
+            Err(err) => {
+                job.cancel.check()?;
+                conn.execute(
+                    "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'remote_timed_text_failed', ?3)",
+                    params![
+                        project_id,
+                        now_ms(),
+                        serde_json::json!({ "url": url, "error": format!("{err:#}") }).to_string()
+                    ],
+                )?;
+            }
+        }
+    }
+
+    Ok(ImportRemoteMediaOutcome::Ok(Box::new(ImportRemoteMediaResponse {
+        info: RemoteMediaInfoSummary {
+            extractor,
+            id,
+            title,
+            duration_s,
+            webpage_url,
+            thumbnail,
+            description,
+            formats,
+            selected_format_id,
+        },
+        info_artifact,
+        input_video,
+        input_audio,
+        proxy_video: None,
+        timed_text,
+    })))
+}
+
+const MAX_PLAYLIST_ENTRIES: usize = 500;
+
+#[derive(Deserialize)]
+struct ResolveRemotePlaylistRequest {
+    url: String,
+    cookies_from_browser: Option<String>,
+    wait: Option<bool>,
+}
+
+#[derive(Serialize, Clone)]
+struct RemotePlaylistSummary {
+    extractor: String,
+    id: String,
+    title: String,
+    webpage_url: String,
+}
+
+/// One entry of a flat playlist. `index` is 1-based and doubles as the part number of
+/// multi-part uploads.
+#[derive(Serialize, Clone)]
+struct RemotePlaylistEntry {
+    index: usize,
+    id: Option<String>,
+    title: Option<String>,
+    url: Option<String>,
+    duration_s: Option<f64>,
+    /// The entry's own (flat) info JSON, for the remote source catalog.
+    #[serde(skip)]
+    info: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct RemotePlaylistResponse {
+    playlist: RemotePlaylistSummary,
+    entries: Vec<RemotePlaylistEntry>,
+    info_artifact: ArtifactResponse,
+}
+
+impl RunArtifacts for RemotePlaylistResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        vec![self.info_artifact.clone()]
+    }
+}
+
+/// Reads the entry list of `--flat-playlist` info JSON. A URL that resolves to a single video
+/// yields one entry, so callers do not need to special-case it.
+fn parse_flat_playlist(info_json: &serde_json::Value, url: &str) -> (RemotePlaylistSummary, Vec<RemotePlaylistEntry>) {
+    let summary = RemotePlaylistSummary {
+        extractor: remote_extractor(info_json),
+        id: json_string(info_json, "id").unwrap_or_else(|| "unknown".to_string()),
+        title: json_string(info_json, "title").unwrap_or_else(|| "untitled".to_string()),
+        webpage_url: json_string(info_json, "webpage_url").unwrap_or_else(|| url.to_string()),
+    };
+
+    let entries = match info_json.get("entries").and_then(|e| e.as_array()) {
+        Some(entries) => entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| RemotePlaylistEntry {
+                index: i + 1,
+                id: json_string(e, "id"),
+                title: json_string(e, "title").map(|s| clean_one_line(&s)).filter(|s| !s.is_empty()),
+                url: json_string(e, "url")
+                    .or_else(|| json_string(e, "webpage_url"))
+                    .filter(|u| u.starts_with("http://") || u.starts_with("https://")),
+                duration_s: json_f64(e, "duration"),
+                info: e.clone(),
+            })
+            .collect(),
+        None => vec![RemotePlaylistEntry {
+            index: 1,
+            id: Some(summary.id.clone()),
+            title: Some(summary.title.clone()),
+            url: Some(summary.webpage_url.clone()),
+            duration_s: json_f64(info_json, "duration"),
+            info: info_json.clone(),
+        }],
+    };
+    (summary, entries)
+}
+
+fn resolve_remote_playlist(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    ytdlp_cmd: &str,
+    url: &str,
+    cookies: Option<&YtdlpCookies>,
+) -> anyhow::Result<ImportRemoteMediaOutcome<RemotePlaylistResponse>> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    let info_json = match resolve_remote_info(
+        job,
+        ytdlp_cmd,
+        url,
+        &["--flat-playlist", "--yes-playlist"],
+        cookies,
+    )? {
+        ImportRemoteMediaOutcome::Ok(v) => v,
+        ImportRemoteMediaOutcome::PreconditionFailed(msg) => return Ok(ImportRemoteMediaOutcome::PreconditionFailed(msg)),
+    };
+
+    let created_at_ms = now_ms();
+    let info_artifact = save_remote_info(conn, data_dir, project_id, "playlist", &info_json, created_at_ms)?;
+    let (playlist, entries) = parse_flat_playlist(&info_json, url);
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_playlist_resolve', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({ "url": url, "playlist_id": &playlist.id, "entries": entries.len() }).to_string()
+        ],
+    )?;
+
+    Ok(ImportRemoteMediaOutcome::Ok(RemotePlaylistResponse {
+        playlist,
+        entries,
+        info_artifact,
+    }))
+}
+
+/// Lists the entries of a playlist, collection or multi-part upload so a client can choose
+/// which ones to download via `/media/remote/playlist/download`.
+async fn resolve_remote_playlist_entries(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<ResolveRemotePlaylistRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let url = validate_remote_url(&req.url)?;
+    let cookies = ytdlp_cookies(&state.data_dir, req.cookies_from_browser.as_ref());
+
+    remote_import_preflight(&state, &project_id, false).await?;
+
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({ "url": &url });
+
+    let data_dir = state.data_dir.clone();
+    let ytdlp_cmd = state.ytdlp_cmd.clone();
+    dispatch_run(&state, &project_id, "remote_playlist_resolve", request_json, wait, move |job| {
+        match resolve_remote_playlist(job, &data_dir, &ytdlp_cmd, &url, cookies.as_ref())? {
+            ImportRemoteMediaOutcome::Ok(r) => Ok(r),
+            ImportRemoteMediaOutcome::PreconditionFailed(msg) => Err(AppError::PreconditionFailed(msg)),
+        }
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct DownloadRemotePlaylistRequest {
+    url: String,
+    /// 1-based entry indexes from the resolve response.
+    entries: Vec<usize>,
+    cookies_from_browser: Option<String>,
+    proxy: Option<bool>,
+    wait: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct RemotePlaylistItem {
+    index: usize,
+    title: Option<String>,
+    url: Option<String>,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_video: Option<ArtifactResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_video: Option<ArtifactResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RemotePlaylistDownloadResponse {
+    playlist: RemotePlaylistSummary,
+    info_artifact: ArtifactResponse,
+    downloaded: usize,
+    failed: usize,
+    items: Vec<RemotePlaylistItem>,
+}
+
+impl RunArtifacts for RemotePlaylistDownloadResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        let mut out = vec![self.info_artifact.clone()];
+        for item in &self.items {
+            out.extend(item.input_video.iter().cloned());
+            out.extend(item.proxy_video.iter().cloned());
+        }
+        out
+    }
+}
+
+/// Sorts and de-duplicates the requested 1-based entry indexes.
+fn playlist_entry_indexes(requested: &[usize]) -> Result<Vec<usize>, String> {
+    let mut entries = requested.to_vec();
+    entries.sort_unstable();
+    entries.dedup();
+    if entries.is_empty() || entries.len() > MAX_PLAYLIST_ENTRIES {
+        return Err(format!("entries must list 1 to {MAX_PLAYLIST_ENTRIES} entry indexes"));
+    }
+    if entries[0] == 0 {
+        return Err("entry indexes start at 1".to_string());
+    }
+    Ok(entries)
+}
+
+/// Resolves a requested index against the flat playlist; indexes past the end are per-item errors.
+fn playlist_entry_url(entries: &[RemotePlaylistEntry], index: usize) -> Result<String, String> {
+    match entries.iter().find(|e| e.index == index) {
+        Some(entry) => entry.url.clone().ok_or_else(|| "entry has no downloadable url".to_string()),
+        None => Err(format!("playlist has no entry {index} ({} entries)", entries.len())),
+    }
+}
+
+async fn download_remote_playlist_entries(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<DownloadRemotePlaylistRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let url = validate_remote_url(&req.url)?;
+    let entries = playlist_entry_indexes(&req.entries).map_err(AppError::BadRequest)?;
+    let cookies = ytdlp_cookies(&state.data_dir, req.cookies_from_browser.as_ref());
+
+    remote_import_preflight(&state, &project_id, true).await?;
+
+    let proxy = req.proxy.unwrap_or(false);
+    if proxy && !state.ffprobe {
+        return Err(AppError::PreconditionFailed(
+            "ffprobe not found on PATH; please install ffmpeg and restart".to_string(),
+        ));
+    }
+
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({ "url": &url, "entries": &entries, "proxy": proxy });
+
+    let data_dir = state.data_dir.clone();
+    let ytdlp_cmd = state.ytdlp_cmd.clone();
+    dispatch_run(&state, &project_id, "remote_playlist_download", request_json, wait, move |job| {
+        let resolved = match resolve_remote_playlist(job, &data_dir, &ytdlp_cmd, &url, cookies.as_ref())? {
+            ImportRemoteMediaOutcome::Ok(r) => r,
+            ImportRemoteMediaOutcome::PreconditionFailed(msg) => return Err(AppError::PreconditionFailed(msg)),
+        };
+        Ok(run_remote_playlist_download(
+            job,
+            &data_dir,
+            &ytdlp_cmd,
+            resolved,
+            &entries,
+            cookies.as_ref(),
+            proxy,
+        )?)
+    })
+    .await
+}
+
+/// Downloads the chosen entries one by one. Each becomes its own `input_video` labelled with its
+/// part number and title; a failing entry is recorded and the batch moves on. Cancellation still
+/// stops the whole run.
+fn run_remote_playlist_download(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    ytdlp_cmd: &str,
+    resolved: RemotePlaylistResponse,
+    chosen: &[usize],
+    cookies: Option<&YtdlpCookies>,
+    proxy: bool,
+) -> anyhow::Result<RemotePlaylistDownloadResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+    let playlist = resolved.playlist;
+
+    let out_dir_abs = data_dir.join(format!("projects/{}/media/remote", project_id));
+    let playlist_base = remote_file_base(&playlist.extractor, &playlist.id, now_ms());
+    let total = chosen.len();
+    let entry_opts = RemoteDownloadOptions {
+        download: true,
+        subtitles: false,
+        format: None,
+        cookies,
+    };
+
+    let mut items: Vec<RemotePlaylistItem> = Vec::with_capacity(total);
+    for (n, &index) in chosen.iter().enumerate() {
+        job.cancel.check()?;
+        let entry = resolved.entries.iter().find(|e| e.index == index);
+        let mut item = RemotePlaylistItem {
+            index,
+            title: entry.and_then(|e| e.title.clone()),
+            url: entry.and_then(|e| e.url.clone()),
+            ok: false,
+            input_video: None,
+            proxy_video: None,
+            sha256: None,
+            error: None,
+        };
+        let entry_url = match playlist_entry_url(&resolved.entries, index) {
+            Ok(url) => url,
+            Err(err) => {
+                item.error = Some(err);
+                items.push(item);
+                continue;
+            }
+        };
+
+        let span = (n as f64 * 100.0 / total as f64, (n + 1) as f64 * 100.0 / total as f64);
+        let file_base = format!("{playlist_base}-p{index:03}");
+        let downloaded = download_remote_video(job, ytdlp_cmd, &entry_url, &out_dir_abs, &file_base, &entry_opts, span);
+        let downloaded_abs = match downloaded {
+            Ok(ImportRemoteMediaOutcome::Ok(p)) => p,
+            Ok(ImportRemoteMediaOutcome::PreconditionFailed(msg)) => {
+                item.error = Some(msg);
+                items.push(item);
+                continue;
+            }
+            Err(err) => {
+                job.cancel.check()?;
+                remove_partial_downloads(&out_dir_abs, &file_base);
+                item.error = Some(truncate_chars(&format!("{err:#}"), 500));
+                items.push(item);
+                continue;
+            }
+        };
+
+        let created_at_ms = now_ms();
+        let rel_video_path = downloaded_abs
+            .strip_prefix(data_dir)
+            .unwrap_or(&downloaded_abs)
+            .display()
+            .to_string();
+        let video_artifact = ensure_artifact(conn, project_id, "input_video", &rel_video_path, created_at_ms)?;
+        let content_hash = artifact_content_hash(conn, &video_artifact.id, &downloaded_abs)?;
+        let label = truncate_chars(
+            &match item.title.as_deref() {
+                Some(title) => format!("P{index} {title}"),
+                None => format!("P{index}"),
+            },
+            MAX_INPUT_LABEL_CHARS,
+        );
+        conn.execute(
+            "UPDATE artifacts SET label = ?1 WHERE id = ?2",
+            params![&label, &video_artifact.id],
+        )?;
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_download', ?3)",
+            params![
+                project_id,
+                created_at_ms,
+                serde_json::json!({
+                    "url": &entry_url,
+                    "path": &rel_video_path,
+                    "sha256": &content_hash,
+                    "playlist_id": &playlist.id,
+                    "part": index,
+                    "title": &item.title,
+                })
+                .to_string()
+            ],
+        )?;
+
+        if proxy {
+            match ensure_proxy(job, data_dir, &video_artifact.id, false) {
+                Ok(p) => item.proxy_video = p.proxy,
+                Err(err) => {
+                    job.cancel.check()?;
+                    item.error = Some(truncate_chars(&format!("proxy failed: {err:#}"), 500));
+                }
+            }
+        }
+        // Flat entries only carry a few fields; the catalog keeps whatever it already knew.
+        if let Some(mut source) = entry.and_then(|e| RemoteSource::from_info(&e.info, &entry_url, created_at_ms)) {
+            source.webpage_url = entry_url.clone();
+            let (source, _) = upsert_remote_source(conn, source, true)?;
+            link_remote_source(conn, &source, project_id, &video_artifact.id)?;
+        }
+        item.ok = true;
+        item.sha256 = Some(content_hash);
+        item.input_video = Some(video_artifact);
+        items.push(item);
+    }
+
+    let downloaded = items.iter().filter(|i| i.ok).count();
+    let failed = items.len() - downloaded;
+    let failures: Vec<serde_json::Value> = items
+        .iter()
+        .filter(|i| !i.ok)
+        .map(|i| serde_json::json!({ "part": i.index, "error": &i.error }))
+        .collect();
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, ?3, 'remote_playlist_downloaded', ?4)",
+        params![
+            project_id,
+            now_ms(),
+            if failed > 0 { "warn" } else { "info" },
+            serde_json::json!({
+                "url": &playlist.webpage_url,
+                "playlist_id": &playlist.id,
+                "downloaded": downloaded,
+                "failed": failed,
+                "failures": failures,
+            })
+            .to_string()
+        ],
+    )?;
+
+    Ok(RemotePlaylistDownloadResponse {
+        playlist,
+        info_artifact: resolved.info_artifact,
+        downloaded,
+        failed,
+        items,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RemoteChapter {
+    start_s: f64,
+    end_s: Option<f64>,
+    title: Option<String>,
+}
+
+/// Catalog row of a remote video, shared by every project that imported it.
+#[derive(Debug, Clone, Serialize)]
+struct RemoteSource {
+    extractor: String,
+    id: String,
+    title: Option<String>,
+    uploader: Option<String>,
+    channel: Option<String>,
+    /// `YYYY-MM-DD`.
+    upload_date: Option<String>,
+    duration_s: Option<f64>,
+    view_count: Option<i64>,
+    like_count: Option<i64>,
+    tags: Vec<String>,
+    chapters: Vec<RemoteChapter>,
+    webpage_url: String,
+    /// yt-dlp's `availability` (`public`, `unlisted`, `private`, `needs_auth`, ...), or
+    /// `unavailable` once a refresh can no longer resolve the video.
+    availability: Option<String>,
+    first_seen_at_ms: i64,
+    updated_at_ms: i64,
+    refreshed_at_ms: Option<i64>,
+}
+
+/// Fields compared on upsert; everything the catalog stores except bookkeeping timestamps.
+const REMOTE_SOURCE_TRACKED_FIELDS: &[&str] = &[
+    "title",
+    "uploader",
+    "channel",
+    "upload_date",
+    "duration_s",
+    "view_count",
+    "like_count",
+    "tags",
+    "chapters",
+    "webpage_url",
+    "availability",
+];
+
+#[derive(Debug, Clone, Serialize)]
+struct RemoteSourceChange {
+    ts_ms: i64,
+    field: String,
+    old: serde_json::Value,
+    new: serde_json::Value,
+}
+
+/// Stable catalog key: yt-dlp reports `Youtube`/`youtube` depending on the code path.
+fn remote_source_extractor(info_json: &serde_json::Value) -> Option<String> {
+    json_string(info_json, "extractor_key")
+        .or_else(|| json_string(info_json, "ie_key"))
+        .or_else(|| json_string(info_json, "extractor"))
+        .map(|e| e.trim().to_ascii_lowercase())
+        .filter(|e| !e.is_empty())
+}
+
+impl RemoteSource {
+    /// Builds a row from info JSON; `None` when the JSON has no extractor/id to key it by.
+    fn from_info(info_json: &serde_json::Value, fallback_url: &str, now: i64) -> Option<Self> {
+        let extractor = remote_source_extractor(info_json)?;
+        let id = json_string(info_json, "id").filter(|s| !s.trim().is_empty())?;
+        let int = |key: &str| info_json.get(key).and_then(|v| v.as_i64());
+        let upload_date = json_string(info_json, "upload_date").map(|d| match (d.get(0..4), d.get(4..6), d.get(6..8)) {
+            (Some(y), Some(m), Some(day)) if d.len() == 8 => format!("{y}-{m}-{day}"),
+            _ => d,
+        });
+        let tags = info_json
+            .get("tags")
+            .and_then(|t| t.as_array())
+            .map(|t| t.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        let chapters = info_json
+            .get("chapters")
+            .and_then(|c| c.as_array())
+            .map(|c| {
+                c.iter()
+                    .filter_map(|ch| {
+                        Some(RemoteChapter {
+                            start_s: json_f64(ch, "start_time")?,
+                            end_s: json_f64(ch, "end_time"),
+                            title: json_string(ch, "title"),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self {
+            extractor,
+            id,
+            title: json_string(info_json, "title"),
+            uploader: json_string(info_json, "uploader"),
+            channel: json_string(info_json, "channel"),
+            upload_date,
+            duration_s: json_f64(info_json, "duration"),
+            view_count: int("view_count"),
+            like_count: int("like_count"),
+            tags,
+            chapters,
+            webpage_url: json_string(info_json, "webpage_url")
+                .or_else(|| json_string(info_json, "url"))
+                .unwrap_or_else(|| fallback_url.to_string()),
+            availability: json_string(info_json, "availability"),
+            first_seen_at_ms: now,
+            updated_at_ms: now,
+            refreshed_at_ms: None,
+        })
+    }
+
+    fn tracked_values(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut map = match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        map.retain(|k, _| REMOTE_SOURCE_TRACKED_FIELDS.contains(&k.as_str()));
+        map
+    }
+
+    /// Keeps fields of `old` that this (partial) row does not know, e.g. when it came from a flat
+    /// playlist entry that only has a title and duration.
+    fn fill_from(&mut self, old: &RemoteSource) {
+        macro_rules! keep {
+            ($($field:ident),*) => {
+                $(if self.$field.is_none() {
+                    self.$field = old.$field.clone();
+                })*
+            };
+        }
+        keep!(title, uploader, channel, upload_date, duration_s, view_count, like_count, availability);
+        if self.tags.is_empty() {
+            self.tags = old.tags.clone();
+        }
+        if self.chapters.is_empty() {
+            self.chapters = old.chapters.clone();
+        }
+    }
+}
+
+fn read_remote_source(conn: &Connection, extractor: &str, id: &str) -> anyhow::Result<Option<RemoteSource>> {
+    conn.query_row(
+        "SELECT extractor, id, title, uploader, channel, upload_date, duration_s, view_count, like_count, tags_json,\n                chapters_json, webpage_url, availability, first_seen_at_ms, updated_at_ms, refreshed_at_ms\n         FROM remote_sources WHERE extractor = ?1 AND id = ?2",
+        params![extractor, id],
+        remote_source_from_row,
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+fn remote_source_from_row(row: &rusqlite::Row) -> rusqlite::Result<RemoteSource> {
+    let tags_json: Option<String> = row.get(9)?;
+    let chapters_json: Option<String> = row.get(10)?;
+    Ok(RemoteSource {
+        extractor: row.get(0)?,
+        id: row.get(1)?,
+        title: row.get(2)?,
+        uploader: row.get(3)?,
+        channel: row.get(4)?,
+        upload_date: row.get(5)?,
+        duration_s: row.get(6)?,
+        view_count: row.get(7)?,
+        like_count: row.get(8)?,
+        tags: tags_json.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default(),
+        chapters: chapters_json.and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default(),
+        webpage_url: row.get(11)?,
+        availability: row.get(12)?,
+        first_seen_at_ms: row.get(13)?,
+        updated_at_ms: row.get(14)?,
+        refreshed_at_ms: row.get(15)?,
+    })
+}
+
+/// Inserts or updates a catalog row and records every tracked field that changed. With
+/// `partial`, fields the new row does not know keep their stored values.
+fn upsert_remote_source(
+    conn: &Connection,
+    mut source: RemoteSource,
+    partial: bool,
+) -> anyhow::Result<(RemoteSource, Vec<RemoteSourceChange>)> {
+    let now = source.updated_at_ms;
+    let mut changes: Vec<RemoteSourceChange> = Vec::new();
+    if let Some(old) = read_remote_source(conn, &source.extractor, &source.id)? {
+        if partial {
+            source.fill_from(&old);
+        }
+        source.first_seen_at_ms = old.first_seen_at_ms;
+        source.refreshed_at_ms = source.refreshed_at_ms.or(old.refreshed_at_ms);
+        let before = old.tracked_values();
+        for (field, new) in source.tracked_values() {
+            let old_value = before.get(&field).cloned().unwrap_or(serde_json::Value::Null);
+            if old_value != new {
+                changes.push(RemoteSourceChange { ts_ms: now, field, old: old_value, new });
+            }
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO remote_sources (extractor, id, title, uploader, channel, upload_date, duration_s, view_count, like_count,\n           tags_json, chapters_json, webpage_url, availability, first_seen_at_ms, updated_at_ms, refreshed_at_ms)\n         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)\n         ON CONFLICT(extractor, id) DO UPDATE SET\n           title = excluded.title, uploader = excluded.uploader, channel = excluded.channel,\n           upload_date = excluded.upload_date, duration_s = excluded.duration_s, view_count = excluded.view_count,\n           like_count = excluded.like_count, tags_json = excluded.tags_json, chapters_json = excluded.chapters_json,\n           webpage_url = excluded.webpage_url, availability = excluded.availability,\n           updated_at_ms = excluded.updated_at_ms, refreshed_at_ms = excluded.refreshed_at_ms",
+        params![
+            &source.extractor,
+            &source.id,
+            &source.title,
+            &source.uploader,
+            &source.channel,
+            &source.upload_date,
+            source.duration_s,
+            source.view_count,
+            source.like_count,
+            serde_json::to_string(&source.tags)?,
+            serde_json::to_string(&source.chapters)?,
+            &source.webpage_url,
+            &source.availability,
+            source.first_seen_at_ms,
+            source.updated_at_ms,
+            source.refreshed_at_ms,
+        ],
+    )?;
+    for change in &changes {
+        conn.execute(
+            "INSERT INTO remote_source_changes (extractor, source_id, ts_ms, field, old_json, new_json) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                &source.extractor,
+                &source.id,
+                change.ts_ms,
+                &change.field,
+                change.old.to_string(),
+                change.new.to_string()
+            ],
+        )?;
+    }
+    Ok((source, changes))
+}
+
+fn link_remote_source(conn: &Connection, source: &RemoteSource, project_id: &str, artifact_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO remote_source_links (extractor, source_id, project_id, artifact_id, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![&source.extractor, &source.id, project_id, artifact_id, now_ms()],
+    )?;
+    Ok(())
+}
+
+/// Catalogs resolved info JSON and links it to the artifacts produced from it. Cataloging is
+/// bookkeeping, so info JSON without an extractor/id is silently skipped.
+fn catalog_remote_source(
+    conn: &Connection,
+    project_id: &str,
+    info_json: &serde_json::Value,
+    url: &str,
+    artifacts: &[&ArtifactResponse],
+) -> anyhow::Result<Option<RemoteSource>> {
+    let Some(source) = RemoteSource::from_info(info_json, url, now_ms()) else {
+        return Ok(None);
+    };
+    let (source, _) = upsert_remote_source(conn, source, false)?;
+    for artifact in artifacts {
+        link_remote_source(conn, &source, project_id, &artifact.id)?;
+    }
+    Ok(Some(source))
+}
+
+#[derive(Serialize)]
+struct RemoteSourceLink {
+    project_id: String,
+    artifact: ArtifactResponse,
+}
+
+#[derive(Serialize)]
+struct RemoteSourceDetailResponse {
+    #[serde(flatten)]
+    source: RemoteSource,
+    links: Vec<RemoteSourceLink>,
+    changes: Vec<RemoteSourceChange>,
+}
+
+fn remote_source_links(conn: &Connection, source: &RemoteSource, project_id: &str) -> anyhow::Result<Vec<RemoteSourceLink>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.project_id, a.kind, a.path, a.created_at_ms FROM remote_source_links l\n         JOIN artifacts a ON a.id = l.artifact_id\n         WHERE l.extractor = ?1 AND l.source_id = ?2 AND l.project_id = ?3 ORDER BY a.created_at_ms ASC",
+    )?;
+    let rows = stmt.query_map(params![&source.extractor, &source.id, project_id], |row| {
+        Ok(RemoteSourceLink {
+            project_id: row.get(1)?,
+            artifact: ArtifactResponse {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                kind: row.get(2)?,
+                path: row.get(3)?,
+                created_at_ms: row.get(4)?,
+            },
+        })
+    })?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+fn remote_source_changes(conn: &Connection, source: &RemoteSource) -> anyhow::Result<Vec<RemoteSourceChange>> {
+    let mut stmt = conn.prepare(
+        "SELECT ts_ms, field, old_json, new_json FROM remote_source_changes\n         WHERE extractor = ?1 AND source_id = ?2 ORDER BY id DESC LIMIT 200",
+    )?;
+    let rows = stmt.query_map(params![&source.extractor, &source.id], |row| {
+        let parse = |v: Option<String>| v.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or(serde_json::Value::Null);
+        Ok(RemoteSourceChange {
+            ts_ms: row.get(0)?,
+            field: row.get(1)?,
+            old: parse(row.get(2)?),
+            new: parse(row.get(3)?),
+        })
+    })?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// A catalog row as seen from one project; `None` unless the project has used the source.
+fn project_remote_source(
+    conn: &Connection,
+    project_id: &str,
+    extractor: &str,
+    source_id: &str,
+) -> anyhow::Result<Option<RemoteSource>> {
+    let linked = conn
+        .query_row(
+            "SELECT 1 FROM remote_source_links WHERE extractor = ?1 AND source_id = ?2 AND project_id = ?3 LIMIT 1",
+            params![extractor, source_id, project_id],
+            |_row| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if !linked {
+        return Ok(None);
+    }
+    read_remote_source(conn, extractor, source_id)
+}
+
+async fn list_remote_sources(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+) -> AppResult<Json<Vec<RemoteSource>>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let sources = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Vec<RemoteSource>>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+        let mut stmt = conn.prepare(
+            "SELECT s.extractor, s.id, s.title, s.uploader, s.channel, s.upload_date, s.duration_s, s.view_count, s.like_count,\n                    s.tags_json, s.chapters_json, s.webpage_url, s.availability, s.first_seen_at_ms, s.updated_at_ms, s.refreshed_at_ms\n             FROM remote_sources s\n             WHERE EXISTS (SELECT 1 FROM remote_source_links l WHERE l.extractor = s.extractor AND l.source_id = s.id AND l.project_id = ?1)\n             ORDER BY s.first_seen_at_ms ASC",
+        )?;
+        let rows = stmt.query_map([&project_id], remote_source_from_row)?;
+        Ok(Some(rows.filter_map(Result::ok).collect()))
+    })
+    .await
+    .context("list_remote_sources task failed")??;
+
+    match sources {
+        Some(s) => Ok(Json(s)),
+        None => Err(AppError::NotFound("project not found".to_string())),
+    }
+}
+
+async fn get_remote_source(
+    State(state): State<AppState>,
+    Path((project_id, extractor, source_id)): Path<(String, String, String)>,
+) -> AppResult<Json<RemoteSourceDetailResponse>> {
+    let db_path = state.db_path.clone();
+    let detail = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<RemoteSourceDetailResponse>> {
+        let conn = Connection::open(&db_path)?;
+        let Some(source) = project_remote_source(&conn, &project_id, &extractor, &source_id)? else {
+            return Ok(None);
+        };
+        Ok(Some(RemoteSourceDetailResponse {
+            links: remote_source_links(&conn, &source, &project_id)?,
+            changes: remote_source_changes(&conn, &source)?,
+            source,
+        }))
+    })
+    .await
+    .context("get_remote_source task failed")??;
+
+    match detail {
+        Some(d) => Ok(Json(d)),
+        None => Err(AppError::NotFound("remote source not found in this project".to_string())),
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct RefreshRemoteSourceRequest {
+    cookies_from_browser: Option<String>,
+    wait: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct RefreshRemoteSourceResponse {
+    source: RemoteSource,
+    changes: Vec<RemoteSourceChange>,
+    /// Why the video could not be resolved (it is then marked private/unavailable).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl RunArtifacts for RefreshRemoteSourceResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        Vec::new()
+    }
+}
+
+/// Why yt-dlp can no longer resolve a video that used to work, for the messages that mean the video
+/// itself changed. Anything else (network, rate limits, an outdated extractor) is `None`: it says
+/// nothing about the video and must not be recorded as an availability change.
+fn unavailable_reason(error: &str) -> Option<&'static str> {
+    let lower = error.to_ascii_lowercase();
+    if lower.contains("private video") || lower.contains("this video is private") {
+        Some("private")
+    } else if lower.contains("members-only")
+        || lower.contains("join this channel")
+        || lower.contains("sign in to confirm your age")
+        || lower.contains("login required")
+    {
+        Some("needs_auth")
+    } else if lower.contains("video unavailable")
+        || lower.contains("this video has been removed")
+        || lower.contains("this video is no longer available")
+    {
+        Some("unavailable")
+    } else {
+        None
+    }
+}
+
+/// Re-resolves a cataloged video and records what changed since it was last seen.
+async fn refresh_remote_source(
+    State(state): State<AppState>,
+    Path((project_id, extractor, source_id)): Path<(String, String, String)>,
+    body: Option<Json<RefreshRemoteSourceRequest>>,
+) -> AppResult<Response> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    remote_import_preflight(&state, &project_id, false).await?;
+
+    let db_path = state.db_path.clone();
+    let (pid, ex, sid) = (project_id.clone(), extractor.clone(), source_id.clone());
+    let source = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<RemoteSource>> {
+        let conn = Connection::open(&db_path)?;
+        project_remote_source(&conn, &pid, &ex, &sid)
+    })
+    .await
+    .context("refresh_remote_source task failed")??
+    .ok_or_else(|| AppError::NotFound("remote source not found in this project".to_string()))?;
+
+    let cookies = ytdlp_cookies(&state.data_dir, req.cookies_from_browser.as_ref());
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({ "extractor": &extractor, "id": &source_id, "url": &source.webpage_url });
+
+    let ytdlp_cmd = state.ytdlp_cmd.clone();
+    dispatch_run(&state, &project_id, "remote_source_refresh", request_json, wait, move |job| {
+        Ok(run_remote_source_refresh(job, &ytdlp_cmd, source, cookies.as_ref())?)
+    })
+    .await
+}
+
+fn run_remote_source_refresh(
+    job: &JobCtx,
+    ytdlp_cmd: &str,
+    old: RemoteSource,
+    cookies: Option<&YtdlpCookies>,
+) -> anyhow::Result<RefreshRemoteSourceResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+    let now = now_ms();
+
+    let resolved = resolve_remote_info(job, ytdlp_cmd, &old.webpage_url, &["--no-playlist"], cookies);
+    let (mut fresh, partial, error) = match resolved {
+        Ok(ImportRemoteMediaOutcome::Ok(info_json)) => match RemoteSource::from_info(&info_json, &old.webpage_url, now) {
+            // Another extractor/id means the URL now points elsewhere; keep the catalog key.
+            Some(mut s) => {
+                s.extractor = old.extractor.clone();
+                s.id = old.id.clone();
+                (s, false, None)
+            }
+            None => anyhow::bail!("yt-dlp returned no video id for {}", old.webpage_url),
+        },
+        Ok(ImportRemoteMediaOutcome::PreconditionFailed(msg)) => anyhow::bail!(msg),
+        Err(err) => {
+            job.cancel.check()?;
+            let msg = truncate_chars(&format!("{err:#}"), 500);
+            let Some(reason) = unavailable_reason(&msg) else {
+                return Err(err.context(format!("failed to refresh {}", old.webpage_url)));
+            };
+            let mut s = old.clone();
+            s.availability = Some(reason.to_string());
+            (s, true, Some(msg))
+        }
+    };
+    fresh.updated_at_ms = now;
+    fresh.refreshed_at_ms = Some(now);
+    let (source, changes) = upsert_remote_source(conn, fresh, partial)?;
+
+    let went_away = changes
+        .iter()
+        .any(|c| c.field == "availability" && !matches!(c.new.as_str(), Some("public") | Some("unlisted")));
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, ?3, 'remote_source_refreshed', ?4)",
+        params![
+            project_id,
+            now,
+            if went_away || error.is_some() { "warn" } else { "info" },
+            serde_json::json!({
+                "extractor": &source.extractor,
+                "id": &source.id,
+                "changes": &changes,
+                "error": &error,
+            })
+            .to_string()
+        ],
+    )?;
+
+    Ok(RefreshRemoteSourceResponse { source, changes, error })
+}
+
+/// Bucket width of the danmaku heatmap.
+const DANMAKU_HEATMAP_BUCKET_S: f64 = 1.0;
+/// Longest timeline the heatmap covers; comments past it (or past the known duration) are dropped.
+const DANMAKU_HEATMAP_MAX_S: f64 = 24.0 * 60.0 * 60.0;
+/// Peaks are ranked on a sliding window so a burst spread over a few seconds still counts as one moment.
+const DANMAKU_PEAK_WINDOW_S: usize = 5;
+const DANMAKU_PEAK_SEPARATION_S: f64 = 10.0;
+const DANMAKU_TOP_PEAKS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TimedTextFormat {
+    Srt,
+    Vtt,
+    Ass,
+    BilibiliXml,
+}
+
+impl TimedTextFormat {
+    fn from_ext(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "ass" | "ssa" => Some(Self::Ass),
+            "xml" => Some(Self::BilibiliXml),
+            _ => None,
+        }
+    }
+
+    /// Danmaku are viewer comments pinned to a moment; everything else is a subtitle track.
+    fn kind(self) -> &'static str {
+        match self {
+            Self::BilibiliXml => "danmaku",
+            _ => "subtitle",
+        }
+    }
+
+    fn parse(self, text: &str) -> Vec<TimedTextCue> {
+        let mut cues = match self {
+            Self::Srt | Self::Vtt => parse_srt_or_vtt(text),
+            Self::Ass => parse_ass(text),
+            Self::BilibiliXml => parse_bilibili_danmaku(text),
+        };
+        cues.retain(|c| !c.text.is_empty() && c.start_s.is_finite() && c.end_s >= c.start_s);
+        cues.sort_by(|a, b| a.start_s.total_cmp(&b.start_s));
+        cues
+    }
+}
+
+/// One normalized cue. Danmaku have no display duration, so `end_s == start_s`.
+#[derive(Debug, Clone, Serialize)]
+struct TimedTextCue {
+    start_s: f64,
+    end_s: f64,
+    text: String,
+}
+
+/// Drops `<i>`/`<c.yellow>`/`<00:00:01.000>` style markup from SRT and VTT cue text.
+fn strip_angle_tags(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_tag = false;
+    for ch in s.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(ch),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn decode_xml_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let Some(end) = rest.find(';').filter(|e| *e <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// SRT and WebVTT share the block layout: an optional id line, a `start --> end` timing line
+/// and text lines up to the next blank line. SRT uses `,` for milliseconds.
+fn parse_srt_or_vtt(text: &str) -> Vec<TimedTextCue> {
+    let mut cues: Vec<TimedTextCue> = Vec::new();
+    let mut current: Option<(f64, f64, Vec<String>)> = None;
+    let flush = |current: &mut Option<(f64, f64, Vec<String>)>, cues: &mut Vec<TimedTextCue>| {
+        if let Some((start_s, end_s, lines)) = current.take() {
+            let text = lines.join("\n").trim().to_string();
+            cues.push(TimedTextCue { start_s, end_s, text });
+        }
+    };
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            flush(&mut current, &mut cues);
+            continue;
+        }
+        if let Some((a, b)) = line.split_once("-->") {
+            flush(&mut current, &mut cues);
+            // VTT cue settings (`align:start position:10%`) follow the end time.
+            let b = b.split_whitespace().next().unwrap_or("");
+            let ts = |s: &str| parse_vtt_timestamp(&s.trim().replace(',', "."));
+            current = ts(a).zip(ts(b)).map(|(start_s, end_s)| (start_s, end_s, Vec::new()));
+            continue;
+        }
+        if let Some((_, _, lines)) = current.as_mut() {
+            let cleaned = strip_angle_tags(line);
+            let cleaned = decode_xml_entities(cleaned.trim());
+            if !cleaned.is_empty() {
+                lines.push(cleaned);
+            }
+        }
+    }
+    flush(&mut current, &mut cues);
+    cues
+}
+
+/// Reads `Dialogue:` lines of the `[Events]` section, using its `Format:` line to find the
+/// Start/End/Text columns (Text is last and may itself contain commas).
+fn parse_ass(text: &str) -> Vec<TimedTextCue> {
+    let mut cues: Vec<TimedTextCue> = Vec::new();
+    let mut in_events = false;
+    let mut columns: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(fmt) = line.strip_prefix("Format:") {
+            columns = fmt.split(',').map(|c| c.trim().to_ascii_lowercase()).collect();
+            continue;
+        }
+        let Some(rest) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+        if columns.is_empty() {
+            columns = ["layer", "start", "end", "style", "name", "marginl", "marginr", "marginv", "effect", "text"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect();
+        }
+        let fields: Vec<&str> = rest.splitn(columns.len(), ',').collect();
+        let field = |name: &str| columns.iter().position(|c| c == name).and_then(|i| fields.get(i)).copied();
+        let (Some(start), Some(end), Some(raw)) = (field("start"), field("end"), field("text")) else {
+            continue;
+        };
+        let (Some(start_s), Some(end_s)) = (parse_vtt_timestamp(start), parse_vtt_timestamp(end)) else {
+            continue;
+        };
+        // `{\pos(1,2)}` override blocks carry styling only; `\N` is a hard line break.
+        let mut text = String::new();
+        let mut depth = 0usize;
+        for ch in raw.chars() {
+            match ch {
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                _ if depth == 0 => text.push(ch),
+                _ => {}
+            }
+        }
+        let text = text.replace("\\N", "\n").replace("\\n", "\n").replace("\\h", " ");
+        cues.push(TimedTextCue { start_s, end_s, text: text.trim().to_string() });
+    }
+    cues
+}
+
+/// Bilibili danmaku XML: `<d p="time,mode,size,color,sent_at,pool,user,id">text</d>`. Only the
+/// first `p` field (seconds into the video) matters here.
+fn parse_bilibili_danmaku(text: &str) -> Vec<TimedTextCue> {
+    let mut cues: Vec<TimedTextCue> = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("<d ") {
+        rest = &rest[open + 3..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attrs = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+        let Some(close) = rest.find("</d>") else {
+            break;
+        };
+        let body = &rest[..close];
+        rest = &rest[close + 4..];
+
+        let p = attrs
+            .split_once("p=\"")
+            .and_then(|(_, v)| v.split('"').next())
+            .unwrap_or("");
+        let Some(start_s) = p.split(',').next().and_then(|t| t.trim().parse::<f64>().ok()) else {
+            continue;
+        };
+        let text = decode_xml_entities(body.trim());
+        cues.push(TimedTextCue { start_s, end_s: start_s, text });
+    }
+    cues
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DanmakuPeak {
+    rank: usize,
+    /// Center of the densest window.
+    t_s: f64,
+    /// Comments inside the `DANMAKU_PEAK_WINDOW_S` window around `t_s`.
+    count: u32,
+}
+
+/// Per-second comment counts plus the densest moments, as stored in `danmaku_heatmap.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DanmakuHeatmap {
+    version: u32,
+    bucket_s: f64,
+    total: usize,
+    max: u32,
+    counts: Vec<u32>,
+    peaks: Vec<DanmakuPeak>,
+}
+
+fn danmaku_heatmap(cues: &[TimedTextCue], duration_s: Option<f64>) -> DanmakuHeatmap {
+    // Cue times come straight from the fetched XML, so they only size the timeline within bounds.
+    let duration_s = duration_s
+        .filter(|d| d.is_finite() && *d > 0.0)
+        .map(|d| d.min(DANMAKU_HEATMAP_MAX_S));
+    let limit = duration_s.unwrap_or(DANMAKU_HEATMAP_MAX_S);
+    let in_range: Vec<f64> = cues
+        .iter()
+        .map(|c| c.start_s)
+        .filter(|t| (0.0..=limit).contains(t))
+        .collect();
+    let span = duration_s.unwrap_or_else(|| in_range.iter().copied().fold(0.0_f64, f64::max));
+    let buckets = (span / DANMAKU_HEATMAP_BUCKET_S).floor() as usize + 1;
+    let mut counts = vec![0u32; buckets];
+    for t in &in_range {
+        let i = ((t / DANMAKU_HEATMAP_BUCKET_S) as usize).min(buckets - 1);
+        counts[i] += 1;
+    }
+
+    let half = DANMAKU_PEAK_WINDOW_S / 2;
+    let mut windows: Vec<(usize, u32)> = (0..buckets)
+        .map(|i| {
+            let lo = i.saturating_sub(half);
+            let hi = (i + half + 1).min(buckets);
+            (i, counts[lo..hi].iter().sum())
+        })
+        .filter(|(_, n)| *n > 0)
+        .collect();
+    // Ties go to the window whose own second is busiest, so the clip centers on the burst.
+    windows.sort_by(|a, b| b.1.cmp(&a.1).then(counts[b.0].cmp(&counts[a.0])).then(a.0.cmp(&b.0)));
+    let mut peaks: Vec<DanmakuPeak> = Vec::new();
+    for (i, count) in windows {
+        let t_s = (i as f64 + 0.5) * DANMAKU_HEATMAP_BUCKET_S;
+        if peaks.iter().any(|p| (p.t_s - t_s).abs() < DANMAKU_PEAK_SEPARATION_S) {
+            continue;
+        }
+        peaks.push(DanmakuPeak { rank: peaks.len() + 1, t_s, count });
+        if peaks.len() == DANMAKU_TOP_PEAKS {
+            break;
+        }
+    }
+
+    DanmakuHeatmap {
+        version: 1,
+        bucket_s: DANMAKU_HEATMAP_BUCKET_S,
+        total: in_range.len(),
+        max: counts.iter().copied().max().unwrap_or(0),
+        counts,
+        peaks,
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct TimedTextTrack {
+    lang: String,
+    format: TimedTextFormat,
+    kind: &'static str,
+    cues: usize,
+    artifact: ArtifactResponse,
+}
+
+#[derive(Serialize, Clone, Default)]
+struct RemoteTimedText {
+    tracks: Vec<TimedTextTrack>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    danmaku_heatmap: Option<ArtifactResponse>,
+}
+
+/// Fetches every subtitle track yt-dlp offers (on bilibili this includes the danmaku XML) and
+/// normalizes each into a `timed_text` JSON artifact; danmaku also get a `danmaku_heatmap`.
+fn fetch_remote_timed_text(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    ytdlp_cmd: &str,
+    url: &str,
+    file_base: &str,
+    duration_s: Option<f64>,
+    cookies: Option<&YtdlpCookies>,
+) -> anyhow::Result<RemoteTimedText> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    let subs_dir_abs = data_dir.join(format!("projects/{project_id}/media/remote/subs"));
+    std::fs::create_dir_all(&subs_dir_abs)?;
+    let out_template = subs_dir_abs.join(format!("{file_base}.%(ext)s")).display().to_string();
+
+    let mut cmd = Command::new(ytdlp_cmd);
+    cmd.args([
+        "--skip-download",
+        "--write-subs",
+        "--sub-langs",
+        "all",
+        "--no-playlist",
+        "--restrict-filenames",
+        "--no-warnings",
+        "-o",
+        &out_template,
+    ]);
+    if let Some(c) = cookies {
+        c.apply(&mut cmd);
+    }
+    cmd.arg(url);
+    job.report("subtitles", Progress::default());
+    run_cmd_streaming(&mut cmd, &job.cancel, |_line| {})?;
+
+    // yt-dlp names them `{file_base}.{lang}.{ext}`.
+    let prefix = format!("{file_base}.");
+    let mut files: Vec<(String, TimedTextFormat, PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(&subs_dir_abs)?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some((lang, ext)) = name.strip_prefix(&prefix).and_then(|r| r.rsplit_once('.')) else {
+            continue;
+        };
+        if let Some(format) = TimedTextFormat::from_ext(ext) {
+            files.push((lang.to_string(), format, entry.path()));
+        }
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let out_dir_rel = format!("projects/{project_id}/out/timed_text");
+    std::fs::create_dir_all(data_dir.join(&out_dir_rel))?;
+
+    let mut out = RemoteTimedText::default();
+    let mut danmaku: Vec<TimedTextCue> = Vec::new();
+    for (lang, format, abs) in files {
+        let bytes = std::fs::read(&abs)?;
+        let text = String::from_utf8_lossy(&bytes);
+        let cues = format.parse(text.trim_start_matches('\u{feff}'));
+        let source_rel = abs.strip_prefix(data_dir).unwrap_or(&abs).display().to_string();
+        let rel = format!("{out_dir_rel}/{}.{}.json", file_base, sanitize_file_name(&lang));
+        let doc = serde_json::json!({
+            "version": 1,
+            "lang": &lang,
+            "format": format,
+            "kind": format.kind(),
+            "source_path": &source_rel,
+            "cues": &cues,
+        });
+        std::fs::write(data_dir.join(&rel), serde_json::to_vec_pretty(&doc)?)?;
+        let artifact = ensure_artifact(conn, project_id, "timed_text", &rel, now_ms())?;
+        out.tracks.push(TimedTextTrack {
+            lang,
+            format,
+            kind: format.kind(),
+            cues: cues.len(),
+            artifact,
+        });
+        if format == TimedTextFormat::BilibiliXml {
+            danmaku.extend(cues);
+        }
+    }
+
+    if !danmaku.is_empty() {
+        let heatmap = danmaku_heatmap(&danmaku, duration_s);
+        let rel = format!("{out_dir_rel}/{file_base}.danmaku_heatmap.json");
+        std::fs::write(data_dir.join(&rel), serde_json::to_vec_pretty(&heatmap)?)?;
+        let artifact = ensure_artifact(conn, project_id, "danmaku_heatmap", &rel, now_ms())?;
+        out.danmaku_heatmap = Some(artifact);
+    }
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'remote_timed_text', ?3)",
+        params![
+            project_id,
+            now_ms(),
+            serde_json::json!({
+                "url": url,
+                "tracks": out.tracks.iter().map(|t| serde_json::json!({ "lang": &t.lang, "kind": t.kind, "cues": t.cues })).collect::<Vec<_>>(),
+                "danmaku": danmaku.len(),
+            })
+            .to_string()
+        ],
+    )?;
+    Ok(out)
+}
+
+/// Peak moments from the `danmaku_heatmap` linked to an input video, densest first.
+fn load_danmaku_peaks(conn: &Connection, data_dir: &FsPath, project_id: &str, input_artifact_id: &str) -> anyhow::Result<Option<Vec<DanmakuPeak>>> {
+    let Some(rel) = conn
+        .query_row(
+            "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'danmaku_heatmap' AND source_artifact_id = ?2\n             ORDER BY created_at_ms DESC LIMIT 1",
+            params![project_id, input_artifact_id],
+            |r| r.get::<_, String>(0),
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let heatmap: DanmakuHeatmap = serde_json::from_slice(&std::fs::read(data_dir.join(&rel))?)
+        .with_context(|| format!("failed to read {rel}"))?;
+    Ok(Some(heatmap.peaks))
+}
+
+async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        ok: true,
+        service: "toolserver",
+        data_dir: state.data_dir.display().to_string(),
+        ffmpeg: state.ffmpeg,
+        ffprobe: state.ffprobe,
+        ytdlp: state.ytdlp,
+        db_path: state.db_path.display().to_string(),
+    })
+}
+
+#[derive(Serialize)]
+struct ProfileResponse {
+    profile: ProfileMemory,
+    profile_rel_path: String,
+    profile_abs_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FeedbackItem {
+    url: String,
+    kind: String,
+    rating: i64,
+    anchor: bool,
+    created_at_ms: i64,
+    updated_at_ms: i64,
+}
+
+#[derive(Serialize)]
+struct ProjectFeedbackResponse {
+    ok: bool,
+    project_id: String,
+    items: Vec<FeedbackItem>,
+}
+
+#[derive(Deserialize)]
+struct UpsertProjectFeedbackRequest {
+    url: String,
+    kind: Option<String>,
+    action: String, // like | dislike | clear | anchor | unanchor
+}
+
+async fn get_profile(State(state): State<AppState>) -> AppResult<Json<ProfileResponse>> {
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+
+    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<ProfileResponse> {
+        let conn = Connection::open(&db_path)?;
+        let mut profile = load_profile(&conn)?;
+        if profile.prompt.trim().is_empty() {
+            profile.prompt = build_profile_prompt(&profile);
+        }
+        Ok(ProfileResponse {
+            profile,
+            profile_rel_path: profile_file_name().to_string(),
+            profile_abs_path: data_dir.join(profile_file_name()).display().to_string(),
+        })
+    })
+    .await
+    .context("get_profile task failed")??;
+
+    Ok(Json(resp))
+}
+
+async fn reset_profile(State(state): State<AppState>) -> AppResult<Json<ProfileResponse>> {
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+
+    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<ProfileResponse> {
+        let conn = Connection::open(&db_path)?;
+        conn.execute("DELETE FROM profile WHERE id = 1", [])?;
+
+        let file_abs = data_dir.join(profile_file_name());
+        if file_abs.exists() {
+            let _ = std::fs::remove_file(&file_abs);
+        }
+
+        Ok(ProfileResponse {
+            profile: ProfileMemory::default(),
+            profile_rel_path: profile_file_name().to_string(),
+            profile_abs_path: file_abs.display().to_string(),
+        })
+    })
+    .await
+    .context("reset_profile task failed")??;
+
+    Ok(Json(resp))
+}
+
+#[derive(Deserialize)]
+struct UpdateProfileRequest {
+    prompt: Option<String>,
+}
+
+async fn update_profile(
+    State(state): State<AppState>,
+    Json(req): Json<UpdateProfileRequest>,
+) -> AppResult<Json<ProfileResponse>> {
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let prompt = req.prompt.unwrap_or_default();
+
+    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<ProfileResponse> {
+        let conn = Connection::open(&db_path)?;
+        let mut profile = load_profile(&conn)?;
+        profile.user_prompt = truncate_with_ellipsis(prompt.trim(), 4000);
+        profile.updated_at_ms = now_ms();
+        profile.prompt = build_profile_prompt(&profile);
+        save_profile(&conn, &data_dir, &profile)?;
+        Ok(ProfileResponse {
+            profile,
+            profile_rel_path: profile_file_name().to_string(),
+            profile_abs_path: data_dir.join(profile_file_name()).display().to_string(),
+        })
+    })
+    .await
+    .context("update_profile task failed")??;
+
+    Ok(Json(resp))
+}
+
+#[derive(Deserialize)]
+struct FfmpegPipelineRequest {
+    input_video_artifact_id: String,
+    sampling: Option<ClipSamplingSpec>,
+    wait: Option<bool>,
+}
+
+const DEFAULT_CLIP_COUNT: u32 = 3;
+const DEFAULT_CLIP_LEN_S: f64 = 6.0;
+const MAX_PIPELINE_CLIPS: usize = 50;
+const MAX_CLIP_LEN_S: f64 = 600.0;
+
+/// Which clips the pipeline cuts. Explicit `ranges` win over `shots`, then `danmaku_peaks`, then
+/// `audio_peaks`, then `count`; with none of them we keep the historical start/mid/end sampling
+/// (3 clips of 6s).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct ClipSamplingSpec {
+    /// Number of clips: evenly spaced over the video, or evenly picked among detected shots.
+    count: Option<u32>,
+    /// Length of each clip in seconds (shot clips are cut short at the shot's end).
+    clip_len_s: Option<f64>,
+    /// Explicit `[start_s, end_s]` ranges, cut in the given order.
+    ranges: Option<Vec<[f64; 2]>>,
+    /// Cut one clip from the start of each detected shot (see `POST /projects/{id}/pipeline/shots`).
+    shots: Option<ShotDetectParams>,
+    /// Center clips on the loudest moments from `audio_analysis.json` (`count` of them, default 3).
+    audio_peaks: Option<bool>,
+    /// Center clips on the busiest danmaku moments from the input's `danmaku_heatmap` (fetched
+    /// with `subtitles: true` on remote import).
+    danmaku_peaks: Option<bool>,
+}
+
+impl ClipSamplingSpec {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(count) = self.count {
+            if count == 0 || count as usize > MAX_PIPELINE_CLIPS {
+                return Err(format!("sampling.count must be between 1 and {MAX_PIPELINE_CLIPS}"));
+            }
+        }
+        if let Some(len) = self.clip_len_s {
+            if !(len.is_finite() && len > 0.0 && len <= MAX_CLIP_LEN_S) {
+                return Err(format!("sampling.clip_len_s must be > 0 and <= {MAX_CLIP_LEN_S}"));
+            }
+        }
+        if let Some(shots) = &self.shots {
+            shots.validate()?;
+        }
+        if let Some(ranges) = &self.ranges {
+            if ranges.len() > MAX_PIPELINE_CLIPS {
+                return Err(format!("sampling.ranges allows at most {MAX_PIPELINE_CLIPS} ranges"));
+            }
+            for [start, end] in ranges {
+                if !(start.is_finite() && end.is_finite() && *start >= 0.0 && end > start) {
+                    return Err(format!("invalid sampling range [{start}, {end}]"));
+                }
+                if end - start > MAX_CLIP_LEN_S {
+                    return Err(format!("sampling range [{start}, {end}] is longer than {MAX_CLIP_LEN_S}s"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves the spec against the probed duration (and detected shots, when sampling by shot)
+    /// into ordered, de-duplicated `(start, end)` ranges.
+    /// `peaks` are moment centers, strongest first: danmaku peaks when `danmaku_peaks` is set,
+    /// otherwise audio peaks.
+    fn clip_ranges(&self, duration_s: f64, shots: Option<&[Shot]>, peaks: &[f64]) -> anyhow::Result<Vec<(f64, f64)>> {
+        let mut out: Vec<(f64, f64)> = Vec::new();
+        let use_peaks =
+            (self.audio_peaks.unwrap_or(false) || self.danmaku_peaks.unwrap_or(false)) && !peaks.is_empty();
+        match (self.ranges.as_ref().filter(|r| !r.is_empty()), shots) {
+            (Some(ranges), _) => {
+                for [start, end] in ranges {
+                    let (start, end) = if duration_s > 0.0 {
+                        (*start, end.min(duration_s))
+                    } else {
+                        (*start, *end)
+                    };
+                    if end <= start {
+                        anyhow::bail!("clip range starts after the end of the video ({duration_s:.3}s)");
+                    }
+                    out.push((start, end));
+                }
+            }
+            (None, Some(shots)) => {
+                let clip_len_s = self.clip_len_s.unwrap_or(DEFAULT_CLIP_LEN_S);
+                let count = self
+                    .count
+                    .map(|c| c as usize)
+                    .unwrap_or(MAX_PIPELINE_CLIPS)
+                    .min(shots.len());
+                for i in 0..count {
+                    // Evenly pick `count` shots so a long shot list still spans the whole video.
+                    let shot = &shots[i * shots.len() / count];
+                    out.push((shot.start_s, shot.end_s.min(shot.start_s + clip_len_s)));
+                }
+            }
+            (None, None) if use_peaks => {
+                let clip_len_s = self.clip_len_s.unwrap_or(DEFAULT_CLIP_LEN_S);
+                let count = self.count.unwrap_or(DEFAULT_CLIP_COUNT) as usize;
+                let latest_start = (duration_s - clip_len_s).max(0.0);
+                let mut picked: Vec<f64> = peaks.iter().copied().take(count).collect();
+                picked.sort_by(f64::total_cmp);
+                for t_s in picked {
+                    let start = (t_s - clip_len_s / 2.0).clamp(0.0, latest_start);
+                    let end = if duration_s > 0.0 {
+                        (start + clip_len_s).min(duration_s)
+                    } else {
+                        start + clip_len_s
+                    };
+                    out.push((start, end));
+                }
+            }
+            (None, None) => {
+                let count = self.count.unwrap_or(DEFAULT_CLIP_COUNT) as usize;
+                let clip_len_s = self.clip_len_s.unwrap_or(DEFAULT_CLIP_LEN_S);
+                let span = (duration_s - clip_len_s).max(0.0);
+                for i in 0..count {
+                    let start = if count == 1 {
+                        span / 2.0
+                    } else {
+                        span * i as f64 / (count - 1) as f64
+                    };
+                    out.push((start, start + clip_len_s));
+                }
+            }
+        }
+
+        // Ranges that map to the same cache key would produce the same file twice.
+        let mut seen: HashSet<String> = HashSet::new();
+        out.retain(|(start, end)| seen.insert(clip_cache_key(*start, *end)));
+        Ok(out)
+    }
+}
+
+/// Cache key of a clip inside the input's fingerprint folder. Zero-padded so that file names sort by time.
+fn clip_cache_key(start_s: f64, end_s: f64) -> String {
+    let ms = |s: f64| (s * 1000.0).round() as u64;
+    format!("{:09}_{:09}", ms(start_s), ms(end_s))
+}
+
+#[derive(Serialize, Clone)]
+struct PipelineClip {
+    index: usize,
+    start_s: f64,
+    end_s: f64,
+    #[serde(flatten)]
+    artifact: ArtifactResponse,
+    thumbnail: ArtifactResponse,
+}
+
+#[derive(Serialize)]
+struct FfmpegPipelineResponse {
+    input_video_artifact_id: String,
+    fingerprint: String,
+    metadata: ArtifactResponse,
+    clips: Vec<PipelineClip>,
+    audio: ArtifactResponse,
+    /// `audio_analysis.json`: loudness curve, silence/speech segments and peak moments.
+    audio_analysis: ArtifactResponse,
+    thumbnails: Vec<ArtifactResponse>,
+    contact_sheet: ArtifactResponse,
+    sprite: ArtifactResponse,
+    /// WebVTT thumbnails track for `sprite`.
+    sprite_vtt: ArtifactResponse,
+    /// `shots_json` artifact the clips were picked from, when sampling by shot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shots: Option<ArtifactResponse>,
+    /// Non-blocking findings from the input's probe (e.g. codecs browsers cannot play).
+    warnings: Vec<ProbeWarning>,
+}
+
+/// Paths of `kind` artifacts produced by the latest successful pipeline run, in clip order.
+///
+/// Projects processed before runs were tracked fall back to the newest artifact of each of the
+/// fixed `legacy_kinds` (start/mid/end).
+fn latest_pipeline_paths(conn: &Connection, project_id: &str, kind: &str, legacy_kinds: &[&str]) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.path FROM run_artifacts ra JOIN artifacts a ON a.id = ra.artifact_id\n         WHERE ra.run_id = (\n           SELECT id FROM runs WHERE project_id = ?1 AND kind = 'ffmpeg_pipeline' AND status = 'succeeded'\n           ORDER BY finished_at_ms DESC LIMIT 1\n         ) AND a.kind = ?2\n         ORDER BY a.path ASC",
+    )?;
+    let rows = stmt.query_map(params![project_id, kind], |r| r.get::<_, String>(0))?;
+    let paths: Vec<String> = rows.filter_map(Result::ok).collect();
+    if !paths.is_empty() {
+        return Ok(paths);
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    for legacy in legacy_kinds {
+        if let Some(p) = conn
+            .query_row(
+                "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = ?2 ORDER BY created_at_ms DESC LIMIT 1",
+                params![project_id, legacy],
+                |r| r.get::<_, String>(0),
+            )
+            .optional()?
+        {
+            out.push(p);
+        }
+    }
+    Ok(out)
+}
+
+/// Contact sheet, sprite and sprite track of the latest successful pipeline run.
+fn latest_pipeline_preview_paths(conn: &Connection, project_id: &str) -> anyhow::Result<Vec<String>> {
+    let mut out: Vec<String> = Vec::new();
+    for kind in ["contact_sheet", "thumb_sprite", "thumb_sprite_vtt"] {
+        out.extend(latest_pipeline_paths(conn, project_id, kind, &[])?);
+    }
+    Ok(out)
+}
+
+/// Cheap `{size}_{mtime_ms}` stamp, used only to notice that a hashed file changed on disk.
+fn file_stat_key(path: &FsPath) -> anyhow::Result<String> {
+    let meta = std::fs::metadata(path)?;
+    let size = meta.len();
+    let mtime_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Ok(format!("{size}_{mtime_ms}"))
+}
+
+fn hex_lower(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Streaming SHA-256 of a file, hex encoded.
+fn sha256_file(path: &FsPath) -> anyhow::Result<String> {
+    let mut f = std::fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = f.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex_lower(&hasher.finalize()))
+}
+
+fn store_content_hash(conn: &Connection, artifact_id: &str, hash: &str, stat: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "UPDATE artifacts SET content_hash = ?1, content_hash_stat = ?2 WHERE id = ?3",
+        params![hash, stat, artifact_id],
+    )?;
+    Ok(())
+}
+
+/// Content hash of an artifact's file, which keys `out/ffmpeg/{fingerprint}` caches.
+///
+/// The hash is computed at import and stored on the artifact row; it is only recomputed when
+/// the file's size/mtime no longer match (legacy rows, edited files, data dirs copied between
+/// machines).
+fn artifact_content_hash(conn: &Connection, artifact_id: &str, abs: &FsPath) -> anyhow::Result<String> {
+    let stat = file_stat_key(abs)?;
+    let stored: Option<(Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT content_hash, content_hash_stat FROM artifacts WHERE id = ?1",
+            [artifact_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+    if let Some((Some(hash), Some(stored_stat))) = stored {
+        if stored_stat == stat {
+            return Ok(hash);
+        }
+    }
+
+    let hash = sha256_file(abs)?;
+    store_content_hash(conn, artifact_id, &hash, &stat)?;
+    Ok(hash)
+}
+
+fn ensure_artifact(conn: &Connection, project_id: &str, kind: &str, path: &str, created_at_ms: i64) -> anyhow::Result<ArtifactResponse> {
+    if let Some(existing) = conn
+        .query_row(
+            "SELECT id, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = ?2 AND path = ?3 LIMIT 1",
+            params![project_id, kind, path],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .optional()?
+    {
+        return Ok(ArtifactResponse {
+            id: existing.0,
+            project_id: project_id.to_string(),
+            kind: kind.to_string(),
+            path: path.to_string(),
+            created_at_ms: existing.1,
+        });
+    }
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO artifacts (id, project_id, kind, path, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![&id, project_id, kind, path, created_at_ms],
+    )?;
+    Ok(ArtifactResponse {
+        id,
+        project_id: project_id.to_string(),
+        kind: kind.to_string(),
+        path: path.to_string(),
+        created_at_ms,
+    })
+}
+
+/// Cancellation handle shared between a job and `POST /projects/{id}/runs/{run_id}/cancel`.
+///
+/// Children spawned through `run_cmd_streaming` register their pid here so a cancel can kill
+/// the whole process tree (yt-dlp spawns ffmpeg for merging, for example).
+#[derive(Default)]
+struct RunCancel {
+    cancelled: AtomicBool,
+    children: Mutex<Vec<u32>>,
+}
+
+impl RunCancel {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            anyhow::bail!("run cancelled");
+        }
+        Ok(())
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        let children = self.children.lock().unwrap_or_else(|e| e.into_inner());
+        for pid in children.iter() {
+            kill_process_tree(*pid);
+        }
+    }
+
+    fn register_child(&self, pid: u32) {
+        let mut children = self.children.lock().unwrap_or_else(|e| e.into_inner());
+        children.push(pid);
+        // A cancel that raced with the spawn would not have seen this pid.
+        if self.is_cancelled() {
+            kill_process_tree(pid);
+        }
+    }
+
+    fn unregister_child(&self, pid: u32) {
+        let mut children = self.children.lock().unwrap_or_else(|e| e.into_inner());
+        children.retain(|p| *p != pid);
+    }
+}
+
+/// Puts the child in its own process group so `kill_process_tree` reaches its descendants too.
+fn isolate_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = cmd;
+    }
+}
+
+fn kill_process_tree(pid: u32) {
+    #[cfg(unix)]
+    {
+        // The child leads its own process group (see `isolate_process_group`).
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Runs `cmd` to completion, handing every stdout line to `on_line` while the child is running.
+///
+/// The child is killed (with its descendants) when `cancel` fires; the call then fails with
+/// "run cancelled".
+fn run_cmd_streaming(cmd: &mut Command, cancel: &RunCancel, mut on_line: impl FnMut(&str)) -> anyhow::Result<()> {
+    cancel.check()?;
+    isolate_process_group(cmd);
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let pid = child.id();
+    cancel.register_child(pid);
+    let status = wait_streaming(&mut child, &mut on_line);
+    cancel.unregister_child(pid);
+    let (status, stderr) = status?;
+    cancel.check()?;
+    if status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&stderr);
+    anyhow::bail!("command failed: {stderr}");
+}
+
+fn wait_streaming(
+    child: &mut std::process::Child,
+    on_line: &mut impl FnMut(&str),
+) -> anyhow::Result<(std::process::ExitStatus, Vec<u8>)> {
+    let mut stderr_pipe = child.stderr.take().context("failed to capture stderr")?;
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr_pipe.read_to_end(&mut buf);
+        buf
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = std::io::BufReader::new(stdout);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf);
+            on_line(line.trim_end_matches(['\r', '\n']));
+        }
+    }
+
+    let status = child.wait()?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    Ok((status, stderr))
+}
+
+/// Parses one line of ffmpeg's `-progress` output (`out_time_us=…`) or `metadata=print` output
+/// (`frame:… pts_time:…`) into seconds of input processed.
+fn parse_ffmpeg_progress_time(line: &str) -> Option<f64> {
+    if line.starts_with("frame:") {
+        return line
+            .split_whitespace()
+            .find_map(|part| part.strip_prefix("pts_time:"))
+            .and_then(|v| v.parse::<f64>().ok());
+    }
+    let (key, value) = line.split_once('=')?;
+    match key.trim() {
+        // Both keys are microseconds (`out_time_ms` is misnamed upstream).
+        "out_time_us" | "out_time_ms" => value.trim().parse::<f64>().ok().map(|us| us / 1_000_000.0),
+        _ => None,
+    }
+}
+
+const YTDLP_PROGRESS_PREFIX: &str = "vidunpack-progress";
+
+/// Parses a line printed by our yt-dlp `--progress-template` into (downloaded, total, eta_s).
+fn parse_ytdlp_progress_line(line: &str) -> Option<(u64, Option<u64>, Option<f64>)> {
+    let rest = line.trim().strip_prefix(YTDLP_PROGRESS_PREFIX)?;
+    let mut parts = rest.split_whitespace();
+    let num = |s: Option<&str>| s.and_then(|v| v.parse::<f64>().ok()).filter(|v| v.is_finite() && *v >= 0.0);
+    let downloaded = num(parts.next())? as u64;
+    let total = num(parts.next());
+    let total_estimate = num(parts.next());
+    let eta_s = num(parts.next());
+    let total = total.or(total_estimate).map(|v| v as u64).filter(|v| *v > 0);
+    Some((downloaded, total, eta_s))
+}
+
+/// Global ffmpeg args that make it print `key=value` progress blocks on stdout.
+const FFMPEG_PROGRESS_ARGS: [&str; 3] = ["-progress", "pipe:1", "-nostats"];
+
+/// Overall progress of a multi-step ffmpeg job; every step weighs the same.
+struct StepProgress<'a> {
+    job: &'a JobCtx,
+    started: Instant,
+    total_steps: usize,
+    done_steps: usize,
+}
+
+impl<'a> StepProgress<'a> {
+    fn new(job: &'a JobCtx, total_steps: usize) -> Self {
+        Self {
+            job,
+            started: Instant::now(),
+            total_steps: total_steps.max(1),
+            done_steps: 0,
+        }
+    }
+
+    fn report(&self, stage: &str, step_fraction: f64) {
+        let overall = (self.done_steps as f64 + step_fraction.clamp(0.0, 1.0)) / self.total_steps as f64;
+        self.job.report(
+            stage,
+            Progress {
+                percent: Some(overall * 100.0),
+                eta_s: eta_from_fraction(self.started, overall),
+                ..Progress::default()
+            },
+        );
+    }
+
+    /// Marks a cached step as done without running anything.
+    fn skip(&mut self) {
+        self.done_steps += 1;
+    }
+
+    /// Grows the plan once a job learns how much work is left (e.g. the clip count).
+    fn add_steps(&mut self, n: usize) {
+        self.total_steps += n;
+    }
+
+    /// Runs an ffmpeg command that was built with `FFMPEG_PROGRESS_ARGS`, mapping its output time
+    /// against `expected_s` (0 = report on completion only).
+    ///
+    /// `out` is removed if the command fails or is cancelled, so a half-written file is never
+    /// mistaken for a cached result.
+    fn run_ffmpeg(&mut self, stage: &str, cmd: &mut Command, out: &FsPath, expected_s: f64) -> anyhow::Result<()> {
+        let partial = PartialOutput::new(out);
+        self.run_ffmpeg_lines(stage, cmd, expected_s, |_| {})?;
+        partial.keep();
+        Ok(())
+    }
+
+    /// Like `run_ffmpeg`, for commands whose stdout also carries data (e.g. `metadata=print`).
+    /// Every stdout line is forwarded to `on_line`.
+    fn run_ffmpeg_lines(
+        &mut self,
+        stage: &str,
+        cmd: &mut Command,
+        expected_s: f64,
+        mut on_line: impl FnMut(&str),
+    ) -> anyhow::Result<()> {
+        run_cmd_streaming(cmd, &self.job.cancel, |line| {
+            on_line(line);
+            if expected_s <= 0.0 {
+                return;
+            }
+            if let Some(t) = parse_ffmpeg_progress_time(line) {
+                self.report(stage, t / expected_s);
+            }
+        })?;
+        self.done_steps += 1;
+        self.report(stage, 0.0);
+        Ok(())
+    }
+}
+
+/// Deletes a job output on drop unless `keep` was called (the job failed or was cancelled midway).
+struct PartialOutput {
+    path: Option<PathBuf>,
+}
+
+impl PartialOutput {
+    fn new(path: &FsPath) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    fn keep(mut self) {
+        self.path = None;
+    }
+}
+
+impl Drop for PartialOutput {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn eta_from_fraction(started: Instant, fraction: f64) -> Option<f64> {
+    if !(fraction > 0.0 && fraction < 1.0) {
+        return None;
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    Some(elapsed * (1.0 - fraction) / fraction)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProgressEvent {
+    project_id: String,
+    run_id: String,
+    stage: String,
+    percent: Option<f64>,
+    eta_s: Option<f64>,
+    bytes: Option<u64>,
+    total_bytes: Option<u64>,
+    ts_ms: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Progress {
+    percent: Option<f64>,
+    eta_s: Option<f64>,
+    bytes: Option<u64>,
+    total_bytes: Option<u64>,
+}
+
+/// Per-run handle passed to job bodies: the run's DB connection, progress channel and cancel flag.
+struct JobCtx {
+    conn: Connection,
+    project_id: String,
+    run_id: String,
+    progress: broadcast::Sender<ProgressEvent>,
+    last_report: Cell<Option<Instant>>,
+    cancel: Arc<RunCancel>,
+}
+
+impl JobCtx {
+    /// Publishes a progress update to SSE subscribers (throttled; final 100% updates always go out).
+    fn report(&self, stage: &str, p: Progress) {
+        let done = p.percent.is_some_and(|v| v >= 100.0);
+        if let Some(last) = self.last_report.get() {
+            if !done && last.elapsed() < Duration::from_millis(250) {
+                return;
+            }
+        }
+        self.last_report.set(Some(Instant::now()));
+        // No subscribers is fine; progress is best-effort.
+        let _ = self.progress.send(ProgressEvent {
+            project_id: self.project_id.clone(),
+            run_id: self.run_id.clone(),
+            stage: stage.to_string(),
+            percent: p.percent.map(|v| (v.clamp(0.0, 100.0) * 10.0).round() / 10.0),
+            eta_s: p.eta_s.map(|v| v.round()),
+            bytes: p.bytes,
+            total_bytes: p.total_bytes,
+            ts_ms: now_ms(),
+        });
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct RunResponse {
+    id: String,
+    project_id: String,
+    kind: String,
+    status: String,
+    created_at_ms: i64,
+    started_at_ms: Option<i64>,
+    finished_at_ms: Option<i64>,
+    error: Option<String>,
+    request: Option<serde_json::Value>,
+    result: Option<serde_json::Value>,
+    artifacts: Vec<ArtifactResponse>,
+}
+
+#[derive(Serialize)]
+struct RunAcceptedResponse {
+    ok: bool,
+    run: RunResponse,
+}
+
+/// Artifacts produced by a job, linked to its run row once the job succeeds.
+trait RunArtifacts {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse>;
+}
+
+fn load_run(conn: &Connection, project_id: &str, run_id: &str) -> anyhow::Result<Option<RunResponse>> {
+    let row = conn
+        .query_row(
+            "SELECT id, project_id, kind, status, created_at_ms, started_at_ms, finished_at_ms, error, request_json, result_json\n             FROM runs WHERE id = ?1 AND project_id = ?2",
+            params![run_id, project_id],
+            |r| {
+                let request_json: Option<String> = r.get(8)?;
+                let result_json: Option<String> = r.get(9)?;
+                Ok(RunResponse {
+                    id: r.get(0)?,
+                    project_id: r.get(1)?,
+                    kind: r.get(2)?,
+                    status: r.get(3)?,
+                    created_at_ms: r.get(4)?,
+                    started_at_ms: r.get(5)?,
+                    finished_at_ms: r.get(6)?,
+                    error: r.get(7)?,
+                    request: request_json.and_then(|s| serde_json::from_str(&s).ok()),
+                    result: result_json.and_then(|s| serde_json::from_str(&s).ok()),
+                    artifacts: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut run) = row else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.project_id, a.kind, a.path, a.created_at_ms\n         FROM run_artifacts ra JOIN artifacts a ON a.id = ra.artifact_id\n         WHERE ra.run_id = ?1 ORDER BY a.created_at_ms ASC",
+    )?;
+    let rows = stmt.query_map([run_id], |r| {
+        Ok(ArtifactResponse {
+            id: r.get(0)?,
+            project_id: r.get(1)?,
+            kind: r.get(2)?,
+            path: r.get(3)?,
+            created_at_ms: r.get(4)?,
+        })
+    })?;
+    run.artifacts = rows.filter_map(Result::ok).collect();
+    Ok(Some(run))
+}
+
+fn finish_run(
+    conn: &Connection,
+    project_id: &str,
+    run_id: &str,
+    status: &str,
+    error: Option<&str>,
+    result: Option<&serde_json::Value>,
+    artifacts: &[ArtifactResponse],
+) -> anyhow::Result<()> {
+    let finished_at_ms = now_ms();
+    conn.execute(
+        "UPDATE runs SET status = ?1, finished_at_ms = ?2, error = ?3, result_json = ?4 WHERE id = ?5",
+        params![status, finished_at_ms, error, result.map(|v| v.to_string()), run_id],
+    )?;
+    for a in artifacts {
+        conn.execute(
+            "INSERT OR IGNORE INTO run_artifacts (run_id, artifact_id, project_id) VALUES (?1, ?2, ?3)",
+            params![run_id, &a.id, project_id],
+        )?;
+    }
+    if status == "cancelled" {
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'run_cancelled', ?3)",
+            params![
+                project_id,
+                finished_at_ms,
+                serde_json::json!({ "run_id": run_id, "status": status }).to_string()
+            ],
+        )?;
+    } else if status != "succeeded" {
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'run_failed', ?3)",
+            params![
+                project_id,
+                finished_at_ms,
+                serde_json::json!({ "run_id": run_id, "status": status, "error": error }).to_string()
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn execute_run<T, F>(job: JobCtx, body: F) -> AppResult<T>
+where
+    T: Serialize + RunArtifacts,
+    F: FnOnce(&JobCtx) -> AppResult<T>,
+{
+    let (conn, project_id, run_id) = (&job.conn, &job.project_id, &job.run_id);
+    let cancelled = || AppError::Conflict("run cancelled".to_string());
+
+    // Cancelled while still queued.
+    if job.cancel.is_cancelled() {
+        finish_run(conn, project_id, run_id, "cancelled", Some("cancelled before start"), None, &[])?;
+        return Err(cancelled());
+    }
+
+    conn.execute(
+        "UPDATE runs SET status = 'running', started_at_ms = ?1 WHERE id = ?2",
+        params![now_ms(), run_id],
+    )
+    .context("failed to mark run as running")?;
+
+    let outcome = body(&job);
+    match &outcome {
+        Ok(resp) => {
+            let result = serde_json::to_value(resp).context("failed to encode run result")?;
+            finish_run(conn, project_id, run_id, "succeeded", None, Some(&result), &resp.run_artifacts())?;
+        }
+        Err(_) if job.cancel.is_cancelled() => {
+            finish_run(conn, project_id, run_id, "cancelled", Some("cancelled by request"), None, &[])?;
+            return Err(cancelled());
+        }
+        Err(err) => {
+            finish_run(conn, project_id, run_id, "failed", Some(&err.message()), None, &[])?;
+        }
+    }
+    outcome
+}
+
+/// Records a run for `kind` and executes `job` on the blocking pool.
+///
+/// By default the handler answers 202 with the queued run right away and the job keeps going in the
+/// background. With `wait` it blocks and returns the job's own JSON response (plus an `x-run-id`
+/// header) instead.
+async fn dispatch_run<T, F>(
+    state: &AppState,
+    project_id: &str,
+    kind: &'static str,
+    request: serde_json::Value,
+    wait: bool,
+    job: F,
+) -> AppResult<Response>
+where
+    T: Serialize + RunArtifacts + Send + 'static,
+    F: FnOnce(&JobCtx) -> AppResult<T> + Send + 'static,
+{
+    let db_path = state.db_path.clone();
+    let project_id_db = project_id.to_string();
+    let run = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<RunResponse>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_db], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let run_id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO runs (id, project_id, created_at_ms, status, kind, request_json) VALUES (?1, ?2, ?3, 'queued', ?4, ?5)",
+            params![&run_id, &project_id_db, now_ms(), kind, request.to_string()],
+        )?;
+        load_run(&conn, &project_id_db, &run_id)
+    })
+    .await
+    .context("create run task failed")??;
+
+    let Some(run) = run else {
+        return Err(AppError::NotFound("project not found".to_string()));
+    };
+
+    let cancel = Arc::new(RunCancel::default());
+    state
+        .active_runs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(run.id.clone(), cancel.clone());
+
+    let db_path = state.db_path.clone();
+    let project_id_job = project_id.to_string();
+    let run_id = run.id.clone();
+    let progress = state.progress.clone();
+    let active_runs = state.active_runs.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let outcome = Connection::open(&db_path)
+            .context("failed to open sqlite db")
+            .map_err(AppError::from)
+            .and_then(|conn| {
+                let ctx = JobCtx {
+                    conn,
+                    project_id: project_id_job,
+                    run_id: run_id.clone(),
+                    progress,
+                    last_report: Cell::new(None),
+                    cancel,
+                };
+                execute_run(ctx, job)
+            });
+        active_runs.lock().unwrap_or_else(|e| e.into_inner()).remove(&run_id);
+        outcome
+    });
+
+    if !wait {
+        tokio::spawn(async move {
+            match task.await {
+                Ok(Ok(_)) => {}
+                Ok(Err(AppError::Conflict(_))) => tracing::info!("{kind} run cancelled"),
+                Ok(Err(err)) => tracing::warn!("{kind} run failed: {}", err.message()),
+                Err(err) => tracing::warn!("{kind} run task panicked: {err}"),
+            }
+        });
+        return Ok((StatusCode::ACCEPTED, Json(RunAcceptedResponse { ok: true, run })).into_response());
+    }
+
+    let resp = task.await.with_context(|| format!("{kind} task failed"))??;
+    let mut res = Json(resp).into_response();
+    if let Ok(v) = HeaderValue::from_str(&run.id) {
+        res.headers_mut().insert("x-run-id", v);
+    }
+    Ok(res)
+}
+
+async fn list_runs(State(state): State<AppState>, Path(project_id): Path<String>) -> AppResult<Json<Vec<RunResponse>>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let runs = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Vec<RunResponse>>> {
+        let conn = Connection::open(&db_path)?;
+
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let ids: Vec<String> = {
+            let mut stmt =
+                conn.prepare("SELECT id FROM runs WHERE project_id = ?1 ORDER BY created_at_ms DESC LIMIT 100")?;
+            let rows = stmt.query_map([&project_id], |r| r.get::<_, String>(0))?;
+            rows.filter_map(Result::ok).collect()
+        };
+
+        let mut out: Vec<RunResponse> = Vec::new();
+        for id in ids {
+            if let Some(run) = load_run(&conn, &project_id, &id)? {
+                out.push(run);
+            }
+        }
+        Ok(Some(out))
+    })
+    .await
+    .context("list_runs task failed")??;
+
+    match runs {
+        Some(v) => Ok(Json(v)),
+        None => Err(AppError::NotFound("project not found".to_string())),
+    }
+}
+
+async fn get_run(
+    State(state): State<AppState>,
+    Path((project_id, run_id)): Path<(String, String)>,
+) -> AppResult<Json<RunResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    if run_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing run id".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let run = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<RunResponse>> {
+        let conn = Connection::open(&db_path)?;
+        load_run(&conn, &project_id, &run_id)
+    })
+    .await
+    .context("get_run task failed")??;
+
+    match run {
+        Some(r) => Ok(Json(r)),
+        None => Err(AppError::NotFound("run not found".to_string())),
+    }
+}
+
+/// Requests cancellation of a queued or running job; the run turns `cancelled` once its child
+/// processes are killed and partial outputs are removed.
+async fn cancel_run(
+    State(state): State<AppState>,
+    Path((project_id, run_id)): Path<(String, String)>,
+) -> AppResult<Json<RunResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    if run_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing run id".to_string()));
+    }
+
+    let db_path = state.db_path.clone();
+    let active_runs = state.active_runs.clone();
+    let res = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<RunResponse, AppError>> {
+        let conn = Connection::open(&db_path)?;
+        let Some(run) = load_run(&conn, &project_id, &run_id)? else {
+            return Ok(Err(AppError::NotFound("run not found".to_string())));
+        };
+        let handle = active_runs.lock().unwrap_or_else(|e| e.into_inner()).get(&run_id).cloned();
+        let Some(handle) = handle else {
+            return Ok(Err(AppError::Conflict(format!("run is not active (status: {})", run.status))));
+        };
+        handle.cancel();
+
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'run_cancel_requested', ?3)",
+            params![&project_id, now_ms(), serde_json::json!({ "run_id": &run_id, "kind": &run.kind }).to_string()],
+        )?;
+        Ok(Ok(run))
+    })
+    .await
+    .context("cancel_run task failed")??;
+
+    res.map(Json)
+}
+
+#[derive(Deserialize)]
+struct EventStreamQuery {
+    after_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct EventRowResponse {
+    id: i64,
+    project_id: String,
+    ts_ms: i64,
+    level: String,
+    message: String,
+    data: Option<serde_json::Value>,
+}
+
+fn load_events_after(conn: &Connection, project_id: &str, after_id: i64) -> anyhow::Result<Vec<EventRowResponse>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, ts_ms, level, message, data_json FROM events\n         WHERE project_id = ?1 AND id > ?2 ORDER BY id ASC LIMIT 200",
+    )?;
+    let rows = stmt.query_map(params![project_id, after_id], |r| {
+        let data_json: Option<String> = r.get(5)?;
+        Ok(EventRowResponse {
+            id: r.get(0)?,
+            project_id: r.get(1)?,
+            ts_ms: r.get(2)?,
+            level: r.get(3)?,
+            message: r.get(4)?,
+            data: data_json.and_then(|s| serde_json::from_str(&s).ok()),
+        })
+    })?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// SSE feed of a project's `events` rows (event name `event`, id = row id) interleaved with live
+/// job progress (event name `progress`). Resumes after `Last-Event-ID` / `?after_id=` when given,
+/// otherwise starts with events written after the client connected.
+async fn stream_project_events(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> AppResult<Sse<impl futures_util::Stream<Item = Result<Event, Infallible>>>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let resume_after = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .or(query.after_id);
+
+    let db_path = state.db_path.clone();
+    let project_id_check = project_id.clone();
+    let start_after = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<i64>> {
+        let conn = Connection::open(&db_path)?;
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id_check], |_row| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+        if let Some(after) = resume_after {
+            return Ok(Some(after));
+        }
+        let max_id: Option<i64> = conn.query_row(
+            "SELECT MAX(id) FROM events WHERE project_id = ?1",
+            [&project_id_check],
+            |r| r.get(0),
+        )?;
+        Ok(Some(max_id.unwrap_or(0)))
+    })
+    .await
+    .context("stream_project_events preflight failed")??;
+
+    let Some(mut last_id) = start_after else {
+        return Err(AppError::NotFound("project not found".to_string()));
+    };
+
+    let (tx, rx) = mpsc::channel::<Event>(64);
+    let mut progress_rx = state.progress.subscribe();
+    let db_path = state.db_path.clone();
+
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_millis(500));
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {
+                    let db_path = db_path.clone();
+                    let project_id = project_id.clone();
+                    let rows = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<EventRowResponse>> {
+                        let conn = Connection::open(&db_path)?;
+                        load_events_after(&conn, &project_id, last_id)
+                    })
+                    .await;
+                    let rows = match rows {
+                        Ok(Ok(rows)) => rows,
+                        Ok(Err(err)) => {
+                            tracing::warn!("event stream poll failed: {err:#}");
+                            continue;
+                        }
+                        Err(_) => return,
+                    };
+                    for row in rows {
+                        last_id = row.id;
+                        let Ok(ev) = Event::default().event("event").id(row.id.to_string()).json_data(&row) else {
+                            continue;
+                        };
+                        if tx.send(ev).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                msg = progress_rx.recv() => match msg {
+                    Ok(p) if p.project_id == project_id => {
+                        let Ok(ev) = Event::default().event("progress").json_data(&p) else {
+                            continue;
+                        };
+                        if tx.send(ev).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = tx.closed() => return,
+            }
+        }
+    });
+
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|ev| (Ok::<_, Infallible>(ev), rx))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+impl RunArtifacts for FfmpegPipelineResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        let mut out = vec![self.metadata.clone()];
+        out.extend(self.clips.iter().map(|c| c.artifact.clone()));
+        out.push(self.audio.clone());
+        out.push(self.audio_analysis.clone());
+        out.extend(self.thumbnails.iter().cloned());
+        out.extend([self.contact_sheet.clone(), self.sprite.clone(), self.sprite_vtt.clone()]);
+        out.extend(self.shots.iter().cloned());
+        out
+    }
+}
+
+async fn ffmpeg_pipeline(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<FfmpegPipelineRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let input_artifact_id = req.input_video_artifact_id.trim().to_string();
+    if input_artifact_id.is_empty() {
+        return Err(AppError::BadRequest("missing input_video_artifact_id".to_string()));
+    }
+    if !state.ffmpeg || !state.ffprobe {
+        return Err(AppError::PreconditionFailed(
+            "ffmpeg/ffprobe not found on PATH; please install ffmpeg and restart".to_string(),
+        ));
+    }
+
+    let sampling = req.sampling.unwrap_or_default();
+    sampling.validate().map_err(AppError::BadRequest)?;
+
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({ "input_video_artifact_id": &input_artifact_id, "sampling": &sampling });
+
+    let data_dir = state.data_dir.clone();
+    dispatch_run(&state, &project_id, "ffmpeg_pipeline", request_json, wait, move |job| {
+        Ok(run_ffmpeg_pipeline(job, &data_dir, &input_artifact_id, &sampling)?)
+    })
+    .await
+}
+
+/// Raw `ffprobe -print_format json` output. Numbers that ffprobe prints as strings stay strings here;
+/// `MediaProbe::from_ffprobe` turns them into the typed summary.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FfprobeOutput {
+    format: Option<FfprobeFormat>,
+    streams: Vec<FfprobeStream>,
+    chapters: Vec<FfprobeChapter>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    format_long_name: Option<String>,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+    size: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FfprobeStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    profile: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    bit_rate: Option<String>,
+    duration: Option<String>,
+    tags: HashMap<String, String>,
+    disposition: HashMap<String, i64>,
+    side_data_list: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FfprobeChapter {
+    id: i64,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    tags: HashMap<String, String>,
+}
+
+/// Typed summary of an input's ffprobe metadata (`GET /projects/{id}/media/{artifact_id}/probe`).
+#[derive(Serialize, Clone)]
+struct MediaProbe {
+    /// ffprobe's demuxer name list, e.g. `mov,mp4,m4a,3gp,3g2,mj2`.
+    container: Option<String>,
+    container_long_name: Option<String>,
+    duration_s: Option<f64>,
+    bit_rate: Option<u64>,
+    size_bytes: Option<u64>,
+    streams: Vec<ProbeStream>,
+    chapters: Vec<ProbeChapter>,
+}
+
+#[derive(Serialize, Clone)]
+struct ProbeStream {
+    index: u32,
+    /// `video`, `audio`, `subtitle`, `data` or `attachment`.
+    kind: String,
+    codec: Option<String>,
+    profile: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    fps: Option<f64>,
+    /// Display rotation in degrees (0, 90, 180, 270).
+    rotation: Option<i32>,
+    pix_fmt: Option<String>,
+    sample_rate: Option<u32>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    language: Option<String>,
+    bit_rate: Option<u64>,
+    duration_s: Option<f64>,
+    /// Embedded cover art rather than actual video.
+    attached_pic: bool,
+    /// Average and nominal frame rates disagree (typical of screen and phone recordings).
+    variable_frame_rate: bool,
+}
+
+#[derive(Serialize, Clone)]
+struct ProbeChapter {
+    id: i64,
+    start_s: f64,
+    end_s: f64,
+    title: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct ProbeWarning {
+    /// `error` blocks the pipeline; `warning` is informational.
+    severity: &'static str,
+    code: &'static str,
+    message: String,
+}
+
+/// Codecs every mainstream browser plays without a proxy.
+const BROWSER_VIDEO_CODECS: [&str; 4] = ["h264", "vp8", "vp9", "av1"];
+const BROWSER_AUDIO_CODECS: [&str; 5] = ["aac", "mp3", "opus", "vorbis", "flac"];
+
+/// Parses `30000/1001`-style rates; ffprobe reports `0/0` when unknown.
+fn parse_frame_rate(s: &str) -> Option<f64> {
+    let (num, den) = s.split_once('/')?;
+    let (num, den) = (num.parse::<f64>().ok()?, den.parse::<f64>().ok()?);
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+fn parse_num<T: std::str::FromStr>(s: &Option<String>) -> Option<T> {
+    s.as_deref().and_then(|v| v.trim().parse::<T>().ok())
+}
+
+impl MediaProbe {
+    fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        let raw: FfprobeOutput = serde_json::from_slice(bytes).context("invalid ffprobe json")?;
+        Ok(Self::from_ffprobe(raw))
+    }
+
+    fn from_ffprobe(raw: FfprobeOutput) -> Self {
+        let format = raw.format.unwrap_or_default();
+        let streams = raw
+            .streams
+            .into_iter()
+            .map(|s| {
+                // Newer ffprobe puts rotation in the display matrix side data, older in `tags.rotate`.
+                let rotation = s
+                    .side_data_list
+                    .iter()
+                    .find_map(|d| d.get("rotation").and_then(|r| r.as_f64()))
+                    .or_else(|| s.tags.get("rotate").and_then(|r| r.parse::<f64>().ok()))
+                    .map(|r| (r.round() as i32).rem_euclid(360));
+                ProbeStream {
+                    index: s.index,
+                    kind: s.codec_type.clone().unwrap_or_else(|| "unknown".to_string()),
+                    codec: s.codec_name.clone().filter(|c| !c.is_empty() && c != "none"),
+                    profile: s.profile.clone(),
+                    width: s.width.filter(|w| *w > 0),
+                    height: s.height.filter(|h| *h > 0),
+                    fps: s
+                        .avg_frame_rate
+                        .as_deref()
+                        .and_then(parse_frame_rate)
+                        .or_else(|| s.r_frame_rate.as_deref().and_then(parse_frame_rate)),
+                    rotation,
+                    pix_fmt: s.pix_fmt.clone(),
+                    sample_rate: parse_num(&s.sample_rate),
+                    channels: s.channels.filter(|c| *c > 0),
+                    channel_layout: s.channel_layout.clone(),
+                    language: s.tags.get("language").cloned().filter(|l| l != "und"),
+                    bit_rate: parse_num(&s.bit_rate),
+                    duration_s: parse_num(&s.duration),
+                    attached_pic: s.disposition.get("attached_pic").copied().unwrap_or(0) != 0,
+                    variable_frame_rate: match (
+                        s.avg_frame_rate.as_deref().and_then(parse_frame_rate),
+                        s.r_frame_rate.as_deref().and_then(parse_frame_rate),
+                    ) {
+                        (Some(avg), Some(nominal)) => (avg - nominal).abs() / nominal > 0.05,
+                        _ => false,
+                    },
+                }
+            })
+            .collect();
+        let chapters = raw
+            .chapters
+            .into_iter()
+            .filter_map(|c| {
+                Some(ProbeChapter {
+                    id: c.id,
+                    start_s: parse_num(&c.start_time)?,
+                    end_s: parse_num(&c.end_time)?,
+                    title: c.tags.get("title").cloned(),
+                })
+            })
+            .collect();
+
+        Self {
+            container: format.format_name,
+            container_long_name: format.format_long_name,
+            duration_s: parse_num::<f64>(&format.duration).filter(|d| d.is_finite() && *d > 0.0),
+            bit_rate: parse_num(&format.bit_rate),
+            size_bytes: parse_num(&format.size),
+            streams,
+            chapters,
+        }
+    }
+
+    /// First real video stream (cover art is skipped).
+    fn video(&self) -> Option<&ProbeStream> {
+        self.streams.iter().find(|s| s.kind == "video" && !s.attached_pic)
+    }
+
+    fn audio(&self) -> Option<&ProbeStream> {
+        self.streams.iter().find(|s| s.kind == "audio")
+    }
+
+    /// Why a `<video>` element would fail to play (or mis-render) the file as-is; empty when it plays.
+    fn proxy_reasons(&self, ext: &str) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        let ext = ext.to_ascii_lowercase();
+        if let Some(v) = self.video() {
+            let codec = v.codec.as_deref().unwrap_or("unknown");
+            let container_ok = match ext.as_str() {
+                "mp4" | "m4v" | "mov" => true,
+                "webm" => matches!(codec, "vp8" | "vp9" | "av1"),
+                _ => false,
+            };
+            if !BROWSER_VIDEO_CODECS.contains(&codec) {
+                out.push(format!("video codec {codec}"));
+            } else if !container_ok {
+                out.push(format!("{codec} in .{ext} container"));
+            }
+            if !matches!(v.pix_fmt.as_deref(), None | Some("yuv420p") | Some("yuvj420p")) {
+                out.push(format!("pixel format {}", v.pix_fmt.as_deref().unwrap_or("")));
+            }
+            if let Some(rotation) = v.rotation.filter(|r| *r != 0) {
+                out.push(format!("rotated {rotation}°"));
+            }
+            if v.variable_frame_rate {
+                out.push("variable frame rate".to_string());
+            }
+        }
+        if let Some(codec) = self.audio().map(|a| a.codec.as_deref().unwrap_or("unknown")) {
+            if !BROWSER_AUDIO_CODECS.contains(&codec) {
+                out.push(format!("audio codec {codec}"));
+            }
+        }
+        out
+    }
+
+    /// Problems worth surfacing before the pipeline runs. Anything with `severity: error` would make
+    /// the pipeline fail midway, so it refuses to start instead.
+    fn warnings(&self) -> Vec<ProbeWarning> {
+        let mut out: Vec<ProbeWarning> = Vec::new();
+        let mut push = |severity, code, message: String| out.push(ProbeWarning { severity, code, message });
+
+        match self.video() {
+            None => push("error", "no_video", "no video stream found".to_string()),
+            Some(v) => match v.codec.as_deref() {
+                None => push("error", "unknown_video_codec", format!("video stream #{} has no decodable codec", v.index)),
+                Some(codec) => {
+                    if !BROWSER_VIDEO_CODECS.contains(&codec) {
+                        push(
+                            "warning",
+                            "video_codec_not_browser_playable",
+                            format!("video codec {codec} does not play in most browsers; use a proxy for preview"),
+                        );
+                    }
+                    if codec == "h264" && !matches!(v.pix_fmt.as_deref(), None | Some("yuv420p") | Some("yuvj420p")) {
+                        push(
+                            "warning",
+                            "pix_fmt_not_browser_playable",
+                            format!("h264 with pixel format {} does not play in most browsers", v.pix_fmt.as_deref().unwrap_or("")),
+                        );
+                    }
+                    if let Some(rotation) = v.rotation.filter(|r| *r != 0) {
+                        push("warning", "rotated", format!("video is rotated by {rotation}°; outputs are auto-rotated"));
+                    }
+                }
+            },
+        }
+
+        match self.audio() {
+            None => push("error", "no_audio", "no audio stream found; audio extraction and analysis need one".to_string()),
+            Some(a) => match a.codec.as_deref() {
+                None => push("error", "unknown_audio_codec", format!("audio stream #{} has no decodable codec", a.index)),
+                Some(codec) if !BROWSER_AUDIO_CODECS.contains(&codec) => push(
+                    "warning",
+                    "audio_codec_not_browser_playable",
+                    format!("audio codec {codec} does not play in most browsers; use a proxy for preview"),
+                ),
+                Some(_) => {}
+            },
+        }
+
+        if self.duration_s.is_none() {
+            push("warning", "unknown_duration", "container reports no duration; clip sampling assumes 0s".to_string());
+        }
+        out
+    }
+}
+
+/// An `input_video` artifact resolved on disk, with its per-fingerprint output folder and probed duration.
+struct PipelineInput {
+    abs: PathBuf,
+    fingerprint: String,
+    out_dir_rel: String,
+    metadata_rel: String,
+    duration_s: f64,
+    /// Width and height of the first video stream.
+    video_size: Option<(u32, u32)>,
+    probe: MediaProbe,
+    /// Browser-compatible `proxy_video` of this input, if one was made.
+    proxy_abs: Option<PathBuf>,
+}
+
+impl PipelineInput {
+    /// File to cut clips and extracts from: the proxy when there is one (already H.264, upright and
+    /// constant frame rate), else the original. Probe data always describes the original.
+    fn media_abs(&self) -> &FsPath {
+        self.proxy_abs.as_deref().unwrap_or(&self.abs)
+    }
+}
+
+fn ffprobe_json(path: &FsPath) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_format", "-show_streams", "-show_chapters", "-print_format", "json"])
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("ffprobe failed: {stderr}");
+    }
+    Ok(output.stdout)
+}
+
+#[derive(Serialize)]
+struct MediaProbeResponse {
+    artifact: ArtifactResponse,
+    probe: MediaProbe,
+    warnings: Vec<ProbeWarning>,
+}
+
+/// Typed ffprobe summary of a media artifact. `input_video` reuses (and fills) the cached
+/// `metadata.json` of its fingerprint folder; other artifacts are probed on the fly.
+async fn probe_media(
+    State(state): State<AppState>,
+    Path((project_id, artifact_id)): Path<(String, String)>,
+) -> AppResult<Json<MediaProbeResponse>> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    if artifact_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing artifact_id".to_string()));
+    }
+    if !state.ffprobe {
+        return Err(AppError::PreconditionFailed(
+            "ffprobe not found on PATH; please install ffmpeg and restart".to_string(),
+        ));
+    }
+
+    let db_path = state.db_path.clone();
+    let data_dir = state.data_dir.clone();
+    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<MediaProbeResponse>> {
+        let conn = Connection::open(&db_path)?;
+        let Some(artifact) = conn
+            .query_row(
+                "SELECT id, project_id, kind, path, created_at_ms FROM artifacts WHERE id = ?1 AND project_id = ?2",
+                params![&artifact_id, &project_id],
+                |r| {
+                    Ok(ArtifactResponse {
+                        id: r.get(0)?,
+                        project_id: r.get(1)?,
+                        kind: r.get(2)?,
+                        path: r.get(3)?,
+                        created_at_ms: r.get(4)?,
+                    })
+                },
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let probe = if artifact.kind == "input_video" {
+            prepare_pipeline_input(&conn, &data_dir, &project_id, &artifact.id)?.probe
+        } else {
+            let abs = data_dir.join(&artifact.path);
+            if !abs.exists() {
+                anyhow::bail!("artifact file missing on disk: {}", abs.display());
+            }
+            MediaProbe::parse(&ffprobe_json(&abs)?)?
+        };
+        let warnings = probe.warnings();
+        Ok(Some(MediaProbeResponse {
+            artifact,
+            probe,
+            warnings,
+        }))
+    })
+    .await
+    .context("probe_media task failed")??;
+
+    match resp {
+        Some(v) => Ok(Json(v)),
+        None => Err(AppError::NotFound("artifact not found".to_string())),
+    }
+}
+
+/// Proxies cap the short side here; they are for playback and clip cutting, not archival.
+const PROXY_MAX_SHORT_SIDE: u32 = 1080;
+
+#[derive(Deserialize)]
+struct CreateProxyRequest {
+    /// Transcode even when the input already plays in browsers.
+    force: Option<bool>,
+    wait: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ProxyResponse {
+    source_artifact_id: String,
+    /// Why the source does not play in browsers as-is; empty when it does.
+    reasons: Vec<String>,
+    /// The `proxy_video` artifact, or `None` when no proxy was needed.
+    proxy: Option<ArtifactResponse>,
+    cached: bool,
+}
+
+impl RunArtifacts for ProxyResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        self.proxy.iter().cloned().collect()
+    }
+}
+
+async fn create_proxy(
+    State(state): State<AppState>,
+    Path((project_id, artifact_id)): Path<(String, String)>,
+    Json(req): Json<CreateProxyRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let artifact_id = artifact_id.trim().to_string();
+    if artifact_id.is_empty() {
+        return Err(AppError::BadRequest("missing artifact_id".to_string()));
+    }
+    if !state.ffmpeg || !state.ffprobe {
+        return Err(AppError::PreconditionFailed(
+            "ffmpeg/ffprobe not found on PATH; please install ffmpeg and restart".to_string(),
+        ));
+    }
+
+    let force = req.force.unwrap_or(false);
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({ "input_video_artifact_id": &artifact_id, "force": force });
+
+    let data_dir = state.data_dir.clone();
+    dispatch_run(&state, &project_id, "proxy_transcode", request_json, wait, move |job| {
+        Ok(ensure_proxy(job, &data_dir, &artifact_id, force)?)
+    })
+    .await
+}
+
+/// Newest existing `proxy_video` transcoded from `source_artifact_id`.
+fn find_proxy(conn: &Connection, data_dir: &FsPath, project_id: &str, source_artifact_id: &str) -> anyhow::Result<Option<PathBuf>> {
+    let path: Option<String> = conn
+        .query_row(
+            "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'proxy_video' AND source_artifact_id = ?2\n             ORDER BY created_at_ms DESC LIMIT 1",
+            params![project_id, source_artifact_id],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(path.map(|p| data_dir.join(p)).filter(|abs| abs.exists()))
+}
+
+/// Transcodes an `input_video` into an H.264/AAC faststart mp4 (`proxy.mp4` in its fingerprint
+/// folder) when its probe says browsers cannot play it, or always with `force`.
+fn ensure_proxy(job: &JobCtx, data_dir: &FsPath, input_artifact_id: &str, force: bool) -> anyhow::Result<ProxyResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    let input = prepare_pipeline_input(conn, data_dir, project_id, input_artifact_id)?;
+    let ext = input.abs.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let reasons = input.probe.proxy_reasons(ext);
+    if reasons.is_empty() && !force {
+        return Ok(ProxyResponse {
+            source_artifact_id: input_artifact_id.to_string(),
+            reasons,
+            proxy: None,
+            cached: false,
+        });
+    }
+
+    let proxy_rel = format!("{}/proxy.mp4", input.out_dir_rel);
+    let proxy_abs = data_dir.join(&proxy_rel);
+    let cached = proxy_abs.exists();
+    if !cached {
+        let mut steps = StepProgress::new(job, 1);
+        let fps = input.probe.video().and_then(|v| v.fps).unwrap_or(30.0).clamp(1.0, 60.0);
+        let short = PROXY_MAX_SHORT_SIDE;
+        // ffmpeg applies the rotation matrix while decoding, so `iw`/`ih` are already upright here.
+        let scale = format!("scale='if(gte(iw,ih),-2,min({short},iw))':'if(gte(iw,ih),min({short},ih),-2)'");
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(FFMPEG_PROGRESS_ARGS)
+            .arg("-i")
+            .arg(&input.abs)
+            .args(["-map", "0:v:0", "-map", "0:a:0?", "-vf"])
+            .arg(scale)
+            // A fixed output rate turns VFR screen recordings into constant frame rate.
+            .arg("-r")
+            .arg(format!("{fps:.3}"))
+            .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-profile:v", "high", "-pix_fmt", "yuv420p"])
+            .args(["-c:a", "aac", "-b:a", "160k", "-ac", "2", "-movflags", "+faststart"])
+            .arg(&proxy_abs);
+        steps.run_ffmpeg("proxy", &mut cmd, &proxy_abs, input.duration_s)?;
+    }
+
+    let created_at_ms = now_ms();
+    let proxy = ensure_artifact(conn, project_id, "proxy_video", &proxy_rel, created_at_ms)?;
+    conn.execute(
+        "UPDATE artifacts SET source_artifact_id = ?1 WHERE id = ?2",
+        params![input_artifact_id, &proxy.id],
+    )?;
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'proxy_created', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({
+                "input_artifact_id": input_artifact_id,
+                "fingerprint": &input.fingerprint,
+                "path": &proxy_rel,
+                "reasons": &reasons,
+                "cached": cached,
+            })
+            .to_string()
+        ],
+    )?;
+
+    Ok(ProxyResponse {
+        source_artifact_id: input_artifact_id.to_string(),
+        reasons,
+        proxy: Some(proxy),
+        cached,
+    })
+}
+
+fn prepare_pipeline_input(
+    conn: &Connection,
+    data_dir: &FsPath,
+    project_id: &str,
+    input_artifact_id: &str,
+) -> anyhow::Result<PipelineInput> {
+    let mut stmt = conn.prepare(
+        "SELECT kind, path FROM artifacts WHERE id = ?1 AND project_id = ?2 LIMIT 1",
+    )?;
+    let mut rows = stmt.query(params![input_artifact_id, project_id])?;
+    let Some(row) = rows.next()? else {
+        return Err(anyhow::anyhow!("input artifact not found"));
+    };
+    let kind: String = row.get(0)?;
+    let rel_path: String = row.get(1)?;
+    if kind != "input_video" {
+        return Err(anyhow::anyhow!("artifact kind must be input_video"));
+    }
+
+    let input_abs = data_dir.join(&rel_path);
+    if !input_abs.exists() {
+        return Err(anyhow::anyhow!("input file missing on disk: {}", input_abs.display()));
+    }
+
+    let fingerprint = artifact_content_hash(conn, input_artifact_id, &input_abs)?;
+    let out_dir_rel = format!("projects/{}/out/ffmpeg/{}", project_id, fingerprint);
+    let out_dir_abs = data_dir.join(&out_dir_rel);
+    std::fs::create_dir_all(&out_dir_abs)?;
+
+    let metadata_rel = format!("{out_dir_rel}/metadata.json");
+    let metadata_abs = data_dir.join(&metadata_rel);
+    if !metadata_abs.exists() {
+        std::fs::write(&metadata_abs, ffprobe_json(&input_abs)?)?;
+    }
+
+    let probe = MediaProbe::parse(&std::fs::read(&metadata_abs)?)?;
+    let duration_s = probe.duration_s.unwrap_or(0.0);
+    let video_size = probe.video().and_then(|v| v.width.zip(v.height));
+
+    Ok(PipelineInput {
+        abs: input_abs,
+        fingerprint,
+        out_dir_rel,
+        metadata_rel,
+        duration_s,
+        video_size,
+        probe,
+        proxy_abs: find_proxy(conn, data_dir, project_id, input_artifact_id)?,
+    })
+}
+
+fn run_ffmpeg_pipeline(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    input_artifact_id: &str,
+    sampling: &ClipSamplingSpec,
+) -> anyhow::Result<FfmpegPipelineResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    let input = prepare_pipeline_input(conn, data_dir, project_id, input_artifact_id)?;
+    let input_abs = &input.abs;
+    let out_dir_rel = &input.out_dir_rel;
+    let metadata_rel = &input.metadata_rel;
+    let duration_s = input.duration_s;
+
+    let warnings = input.probe.warnings();
+    if !warnings.is_empty() {
+        conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'media_probe_warnings', ?3)",
+            params![
+                project_id,
+                now_ms(),
+                serde_json::json!({ "input_artifact_id": input_artifact_id, "warnings": &warnings }).to_string()
+            ],
+        )?;
+    }
+    if let Some(blocking) = warnings.iter().find(|w| w.severity == "error") {
+        anyhow::bail!("input cannot be processed: {}", blocking.message);
+    }
+
+    // Audio first: its analysis can drive clip selection.
+    let mut steps = StepProgress::new(job, 4);
+    let audio_rel = format!("{out_dir_rel}/audio.wav");
+    let audio_abs = data_dir.join(&audio_rel);
+    if audio_abs.exists() {
+        steps.skip();
+    } else {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(FFMPEG_PROGRESS_ARGS)
+            .arg("-i")
+            .arg(input_abs)
+            .args(["-vn", "-ac", "1", "-ar", "16000"])
+            .arg(&audio_abs);
+        steps.run_ffmpeg("audio", &mut cmd, &audio_abs, duration_s)?;
+    }
+    let (audio_analysis_rel, audio_analysis) = analyze_audio(&mut steps, data_dir, &input, &audio_abs)?;
+
+    let shots = match (&sampling.shots, &sampling.ranges) {
+        (Some(params), None) => Some(run_shot_detection(job, data_dir, input_artifact_id, &input, params)?),
+        _ => None,
+    };
+    let peaks: Vec<f64> = if sampling.danmaku_peaks.unwrap_or(false) && sampling.ranges.is_none() {
+        load_danmaku_peaks(conn, data_dir, project_id, input_artifact_id)?
+            .ok_or_else(|| anyhow::anyhow!("no danmaku heatmap for this input; import it with subtitles enabled"))?
+            .iter()
+            .map(|p| p.t_s)
+            .collect()
+    } else {
+        audio_analysis.peaks.iter().map(|p| p.t_s).collect()
+    };
+    let clip_ranges = sampling.clip_ranges(duration_s, shots.as_ref().map(|s| s.shots.as_slice()), &peaks)?;
+    let clip_specs: Vec<(f64, f64, String, String)> = clip_ranges
+        .iter()
+        .map(|(start, end)| {
+            let key = clip_cache_key(*start, *end);
+            (*start, *end, format!("{out_dir_rel}/clip_{key}.mp4"), format!("{out_dir_rel}/thumb_{key}.jpg"))
+        })
+        .collect();
+    steps.add_steps(clip_specs.len() * 2);
+
+    for (start, end, rel, _) in &clip_specs {
+        let abs = data_dir.join(rel);
+        if abs.exists() {
+            steps.skip();
+            continue;
+        }
+        let len = end - start;
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(FFMPEG_PROGRESS_ARGS)
+            .arg("-ss")
+            .arg(format!("{start:.3}"))
+            .arg("-t")
+            .arg(format!("{len:.3}"))
+            .arg("-i")
+            .arg(input.media_abs())
+            .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "28"])
+            .args(["-c:a", "aac", "-b:a", "128k"])
+            .arg(&abs);
+        steps.run_ffmpeg("clips", &mut cmd, &abs, len)?;
+    }
+
+    for (ss, _, _, rel) in &clip_specs {
+        let abs = data_dir.join(rel);
+        if abs.exists() {
+            steps.skip();
+            continue;
+        }
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(FFMPEG_PROGRESS_ARGS)
+            .arg("-ss")
+            .arg(format!("{ss:.3}"))
+            .arg("-i")
+            .arg(input.media_abs())
+            .args(["-frames:v", "1", "-q:v", "2"])
+            .arg(&abs);
+        steps.run_ffmpeg("thumbnails", &mut cmd, &abs, 0.0)?;
+    }
+
+    let previews = build_previews(&mut steps, data_dir, &input)?;
+
+    let created_at_ms = now_ms();
+    let contact_sheet_art = ensure_artifact(conn, project_id, "contact_sheet", &previews.contact_sheet_rel, created_at_ms)?;
+    let sprite_art = ensure_artifact(conn, project_id, "thumb_sprite", &previews.sprite_rel, created_at_ms)?;
+    let sprite_vtt_art = ensure_artifact(conn, project_id, "thumb_sprite_vtt", &previews.sprite_vtt_rel, created_at_ms)?;
+    let metadata_art = ensure_artifact(conn, project_id, "metadata_json", metadata_rel, created_at_ms)?;
+    let audio_art = ensure_artifact(conn, project_id, "audio_wav", &audio_rel, created_at_ms)?;
+    let audio_analysis_art = ensure_artifact(conn, project_id, "audio_analysis", &audio_analysis_rel, created_at_ms)?;
+    let mut clips: Vec<PipelineClip> = Vec::new();
+    for (index, (start, end, clip_rel, thumb_rel)) in clip_specs.iter().enumerate() {
+        clips.push(PipelineClip {
+            index,
+            start_s: *start,
+            end_s: *end,
+            artifact: ensure_artifact(conn, project_id, "clip", clip_rel, created_at_ms)?,
+            thumbnail: ensure_artifact(conn, project_id, "clip_thumb", thumb_rel, created_at_ms)?,
+        });
+    }
+    let clip_ranges_json: Vec<[f64; 2]> = clips.iter().map(|c| [c.start_s, c.end_s]).collect();
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'ffmpeg_pipeline', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({
+                "input_artifact_id": input_artifact_id,
+                "fingerprint": &input.fingerprint,
+                "duration_s": duration_s,
+                "clip_ranges": clip_ranges_json,
+            })
+            .to_string()
+        ],
+    )?;
+
+    Ok(FfmpegPipelineResponse {
+        input_video_artifact_id: input_artifact_id.to_string(),
+        fingerprint: input.fingerprint.clone(),
+        metadata: metadata_art,
+        thumbnails: clips.iter().map(|c| c.thumbnail.clone()).collect(),
+        clips,
+        audio: audio_art,
+        audio_analysis: audio_analysis_art,
+        contact_sheet: contact_sheet_art,
+        sprite: sprite_art,
+        sprite_vtt: sprite_vtt_art,
+        shots: shots.map(|s| s.shots_json),
+        warnings,
+    })
+}
+
+const SILENCE_NOISE_DB: f64 = -35.0;
+const MIN_SILENCE_S: f64 = 0.5;
+const AUDIO_TOP_PEAKS: usize = 10;
+/// Peaks closer than this to a higher-ranked one are treated as the same moment.
+const AUDIO_PEAK_SEPARATION_S: f64 = 5.0;
+/// Floor for digital silence, so the curve stays finite.
+const LOUDNESS_FLOOR_DB: f64 = -100.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct TimeSpan {
+    start_s: f64,
+    end_s: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AudioPeak {
+    rank: usize,
+    /// Center of the one-second window.
+    t_s: f64,
+    loudness_db: f64,
+    /// Jump over the previous second; sudden hits rank above sustained loudness.
+    rise_db: f64,
+}
+
+/// Contents of `audio_analysis.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct AudioAnalysis {
+    version: u32,
+    fingerprint: String,
+    duration_s: f64,
+    sample_rate: u32,
+    /// RMS loudness in dBFS, one value per second of audio.
+    loudness_db: Vec<f64>,
+    silence_noise_db: f64,
+    min_silence_s: f64,
+    silences: Vec<TimeSpan>,
+    /// Complement of `silences` over the whole duration.
+    speech: Vec<TimeSpan>,
+    peaks: Vec<AudioPeak>,
+}
+
+/// Per-second RMS loudness (dBFS) of a 16-bit PCM WAV, streamed so long inputs stay cheap.
+fn wav_loudness_per_second(path: &FsPath) -> anyhow::Result<(u32, Vec<f64>)> {
+    let mut r = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut header = [0u8; 12];
+    r.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        anyhow::bail!("not a RIFF/WAVE file");
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        r.read_exact(&mut chunk).context("wav data chunk not found")?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut fmt = vec![0u8; size as usize];
+                r.read_exact(&mut fmt)?;
+                if fmt.len() < 16 {
+                    anyhow::bail!("wav fmt chunk too short");
+                }
+                let audio_format = u16::from_le_bytes([fmt[0], fmt[1]]);
+                let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                format = Some((audio_format, channels, sample_rate, bits));
+                if size % 2 == 1 {
+                    std::io::copy(&mut (&mut r).take(1), &mut std::io::sink())?;
+                }
+            }
+            b"data" => break,
+            _ => {
+                std::io::copy(&mut (&mut r).take(size + size % 2), &mut std::io::sink())?;
+            }
+        }
+    }
+
+    let Some((audio_format, channels, sample_rate, bits)) = format else {
+        anyhow::bail!("wav fmt chunk missing");
+    };
+    // 0xFFFE = WAVE_FORMAT_EXTENSIBLE, which ffmpeg uses for some layouts.
+    if !(audio_format == 1 || audio_format == 0xFFFE) || bits != 16 || channels == 0 || sample_rate == 0 {
+        anyhow::bail!("unsupported wav format (need 16-bit PCM)");
+    }
+
+    let window = sample_rate as u64 * channels as u64;
+    let mut out: Vec<f64> = Vec::new();
+    let (mut sum_sq, mut n) = (0.0f64, 0u64);
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut carry: Option<u8> = None;
+    let to_db = |sum_sq: f64, n: u64| {
+        let rms = (sum_sq / n as f64).sqrt() / 32768.0;
+        if rms > 0.0 {
+            (20.0 * rms.log10()).max(LOUDNESS_FLOOR_DB)
+        } else {
+            LOUDNESS_FLOOR_DB
+        }
+    };
+    loop {
+        let read = r.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        let mut bytes = &buf[..read];
+        let mut push = |sample: i16| {
+            let v = sample as f64;
+            sum_sq += v * v;
+            n += 1;
+            if n == window {
+                out.push(to_db(sum_sq, n));
+                sum_sq = 0.0;
+                n = 0;
+            }
+        };
+        if let Some(lo) = carry.take() {
+            push(i16::from_le_bytes([lo, bytes[0]]));
+            bytes = &bytes[1..];
+        }
+        let mut pairs = bytes.chunks_exact(2);
+        for pair in &mut pairs {
+            push(i16::from_le_bytes([pair[0], pair[1]]));
+        }
+        carry = pairs.remainder().first().copied();
+    }
+    if n > 0 {
+        out.push(to_db(sum_sq, n));
+    }
+    Ok((sample_rate, out))
+}
+
+/// Top loud/energetic seconds, ranked by loudness plus any sudden rise, keeping peaks at least
+/// `AUDIO_PEAK_SEPARATION_S` apart.
+fn rank_audio_peaks(loudness_db: &[f64]) -> Vec<AudioPeak> {
+    let mut candidates: Vec<(usize, f64, f64)> = loudness_db
+        .iter()
+        .enumerate()
+        .filter(|(_, db)| **db > LOUDNESS_FLOOR_DB)
+        .map(|(i, db)| {
+            // Rises out of near-silence are capped so a quiet line after a pause does not
+            // outrank the genuinely loud moments.
+            let prev = if i > 0 { loudness_db[i - 1].max(-60.0) } else { *db };
+            let rise = (db - prev).clamp(0.0, 20.0);
+            (i, *db, rise)
+        })
+        .collect();
+    let score = |c: &(usize, f64, f64)| c.1 + 0.5 * c.2;
+    candidates.sort_by(|a, b| score(b).total_cmp(&score(a)));
+
+    let mut picked: Vec<AudioPeak> = Vec::new();
+    for (i, db, rise) in candidates {
+        let t_s = i as f64 + 0.5;
+        if picked.iter().any(|p| (p.t_s - t_s).abs() < AUDIO_PEAK_SEPARATION_S) {
+            continue;
+        }
+        picked.push(AudioPeak {
+            rank: picked.len() + 1,
+            t_s,
+            loudness_db: (db * 10.0).round() / 10.0,
+            rise_db: (rise * 10.0).round() / 10.0,
+        });
+        if picked.len() == AUDIO_TOP_PEAKS {
+            break;
+        }
+    }
+    picked
+}
+
+/// Parses `silencedetect` + `ametadata=print` output into silent spans.
+fn parse_silences(lines: &[String], duration_s: f64) -> Vec<TimeSpan> {
+    let mut out: Vec<TimeSpan> = Vec::new();
+    let mut open: Option<f64> = None;
+    for line in lines {
+        let line = line.trim();
+        if let Some(v) = line.strip_prefix("lavfi.silence_start=") {
+            open = v.parse::<f64>().ok().map(|t| t.max(0.0));
+        } else if let Some(v) = line.strip_prefix("lavfi.silence_end=") {
+            if let (Some(start_s), Ok(end_s)) = (open.take(), v.parse::<f64>()) {
+                out.push(TimeSpan { start_s, end_s });
+            }
+        }
+    }
+    // Silence running into the end of the file has no `silence_end`.
+    if let Some(start_s) = open {
+        if duration_s > start_s {
+            out.push(TimeSpan { start_s, end_s: duration_s });
+        }
+    }
+    out
+}
+
+fn complement_spans(spans: &[TimeSpan], duration_s: f64) -> Vec<TimeSpan> {
+    let mut out: Vec<TimeSpan> = Vec::new();
+    let mut cursor = 0.0;
+    for span in spans {
+        if span.start_s > cursor {
+            out.push(TimeSpan { start_s: cursor, end_s: span.start_s });
+        }
+        cursor = cursor.max(span.end_s);
+    }
+    if duration_s > cursor {
+        out.push(TimeSpan { start_s: cursor, end_s: duration_s });
+    }
+    out
+}
+
+/// Writes (or loads the cached) `audio_analysis.json` next to `audio.wav`.
+fn analyze_audio(
+    steps: &mut StepProgress,
+    data_dir: &FsPath,
+    input: &PipelineInput,
+    audio_abs: &FsPath,
+) -> anyhow::Result<(String, AudioAnalysis)> {
+    let rel = format!("{}/audio_analysis.json", input.out_dir_rel);
+    let abs = data_dir.join(&rel);
+    if let Some(cached) = std::fs::read(&abs)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<AudioAnalysis>(&bytes).ok())
+    {
+        steps.skip();
+        return Ok((rel, cached));
+    }
+
+    let (sample_rate, loudness_db) = wav_loudness_per_second(audio_abs)?;
+    let duration_s = if input.duration_s > 0.0 {
+        input.duration_s
+    } else {
+        loudness_db.len() as f64
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-nostats"])
+        .arg("-i")
+        .arg(audio_abs)
+        .arg("-af")
+        .arg(format!(
+            "silencedetect=noise={SILENCE_NOISE_DB}dB:d={MIN_SILENCE_S},ametadata=mode=print:file=-"
+        ))
+        .args(["-f", "null", "-"]);
+    steps.run_ffmpeg_lines("audio_analysis", &mut cmd, duration_s, |line| {
+        if line.starts_with("lavfi.silence_") {
+            lines.push(line.to_string());
+        }
+    })?;
+    let silences = parse_silences(&lines, duration_s);
+
+    let analysis = AudioAnalysis {
+        version: 1,
+        fingerprint: input.fingerprint.clone(),
+        duration_s,
+        sample_rate,
+        peaks: rank_audio_peaks(&loudness_db),
+        loudness_db: loudness_db.iter().map(|v| (v * 10.0).round() / 10.0).collect(),
+        silence_noise_db: SILENCE_NOISE_DB,
+        min_silence_s: MIN_SILENCE_S,
+        speech: complement_spans(&silences, duration_s),
+        silences,
+    };
+    std::fs::write(&abs, serde_json::to_vec_pretty(&analysis)?)?;
+    Ok((rel, analysis))
+}
+
+const CONTACT_SHEET_COLUMNS: u32 = 5;
+const CONTACT_SHEET_MAX_TILES: u32 = 30;
+const CONTACT_SHEET_TILE_W: u32 = 320;
+const SPRITE_COLUMNS: u32 = 10;
+const SPRITE_MAX_TILES: u32 = 100;
+const SPRITE_TILE_W: u32 = 160;
+
+/// A grid of frames sampled every `interval_s` seconds.
+#[derive(Debug, Clone, Copy)]
+struct PreviewGrid {
+    interval_s: u32,
+    tiles: u32,
+    columns: u32,
+    rows: u32,
+    tile_w: u32,
+    tile_h: u32,
+}
+
+impl PreviewGrid {
+    fn new(duration_s: f64, video_size: Option<(u32, u32)>, max_tiles: u32, columns: u32, tile_w: u32) -> Self {
+        let duration_s = duration_s.max(1.0);
+        let interval_s = ((duration_s / max_tiles as f64).ceil() as u32).max(1);
+        let tiles = ((duration_s / interval_s as f64).ceil() as u32).clamp(1, max_tiles);
+        let columns = columns.min(tiles);
+        let rows = tiles.div_ceil(columns);
+        let (w, h) = video_size.unwrap_or((16, 9));
+        // Even height keeps yuv420 encoders happy.
+        let tile_h = ((tile_w as f64 * h as f64 / w as f64 / 2.0).round() as u32).max(1) * 2;
+        Self {
+            interval_s,
+            tiles,
+            columns,
+            rows,
+            tile_w,
+            tile_h,
+        }
+    }
+
+    /// `-vf` chain sampling one frame per interval, scaled to the tile size, with `extra` filters
+    /// applied per tile before tiling.
+    fn filter(&self, extra: Option<&str>) -> String {
+        let mut chain = format!("fps=1/{},scale={}:{}", self.interval_s, self.tile_w, self.tile_h);
+        if let Some(extra) = extra {
+            chain.push(',');
+            chain.push_str(extra);
+        }
+        chain.push_str(&format!(",tile={}x{}", self.columns, self.rows));
+        chain
+    }
+}
+
+struct PipelinePreviews {
+    contact_sheet_rel: String,
+    sprite_rel: String,
+    sprite_vtt_rel: String,
+}
+
+fn vtt_timestamp(s: f64) -> String {
+    let ms = (s.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+/// WebVTT thumbnails track pointing every cue at its tile in `sprite_file` (`#xywh=` fragments).
+fn sprite_vtt(grid: &PreviewGrid, duration_s: f64, sprite_file: &str) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for i in 0..grid.tiles {
+        let start = (i * grid.interval_s) as f64;
+        let mut end = ((i + 1) * grid.interval_s) as f64;
+        if duration_s > start {
+            end = end.min(duration_s);
+        }
+        let x = (i % grid.columns) * grid.tile_w;
+        let y = (i / grid.columns) * grid.tile_h;
+        out.push_str(&format!(
+            "\n{} --> {}\n{sprite_file}#xywh={x},{y},{},{}\n",
+            vtt_timestamp(start),
+            vtt_timestamp(end),
+            grid.tile_w,
+            grid.tile_h
+        ));
+    }
+    out
+}
+
+/// Contact sheet (timestamped grid) plus thumbnail sprite and its WebVTT track, cached in the
+/// input's fingerprint folder next to the other pipeline outputs.
+fn build_previews(steps: &mut StepProgress, data_dir: &FsPath, input: &PipelineInput) -> anyhow::Result<PipelinePreviews> {
+    let out_dir_rel = &input.out_dir_rel;
+    let contact_sheet_rel = format!("{out_dir_rel}/contact_sheet.jpg");
+    let sprite_rel = format!("{out_dir_rel}/sprite.jpg");
+    let sprite_vtt_rel = format!("{out_dir_rel}/sprite.vtt");
+
+    let grid_cmd = |abs: &FsPath, filter: &str| {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(FFMPEG_PROGRESS_ARGS)
+            .arg("-i")
+            .arg(&input.abs)
+            .arg("-an")
+            .arg("-vf")
+            .arg(filter)
+            .args(["-frames:v", "1", "-q:v", "3"])
+            .arg(abs);
+        cmd
+    };
+
+    let contact_sheet_abs = data_dir.join(&contact_sheet_rel);
+    if contact_sheet_abs.exists() {
+        steps.skip();
+    } else {
+        let grid = PreviewGrid::new(
+            input.duration_s,
+            input.video_size,
+            CONTACT_SHEET_MAX_TILES,
+            CONTACT_SHEET_COLUMNS,
+            CONTACT_SHEET_TILE_W,
+        );
+        let stamp = "drawtext=text='%{pts\\:hms}':x=6:y=6:fontsize=16:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=4";
+        let mut cmd = grid_cmd(&contact_sheet_abs, &grid.filter(Some(stamp)));
+        if let Err(err) = steps.run_ffmpeg("previews", &mut cmd, &contact_sheet_abs, input.duration_s) {
+            if steps.job.cancel.is_cancelled() {
+                return Err(err);
+            }
+            // Builds without drawtext/fontconfig: still produce the grid, just without timestamps.
+            tracing::warn!("contact sheet with timestamps failed, retrying without: {err:#}");
+            let mut cmd = grid_cmd(&contact_sheet_abs, &grid.filter(None));
+            steps.run_ffmpeg("previews", &mut cmd, &contact_sheet_abs, input.duration_s)?;
+        }
+    }
+
+    let sprite_abs = data_dir.join(&sprite_rel);
+    let sprite_vtt_abs = data_dir.join(&sprite_vtt_rel);
+    let grid = PreviewGrid::new(input.duration_s, input.video_size, SPRITE_MAX_TILES, SPRITE_COLUMNS, SPRITE_TILE_W);
+    if sprite_abs.exists() {
+        steps.skip();
+    } else {
+        let mut cmd = grid_cmd(&sprite_abs, &grid.filter(None));
+        steps.run_ffmpeg("previews", &mut cmd, &sprite_abs, input.duration_s)?;
+    }
+    if !sprite_vtt_abs.exists() {
+        std::fs::write(&sprite_vtt_abs, sprite_vtt(&grid, input.duration_s, "sprite.jpg"))?;
+    }
+
+    Ok(PipelinePreviews {
+        contact_sheet_rel,
+        sprite_rel,
+        sprite_vtt_rel,
+    })
 }
 
-#[derive(Deserialize)]
-struct UpdateProfileRequest {
-    prompt: Option<String>,
+const DEFAULT_SCENE_THRESHOLD: f64 = 0.3;
+const DEFAULT_MIN_SHOT_S: f64 = 1.0;
+const MAX_SHOTS: usize = 500;
+
+/// Scene-cut detection settings; each combination gets its own cache folder.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct ShotDetectParams {
+    /// ffmpeg scene score (0..1) above which a frame starts a new shot.
+    threshold: Option<f64>,
+    /// Cuts closer than this to the previous one are ignored (flashes, fast zooms).
+    min_shot_s: Option<f64>,
 }
 
-async fn update_profile(
-    State(state): State<AppState>,
-    Json(req): Json<UpdateProfileRequest>,
-) -> AppResult<Json<ProfileResponse>> {
-    let db_path = state.db_path.clone();
-    let data_dir = state.data_dir.clone();
-    let prompt = req.prompt.unwrap_or_default();
+impl ShotDetectParams {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(t) = self.threshold {
+            if !(t.is_finite() && t > 0.0 && t < 1.0) {
+                return Err("shots.threshold must be between 0 and 1".to_string());
+            }
+        }
+        if let Some(m) = self.min_shot_s {
+            if !(m.is_finite() && (0.0..=60.0).contains(&m)) {
+                return Err("shots.min_shot_s must be between 0 and 60".to_string());
+            }
+        }
+        Ok(())
+    }
 
-    let resp = tokio::task::spawn_blocking(move || -> anyhow::Result<ProfileResponse> {
-        let conn = Connection::open(&db_path)?;
-        let mut profile = load_profile(&conn)?;
-        profile.user_prompt = truncate_with_ellipsis(prompt.trim(), 4000);
-        profile.updated_at_ms = now_ms();
-        profile.prompt = build_profile_prompt(&profile);
-        save_profile(&conn, &data_dir, &profile)?;
-        Ok(ProfileResponse {
-            profile,
-            profile_rel_path: profile_file_name().to_string(),
-            profile_abs_path: data_dir.join(profile_file_name()).display().to_string(),
-        })
-    })
-    .await
-    .context("update_profile task failed")??;
+    fn threshold(&self) -> f64 {
+        self.threshold.unwrap_or(DEFAULT_SCENE_THRESHOLD)
+    }
 
-    Ok(Json(resp))
+    fn min_shot_s(&self) -> f64 {
+        self.min_shot_s.unwrap_or(DEFAULT_MIN_SHOT_S)
+    }
+
+    fn cache_key(&self) -> String {
+        format!(
+            "shots_t{:04}_m{:05}",
+            (self.threshold() * 1000.0).round() as u64,
+            (self.min_shot_s() * 1000.0).round() as u64
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Shot {
+    index: usize,
+    start_s: f64,
+    end_s: f64,
+    /// Scene score of the cut that opened this shot (none for the first shot).
+    scene_score: Option<f64>,
+    keyframe_s: f64,
+    keyframe_path: String,
+    #[serde(default)]
+    keyframe_artifact_id: Option<String>,
+}
+
+/// Contents of `shots.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ShotList {
+    version: u32,
+    input_video_artifact_id: String,
+    fingerprint: String,
+    threshold: f64,
+    min_shot_s: f64,
+    duration_s: f64,
+    shots: Vec<Shot>,
 }
 
 #[derive(Deserialize)]
-struct FfmpegPipelineRequest {
+struct DetectShotsRequest {
     input_video_artifact_id: String,
+    #[serde(flatten)]
+    params: ShotDetectParams,
+    wait: Option<bool>,
 }
 
 #[derive(Serialize)]
-struct FfmpegPipelineResponse {
+struct DetectShotsResponse {
     input_video_artifact_id: String,
     fingerprint: String,
-    metadata: ArtifactResponse,
-    clips: Vec<ArtifactResponse>,
-    audio: ArtifactResponse,
-    thumbnails: Vec<ArtifactResponse>,
+    shots_json: ArtifactResponse,
+    shots: Vec<Shot>,
+    keyframes: Vec<ArtifactResponse>,
 }
 
-fn file_fingerprint(path: &FsPath) -> anyhow::Result<String> {
-    let meta = std::fs::metadata(path)?;
-    let size = meta.len();
-    let mtime_ms = meta
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-        .map(|d| d.as_millis() as u64)
-        .unwrap_or(0);
-    Ok(format!("{size}_{mtime_ms}"))
+impl RunArtifacts for DetectShotsResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        let mut out = vec![self.shots_json.clone()];
+        out.extend(self.keyframes.iter().cloned());
+        out
+    }
 }
 
-fn ensure_artifact(conn: &Connection, project_id: &str, kind: &str, path: &str, created_at_ms: i64) -> anyhow::Result<ArtifactResponse> {
-    if let Some(existing) = conn
-        .query_row(
-            "SELECT id, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = ?2 AND path = ?3 LIMIT 1",
-            params![project_id, kind, path],
-            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
-        )
-        .optional()?
-    {
-        return Ok(ArtifactResponse {
-            id: existing.0,
-            project_id: project_id.to_string(),
-            kind: kind.to_string(),
-            path: path.to_string(),
-            created_at_ms: existing.1,
-        });
+async fn detect_shots(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<DetectShotsRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+    let input_artifact_id = req.input_video_artifact_id.trim().to_string();
+    if input_artifact_id.is_empty() {
+        return Err(AppError::BadRequest("missing input_video_artifact_id".to_string()));
+    }
+    if !state.ffmpeg || !state.ffprobe {
+        return Err(AppError::PreconditionFailed(
+            "ffmpeg/ffprobe not found on PATH; please install ffmpeg and restart".to_string(),
+        ));
     }
+    let params = req.params;
+    params.validate().map_err(AppError::BadRequest)?;
+
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({ "input_video_artifact_id": &input_artifact_id, "params": &params });
+
+    let data_dir = state.data_dir.clone();
+    dispatch_run(&state, &project_id, "shot_detection", request_json, wait, move |job| {
+        let input = prepare_pipeline_input(&job.conn, &data_dir, &job.project_id, &input_artifact_id)?;
+        Ok(run_shot_detection(job, &data_dir, &input_artifact_id, &input, &params)?)
+    })
+    .await
+}
+
+/// Parses ffmpeg `metadata=print` output into `(pts_time, scene_score)` pairs.
+fn parse_scene_cuts(lines: &[String]) -> Vec<(f64, f64)> {
+    let mut out: Vec<(f64, f64)> = Vec::new();
+    let mut pts_time: Option<f64> = None;
+    for line in lines {
+        let line = line.trim();
+        if line.starts_with("frame:") {
+            pts_time = parse_ffmpeg_progress_time(line);
+        } else if let Some(score) = line.strip_prefix("lavfi.scene_score=") {
+            if let (Some(t), Ok(score)) = (pts_time.take(), score.parse::<f64>()) {
+                out.push((t, score));
+            }
+        }
+    }
+    out
+}
+
+/// Runs (or loads cached) scene detection for `input` and makes sure every shot has its keyframe.
+///
+/// Writes `shots.json` plus one `keyframe_NNNN.jpg` per shot into
+/// `out/ffmpeg/{fingerprint}/{cache_key}/`, all registered as artifacts.
+fn run_shot_detection(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    input_artifact_id: &str,
+    input: &PipelineInput,
+    params: &ShotDetectParams,
+) -> anyhow::Result<DetectShotsResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    let shots_dir_rel = format!("{}/{}", input.out_dir_rel, params.cache_key());
+    std::fs::create_dir_all(data_dir.join(&shots_dir_rel))?;
+    let shots_rel = format!("{shots_dir_rel}/shots.json");
+    let shots_abs = data_dir.join(&shots_rel);
+
+    let cached: Option<ShotList> = std::fs::read(&shots_abs)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+    let mut shots: Vec<Shot> = match cached {
+        Some(list) => list.shots,
+        None => {
+            let mut steps = StepProgress::new(job, 1);
+            let mut lines: Vec<String> = Vec::new();
+            // No `-progress` here: stdout carries the metadata lines, whose pts_time doubles as progress.
+            let mut cmd = Command::new("ffmpeg");
+            cmd.args(["-hide_banner", "-loglevel", "error", "-nostats"])
+                .arg("-i")
+                .arg(&input.abs)
+                .arg("-an")
+                .arg("-vf")
+                .arg(format!("select='gt(scene,{:.3})',metadata=print:file=-", params.threshold()))
+                .args(["-f", "null", "-"]);
+            steps.run_ffmpeg_lines("shots", &mut cmd, input.duration_s, |line| {
+                if line.starts_with("frame:") || line.starts_with("lavfi.scene_score=") {
+                    lines.push(line.to_string());
+                }
+            })?;
+
+            let mut cuts: Vec<(f64, f64)> = Vec::new();
+            for (t, score) in parse_scene_cuts(&lines) {
+                let last = cuts.last().map(|(t, _)| *t).unwrap_or(0.0);
+                if t - last >= params.min_shot_s() && (input.duration_s <= 0.0 || input.duration_s - t >= params.min_shot_s()) {
+                    cuts.push((t, score));
+                }
+            }
+            if cuts.len() + 1 > MAX_SHOTS {
+                anyhow::bail!(
+                    "scene detection found {} shots (max {MAX_SHOTS}); raise threshold or min_shot_s",
+                    cuts.len() + 1
+                );
+            }
+
+            let mut bounds: Vec<(f64, Option<f64>)> = vec![(0.0, None)];
+            bounds.extend(cuts.iter().map(|(t, score)| (*t, Some(*score))));
+            let video_end = if input.duration_s > 0.0 {
+                input.duration_s
+            } else {
+                bounds.last().map(|(t, _)| *t).unwrap_or(0.0)
+            };
+            bounds
+                .iter()
+                .enumerate()
+                .map(|(index, (start_s, scene_score))| {
+                    let end_s = bounds.get(index + 1).map(|(t, _)| *t).unwrap_or(video_end).max(*start_s);
+                    Shot {
+                        index,
+                        start_s: *start_s,
+                        end_s,
+                        scene_score: *scene_score,
+                        keyframe_s: start_s + (end_s - start_s) / 2.0,
+                        keyframe_path: format!("{shots_dir_rel}/keyframe_{index:04}.jpg"),
+                        keyframe_artifact_id: None,
+                    }
+                })
+                .collect()
+        }
+    };
+
+    let mut steps = StepProgress::new(job, shots.len());
+    for shot in &shots {
+        let abs = data_dir.join(&shot.keyframe_path);
+        if abs.exists() {
+            steps.skip();
+            continue;
+        }
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(FFMPEG_PROGRESS_ARGS)
+            .arg("-ss")
+            .arg(format!("{:.3}", shot.keyframe_s))
+            .arg("-i")
+            .arg(&input.abs)
+            .args(["-frames:v", "1", "-q:v", "2"])
+            .arg(&abs);
+        steps.run_ffmpeg("keyframes", &mut cmd, &abs, 0.0)?;
+    }
+
+    let created_at_ms = now_ms();
+    let mut keyframes: Vec<ArtifactResponse> = Vec::new();
+    for shot in shots.iter_mut() {
+        let art = ensure_artifact(conn, project_id, "shot_keyframe", &shot.keyframe_path, created_at_ms)?;
+        shot.keyframe_artifact_id = Some(art.id.clone());
+        keyframes.push(art);
+    }
+
+    let list = ShotList {
+        version: 1,
+        input_video_artifact_id: input_artifact_id.to_string(),
+        fingerprint: input.fingerprint.clone(),
+        threshold: params.threshold(),
+        min_shot_s: params.min_shot_s(),
+        duration_s: input.duration_s,
+        shots,
+    };
+    std::fs::write(&shots_abs, serde_json::to_vec_pretty(&list)?)?;
+    let shots_json = ensure_artifact(conn, project_id, "shots_json", &shots_rel, created_at_ms)?;
 
-    let id = Uuid::new_v4().to_string();
     conn.execute(
-        "INSERT INTO artifacts (id, project_id, kind, path, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![&id, project_id, kind, path, created_at_ms],
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'shots_detected', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({
+                "input_artifact_id": input_artifact_id,
+                "fingerprint": &input.fingerprint,
+                "threshold": list.threshold,
+                "min_shot_s": list.min_shot_s,
+                "shots": list.shots.len(),
+                "shots_json": &shots_rel,
+            })
+            .to_string()
+        ],
     )?;
-    Ok(ArtifactResponse {
-        id,
-        project_id: project_id.to_string(),
-        kind: kind.to_string(),
-        path: path.to_string(),
-        created_at_ms,
+
+    Ok(DetectShotsResponse {
+        input_video_artifact_id: input_artifact_id.to_string(),
+        fingerprint: list.fingerprint,
+        shots_json,
+        shots: list.shots,
+        keyframes,
     })
 }
 
-fn run_cmd(cmd: &mut Command) -> anyhow::Result<()> {
-    let output = cmd.output()?;
-    if output.status.success() {
-        return Ok(());
+const MAX_EXTRACT_OUTPUTS: usize = 8;
+/// GIF/WebP get large fast; longer ranges should be cut as mp4.
+const MAX_ANIMATION_S: f64 = 60.0;
+const DEFAULT_ANIMATION_WIDTH: u32 = 480;
+const DEFAULT_ANIMATION_FPS: f64 = 12.0;
+const DEFAULT_EXTRACT_CRF: u32 = 20;
+const DEFAULT_MP3_BITRATE_KBPS: u32 = 192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ExtractFormat {
+    /// Re-encoded H.264/AAC mp4, cut on the exact frame.
+    Mp4,
+    /// Stream copy into the input's container: fast and lossless, but starts on the nearest keyframe.
+    Copy,
+    Gif,
+    Webp,
+    Mp3,
+    Wav,
+    /// Single frame at `start`.
+    Png,
+}
+
+impl ExtractFormat {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Copy => "copy",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+            Self::Mp3 => "mp3",
+            Self::Wav => "wav",
+            Self::Png => "png",
+        }
     }
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    anyhow::bail!("command failed: {stderr}");
+
+    fn artifact_kind(self) -> &'static str {
+        match self {
+            Self::Mp4 | Self::Copy => "extract_video",
+            Self::Gif | Self::Webp => "extract_animation",
+            Self::Mp3 | Self::Wav => "extract_audio",
+            Self::Png => "extract_frame",
+        }
+    }
+
+    fn pool_kind(self) -> &'static str {
+        match self {
+            Self::Mp4 | Self::Copy => "video",
+            Self::Gif | Self::Webp | Self::Png => "image",
+            Self::Mp3 | Self::Wav => "audio",
+        }
+    }
+}
+
+/// One requested output of `POST /projects/{id}/media/extract`. Options that do not apply to the
+/// format are ignored (and do not affect its cache key).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct ExtractOutputSpec {
+    format: ExtractFormat,
+    /// Output width in pixels, height follows the aspect ratio. Defaults to the source width
+    /// (480 for gif/webp).
+    width: Option<u32>,
+    /// Frame rate of gif/webp output (default 12).
+    fps: Option<f64>,
+    /// x264 CRF of mp4 output (default 20).
+    crf: Option<u32>,
+    /// mp3 bitrate in kbit/s (default 192).
+    bitrate_kbps: Option<u32>,
+}
+
+impl ExtractOutputSpec {
+    fn validate(&self) -> Result<(), String> {
+        let name = self.format.name();
+        if let Some(width) = self.width {
+            if !(16..=7680).contains(&width) {
+                return Err(format!("{name}: width must be between 16 and 7680"));
+            }
+        }
+        if let Some(fps) = self.fps {
+            if !(fps.is_finite() && (1.0..=60.0).contains(&fps)) {
+                return Err(format!("{name}: fps must be between 1 and 60"));
+            }
+        }
+        if let Some(crf) = self.crf {
+            if crf > 51 {
+                return Err(format!("{name}: crf must be between 0 and 51"));
+            }
+        }
+        if let Some(kbps) = self.bitrate_kbps {
+            if !(32..=320).contains(&kbps) {
+                return Err(format!("{name}: bitrate_kbps must be between 32 and 320"));
+            }
+        }
+        Ok(())
+    }
+
+    fn animation_width(&self) -> u32 {
+        self.width.unwrap_or(DEFAULT_ANIMATION_WIDTH)
+    }
+
+    fn animation_fps(&self) -> f64 {
+        self.fps.unwrap_or(DEFAULT_ANIMATION_FPS)
+    }
+
+    /// File name inside `out/ffmpeg/{fingerprint}/extract/`; encodes every parameter that changes the bytes.
+    fn file_name(&self, start_s: f64, end_s: f64, input_ext: &str) -> String {
+        let range = clip_cache_key(start_s, end_s);
+        let width = self.width.unwrap_or(0);
+        match self.format {
+            ExtractFormat::Mp4 => format!("{range}_crf{}_w{width}.mp4", self.crf.unwrap_or(DEFAULT_EXTRACT_CRF)),
+            ExtractFormat::Copy => format!("{range}_copy.{input_ext}"),
+            ExtractFormat::Gif | ExtractFormat::Webp => format!(
+                "{range}_w{}_fps{}.{}",
+                self.animation_width(),
+                (self.animation_fps() * 100.0).round() as u32,
+                self.format.name()
+            ),
+            ExtractFormat::Mp3 => format!("{range}_b{}.mp3", self.bitrate_kbps.unwrap_or(DEFAULT_MP3_BITRATE_KBPS)),
+            ExtractFormat::Wav => format!("{range}.wav"),
+            ExtractFormat::Png => format!("{}_w{width}.png", clip_cache_key(start_s, start_s)),
+        }
+    }
+
+    /// ffmpeg arguments between the input and the output path.
+    fn ffmpeg_args(&self) -> Vec<String> {
+        let scale = self.width.map(|w| format!("scale={w}:-2"));
+        let mut args: Vec<String> = Vec::new();
+        match self.format {
+            ExtractFormat::Mp4 => {
+                args.extend(["-map", "0:v:0?", "-map", "0:a:0?"].map(String::from));
+                if let Some(scale) = scale {
+                    args.extend(["-vf".to_string(), scale]);
+                }
+                args.extend(["-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p", "-crf"].map(String::from));
+                args.push(self.crf.unwrap_or(DEFAULT_EXTRACT_CRF).to_string());
+                args.extend(["-c:a", "aac", "-b:a", "160k", "-movflags", "+faststart"].map(String::from));
+            }
+            ExtractFormat::Copy => {
+                args.extend(["-map", "0:v?", "-map", "0:a?", "-c", "copy", "-avoid_negative_ts", "make_zero"].map(String::from));
+            }
+            ExtractFormat::Gif => {
+                // Single pass palettegen/paletteuse: far better colors than ffmpeg's default GIF palette.
+                args.extend(["-an".to_string(), "-vf".to_string()]);
+                args.push(format!(
+                    "fps={},scale={}:-1:flags=lanczos,split[a][b];[a]palettegen=stats_mode=diff[p];[b][p]paletteuse=dither=bayer:bayer_scale=5",
+                    self.animation_fps(),
+                    self.animation_width()
+                ));
+                args.extend(["-loop", "0"].map(String::from));
+            }
+            ExtractFormat::Webp => {
+                args.extend(["-an".to_string(), "-vf".to_string()]);
+                args.push(format!("fps={},scale={}:-1:flags=lanczos", self.animation_fps(), self.animation_width()));
+                args.extend(["-c:v", "libwebp", "-lossless", "0", "-q:v", "75", "-loop", "0"].map(String::from));
+            }
+            ExtractFormat::Mp3 => {
+                args.extend(["-vn", "-c:a", "libmp3lame", "-b:a"].map(String::from));
+                args.push(format!("{}k", self.bitrate_kbps.unwrap_or(DEFAULT_MP3_BITRATE_KBPS)));
+            }
+            ExtractFormat::Wav => {
+                args.extend(["-vn", "-c:a", "pcm_s16le"].map(String::from));
+            }
+            ExtractFormat::Png => {
+                if let Some(scale) = scale {
+                    args.extend(["-vf".to_string(), scale]);
+                }
+                args.extend(["-frames:v", "1"].map(String::from));
+            }
+        }
+        args
+    }
+}
+
+/// Seconds (`72.4`) or a `[HH:]MM:SS[.fff]` string (`"00:01:12.4"`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Timestamp {
+    Seconds(f64),
+    Text(String),
 }
 
-fn run_cmd_output(cmd: &mut Command) -> anyhow::Result<std::process::Output> {
-    let output = cmd.output()?;
-    if output.status.success() {
-        return Ok(output);
+impl Timestamp {
+    fn seconds(&self, field: &str) -> Result<f64, String> {
+        let s = match self {
+            Self::Seconds(s) => Some(*s),
+            Self::Text(t) => parse_vtt_timestamp(t),
+        };
+        s.filter(|s| s.is_finite() && *s >= 0.0)
+            .ok_or_else(|| format!("{field} must be a non-negative number of seconds or HH:MM:SS.fff"))
     }
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    anyhow::bail!("command failed: {stderr}");
 }
 
-async fn ffmpeg_pipeline(
+#[derive(Deserialize)]
+struct ExtractMediaRequest {
+    input_video_artifact_id: String,
+    start: Timestamp,
+    /// Required unless every output is `png`.
+    end: Option<Timestamp>,
+    outputs: Vec<ExtractOutputSpec>,
+    /// Also add each output to the project's pool (deduplicated by artifact).
+    add_to_pool: Option<bool>,
+    pool_title: Option<String>,
+    wait: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ExtractedOutput {
+    format: ExtractFormat,
+    /// Served from an earlier extraction with identical input, range and parameters.
+    cached: bool,
+    artifact: ArtifactResponse,
+    pool_item: Option<PoolItemResponse>,
+}
+
+#[derive(Serialize)]
+struct ExtractMediaResponse {
+    input_video_artifact_id: String,
+    fingerprint: String,
+    start_s: f64,
+    end_s: f64,
+    outputs: Vec<ExtractedOutput>,
+}
+
+impl RunArtifacts for ExtractMediaResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        self.outputs.iter().map(|o| o.artifact.clone()).collect()
+    }
+}
+
+async fn extract_media(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-    Json(req): Json<FfmpegPipelineRequest>,
-) -> AppResult<Json<FfmpegPipelineResponse>> {
+    Json(req): Json<ExtractMediaRequest>,
+) -> AppResult<Response> {
     if project_id.trim().is_empty() {
         return Err(AppError::BadRequest("missing project id".to_string()));
     }
@@ -2948,169 +10679,440 @@ async fn ffmpeg_pipeline(
         ));
     }
 
-    let data_dir = state.data_dir.clone();
-    let db_path = state.db_path.clone();
+    if req.outputs.is_empty() || req.outputs.len() > MAX_EXTRACT_OUTPUTS {
+        return Err(AppError::BadRequest(format!("outputs must list 1 to {MAX_EXTRACT_OUTPUTS} formats")));
+    }
+    for output in &req.outputs {
+        output.validate().map_err(AppError::BadRequest)?;
+    }
+    let start_s = req.start.seconds("start").map_err(AppError::BadRequest)?;
+    let end_s = match &req.end {
+        Some(end) => Some(end.seconds("end").map_err(AppError::BadRequest)?),
+        None => None,
+    };
+    let only_frames = req.outputs.iter().all(|o| o.format == ExtractFormat::Png);
+    let end_s = match end_s {
+        Some(end) if end > start_s => end,
+        Some(_) => return Err(AppError::BadRequest("end must be after start".to_string())),
+        None if only_frames => start_s,
+        None => return Err(AppError::BadRequest("missing end".to_string())),
+    };
+    let len = end_s - start_s;
+    if len > MAX_CLIP_LEN_S {
+        return Err(AppError::BadRequest(format!("range is longer than {MAX_CLIP_LEN_S}s")));
+    }
+    if len > MAX_ANIMATION_S && req.outputs.iter().any(|o| matches!(o.format, ExtractFormat::Gif | ExtractFormat::Webp)) {
+        return Err(AppError::BadRequest(format!("gif/webp output is limited to {MAX_ANIMATION_S}s")));
+    }
+
+    let outputs = req.outputs;
+    let add_to_pool = req.add_to_pool.unwrap_or(false);
+    let pool_title = req.pool_title.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::json!({
+        "input_video_artifact_id": &input_artifact_id,
+        "start_s": start_s,
+        "end_s": end_s,
+        "outputs": &outputs,
+        "add_to_pool": add_to_pool,
+    });
+
+    let data_dir = state.data_dir.clone();
+    dispatch_run(&state, &project_id, "media_extract", request_json, wait, move |job| {
+        let input = prepare_pipeline_input(&job.conn, &data_dir, &job.project_id, &input_artifact_id)?;
+        let resp = run_media_extract(job, &data_dir, &input_artifact_id, &input, (start_s, end_s), &outputs)?;
+        if !add_to_pool {
+            return Ok(resp);
+        }
+        Ok(add_extracts_to_pool(&job.conn, &job.project_id, &input, pool_title.as_deref(), resp)?)
+    })
+    .await
+}
+
+/// Cuts `range` out of `input` (its proxy, when there is one) once per requested output into
+/// `out/ffmpeg/{fingerprint}/extract/`.
+/// Outputs that already exist on disk are reused as-is.
+fn run_media_extract(
+    job: &JobCtx,
+    data_dir: &FsPath,
+    input_artifact_id: &str,
+    input: &PipelineInput,
+    (start_s, end_s): (f64, f64),
+    outputs: &[ExtractOutputSpec],
+) -> anyhow::Result<ExtractMediaResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    if input.duration_s > 0.0 && start_s >= input.duration_s {
+        anyhow::bail!("start {start_s:.3}s is past the end of the video ({:.3}s)", input.duration_s);
+    }
+    let end_s = if input.duration_s > 0.0 {
+        end_s.min(input.duration_s)
+    } else {
+        end_s
+    };
+    let len = end_s - start_s;
+
+    let extract_dir_rel = format!("{}/extract", input.out_dir_rel);
+    std::fs::create_dir_all(data_dir.join(&extract_dir_rel))?;
+    let input_ext = input
+        .media_abs()
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .filter(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "mkv".to_string());
+
+    let mut steps = StepProgress::new(job, outputs.len());
+    let mut produced: Vec<(ExtractFormat, String, bool)> = Vec::new();
+    for spec in outputs {
+        let rel = format!("{extract_dir_rel}/{}", spec.file_name(start_s, end_s, &input_ext));
+        if produced.iter().any(|(_, p, _)| *p == rel) {
+            continue;
+        }
+        let abs = data_dir.join(&rel);
+        let cached = abs.exists();
+        if cached {
+            steps.skip();
+        } else {
+            // `-ss` before `-i` seeks fast and, when re-encoding, still lands on the exact frame.
+            let mut cmd = Command::new("ffmpeg");
+            cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+                .args(FFMPEG_PROGRESS_ARGS)
+                .arg("-ss")
+                .arg(format!("{start_s:.3}"));
+            if spec.format != ExtractFormat::Png {
+                cmd.arg("-t").arg(format!("{len:.3}"));
+            }
+            cmd.arg("-i").arg(input.media_abs()).args(spec.ffmpeg_args()).arg(&abs);
+            let expected_s = if spec.format == ExtractFormat::Png { 0.0 } else { len };
+            steps.run_ffmpeg(spec.format.name(), &mut cmd, &abs, expected_s)?;
+        }
+        produced.push((spec.format, rel, cached));
+    }
+
+    let created_at_ms = now_ms();
+    let mut extracted: Vec<ExtractedOutput> = Vec::new();
+    for (format, rel, cached) in &produced {
+        extracted.push(ExtractedOutput {
+            format: *format,
+            cached: *cached,
+            artifact: ensure_artifact(conn, project_id, format.artifact_kind(), rel, created_at_ms)?,
+            pool_item: None,
+        });
+    }
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'media_extracted', ?3)",
+        params![
+            project_id,
+            created_at_ms,
+            serde_json::json!({
+                "input_artifact_id": input_artifact_id,
+                "fingerprint": &input.fingerprint,
+                "start_s": start_s,
+                "end_s": end_s,
+                "outputs": produced
+                    .iter()
+                    .map(|(format, rel, cached)| serde_json::json!({ "format": format, "path": rel, "cached": cached }))
+                    .collect::<Vec<_>>(),
+            })
+            .to_string()
+        ],
+    )?;
+
+    Ok(ExtractMediaResponse {
+        input_video_artifact_id: input_artifact_id.to_string(),
+        fingerprint: input.fingerprint.clone(),
+        start_s,
+        end_s,
+        outputs: extracted,
+    })
+}
+
+/// Adds every extracted output to the pool, keyed by artifact so repeated extractions update
+/// the same item instead of piling up duplicates.
+fn add_extracts_to_pool(
+    conn: &Connection,
+    project_id: &str,
+    input: &PipelineInput,
+    title: Option<&str>,
+    mut resp: ExtractMediaResponse,
+) -> anyhow::Result<ExtractMediaResponse> {
+    let source_name = input
+        .abs
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("input_video")
+        .to_string();
+    for output in resp.outputs.iter_mut() {
+        let title = match title {
+            Some(t) => t.to_string(),
+            None => format!(
+                "{source_name} {}–{} ({})",
+                vtt_timestamp(resp.start_s),
+                vtt_timestamp(resp.end_s),
+                output.format.name()
+            ),
+        };
+        let data = serde_json::json!({
+            "artifact_id": &output.artifact.id,
+            "path": &output.artifact.path,
+            "input_video_artifact_id": &resp.input_video_artifact_id,
+            "fingerprint": &resp.fingerprint,
+            "start_s": resp.start_s,
+            "end_s": resp.end_s,
+            "format": output.format,
+        });
+        let item = upsert_pool_item(
+            conn,
+            project_id,
+            &NewPoolItem {
+                kind: output.format.pool_kind(),
+                title: Some(&title),
+                source_url: None,
+                license: None,
+                dedup_key: &format!("artifact:{}", output.artifact.id),
+                data_json: Some(&data.to_string()),
+                selected: true,
+            },
+        )?;
+        output.pool_item = Some(item);
+    }
+    Ok(resp)
+}
+
+#[derive(Serialize)]
+struct SpriteCue {
+    start_s: f64,
+    end_s: f64,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
 
-    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<FfmpegPipelineResponse>> {
-        let conn = Connection::open(&db_path)?;
+fn parse_vtt_timestamp(s: &str) -> Option<f64> {
+    let mut total = 0.0;
+    for part in s.trim().split(':') {
+        total = total * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(total)
+}
 
-        let exists: bool = conn
-            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
-            .optional()?
-            .is_some();
-        if !exists {
-            return Ok(None);
+/// Reads back the cues of a sprite track written by `sprite_vtt`.
+fn parse_sprite_vtt(text: &str) -> Vec<SpriteCue> {
+    let mut out: Vec<SpriteCue> = Vec::new();
+    let mut timing: Option<(f64, f64)> = None;
+    for line in text.lines() {
+        if let Some((a, b)) = line.split_once("-->") {
+            timing = parse_vtt_timestamp(a).zip(parse_vtt_timestamp(b));
+            continue;
         }
-
-        let mut stmt = conn.prepare(
-            "SELECT kind, path FROM artifacts WHERE id = ?1 AND project_id = ?2 LIMIT 1",
-        )?;
-        let mut rows = stmt.query(params![&input_artifact_id, &project_id])?;
-        let Some(row) = rows.next()? else {
-            return Err(anyhow::anyhow!("input artifact not found"));
+        let Some((start_s, end_s)) = timing.take() else {
+            continue;
         };
-        let kind: String = row.get(0)?;
-        let rel_path: String = row.get(1)?;
-        if kind != "input_video" {
-            return Err(anyhow::anyhow!("artifact kind must be input_video"));
+        let Some((_, xywh)) = line.split_once("#xywh=") else {
+            continue;
+        };
+        let v: Vec<u32> = xywh.split(',').filter_map(|p| p.trim().parse().ok()).collect();
+        if let [x, y, w, h] = v[..] {
+            out.push(SpriteCue { start_s, end_s, x, y, w, h });
         }
+    }
+    out
+}
+
+/// "Preview" card for report.html: the contact sheet plus a sprite scrubber, inlined as data URIs
+/// so the report stays a single self-contained file. Empty when the pipeline has not run yet.
+fn report_preview_html(conn: &Connection, data_dir: &FsPath, project_id: &str) -> anyhow::Result<String> {
+    use base64::Engine as _;
+
+    let latest = |kind: &str| -> anyhow::Result<Option<PathBuf>> {
+        Ok(latest_pipeline_paths(conn, project_id, kind, &[])?
+            .into_iter()
+            .next()
+            .map(|p| data_dir.join(p))
+            .filter(|p| p.exists()))
+    };
+    let data_uri = |abs: &FsPath| -> anyhow::Result<String> {
+        let bytes = std::fs::read(abs)?;
+        Ok(format!("data:image/jpeg;base64,{}", base64::engine::general_purpose::STANDARD.encode(bytes)))
+    };
 
-        let input_abs = data_dir.join(&rel_path);
-        if !input_abs.exists() {
-            return Err(anyhow::anyhow!("input file missing on disk: {}", input_abs.display()));
+    let mut out = String::new();
+    if let Some(sheet) = latest("contact_sheet")? {
+        out.push_str(&format!(
+            "<p class=\"muted\">Contact sheet</p><img alt=\"contact sheet\" style=\"max-width:100%;border-radius:8px\" src=\"{}\">",
+            data_uri(&sheet)?
+        ));
+    }
+    if let (Some(sprite), Some(vtt)) = (latest("thumb_sprite")?, latest("thumb_sprite_vtt")?) {
+        let cues = parse_sprite_vtt(&std::fs::read_to_string(vtt)?);
+        if let Some(first) = cues.first() {
+            out.push_str(&format!(
+                r#"<p class="muted">Scrub preview</p>
+    <div id="scrub" style="width:{w}px;height:{h}px;border-radius:6px;background-image:url({src});background-repeat:no-repeat"></div>
+    <input id="scrub-pos" type="range" min="0" max="{max}" value="0" style="width:{w}px"> <code id="scrub-time"></code>
+    <script>
+      (function () {{
+        var cues = {cues};
+        var el = document.getElementById("scrub");
+        var pos = document.getElementById("scrub-pos");
+        var label = document.getElementById("scrub-time");
+        function show(i) {{
+          var c = cues[i];
+          el.style.backgroundPosition = "-" + c.x + "px -" + c.y + "px";
+          label.textContent = c.start_s.toFixed(1) + "s - " + c.end_s.toFixed(1) + "s";
+        }}
+        pos.addEventListener("input", function () {{ show(Number(pos.value)); }});
+        show(0);
+      }})();
+    </script>"#,
+                w = first.w,
+                h = first.h,
+                src = data_uri(&sprite)?,
+                max = cues.len() - 1,
+                cues = serde_json::to_string(&cues)?,
+            ));
         }
+    }
 
-        let fingerprint = file_fingerprint(&input_abs)?;
-        let out_dir_rel = format!("projects/{}/out/ffmpeg/{}", project_id, fingerprint);
-        let out_dir_abs = data_dir.join(&out_dir_rel);
-        std::fs::create_dir_all(&out_dir_abs)?;
-
-        let metadata_rel = format!("{out_dir_rel}/metadata.json");
-        let metadata_abs = data_dir.join(&metadata_rel);
-        if !metadata_abs.exists() {
-            let output = Command::new("ffprobe")
-                .args(["-v", "error", "-show_format", "-show_streams", "-print_format", "json"])
-                .arg(&input_abs)
-                .output()?;
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                anyhow::bail!("ffprobe failed: {stderr}");
-            }
-            std::fs::write(&metadata_abs, &output.stdout)?;
-        }
-
-        let metadata_json: serde_json::Value = serde_json::from_slice(&std::fs::read(&metadata_abs)?)?;
-        let duration_s: f64 = metadata_json
-            .get("format")
-            .and_then(|f| f.get("duration"))
-            .and_then(|d| d.as_str())
-            .and_then(|s| s.parse::<f64>().ok())
-            .unwrap_or(0.0);
-
-        let clip_len_s: f64 = 6.0;
-        let start_s = 0.0;
-        let mid_s = (duration_s / 2.0 - clip_len_s / 2.0).max(0.0);
-        let end_s = if duration_s > clip_len_s {
-            (duration_s - clip_len_s).max(0.0)
-        } else {
-            0.0
-        };
+    if out.is_empty() {
+        return Ok(out);
+    }
+    Ok(format!("<div class=\"card\">\n    <h2>Preview</h2>\n    {out}\n  </div>"))
+}
 
-        let clip_start_rel = format!("{out_dir_rel}/clip_start.mp4");
-        let clip_mid_rel = format!("{out_dir_rel}/clip_mid.mp4");
-        let clip_end_rel = format!("{out_dir_rel}/clip_end.mp4");
-        let audio_rel = format!("{out_dir_rel}/audio.wav");
-        let thumb_start_rel = format!("{out_dir_rel}/thumb_start.jpg");
-        let thumb_mid_rel = format!("{out_dir_rel}/thumb_mid.jpg");
-        let thumb_end_rel = format!("{out_dir_rel}/thumb_end.jpg");
+/// "Media" card for report.html: container, streams and chapters of the latest pipeline input,
+/// plus any probe warnings.
+fn report_probe_html(conn: &Connection, data_dir: &FsPath, project_id: &str) -> anyhow::Result<String> {
+    let Some(path) = latest_pipeline_paths(conn, project_id, "metadata_json", &["metadata_json"])?
+        .into_iter()
+        .next()
+    else {
+        return Ok(String::new());
+    };
+    let Ok(bytes) = std::fs::read(data_dir.join(path)) else {
+        return Ok(String::new());
+    };
+    let probe = MediaProbe::parse(&bytes)?;
+
+    let opt = |v: Option<String>| v.map(|s| html_escape(&s)).unwrap_or_else(|| "—".to_string());
+    let summary = format!(
+        "<p><strong>Container:</strong> {} &nbsp; <strong>Duration:</strong> {} &nbsp; <strong>Bitrate:</strong> {}</p>",
+        opt(probe.container.clone()),
+        opt(probe.duration_s.map(vtt_timestamp)),
+        opt(probe.bit_rate.map(|b| format!("{:.0} kb/s", b as f64 / 1000.0))),
+    );
 
-        let clip_specs = [
-            (start_s, &clip_start_rel),
-            (mid_s, &clip_mid_rel),
-            (end_s, &clip_end_rel),
-        ];
-        for (ss, rel) in clip_specs {
-            let abs = data_dir.join(rel);
-            if abs.exists() {
-                continue;
-            }
-            let mut cmd = Command::new("ffmpeg");
-            cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
-                .arg("-ss")
-                .arg(format!("{ss:.3}"))
-                .arg("-t")
-                .arg(format!("{clip_len_s:.3}"))
-                .arg("-i")
-                .arg(&input_abs)
-                .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "28"])
-                .args(["-c:a", "aac", "-b:a", "128k"])
-                .arg(&abs);
-            run_cmd(&mut cmd)?;
-        }
+    let mut streams = String::new();
+    for st in &probe.streams {
+        let detail = match st.kind.as_str() {
+            "video" => format!(
+                "{}{}{}",
+                st.width.zip(st.height).map(|(w, h)| format!("{w}×{h}")).unwrap_or_default(),
+                st.fps.map(|f| format!(" @ {f:.3} fps")).unwrap_or_default(),
+                st.rotation.filter(|r| *r != 0).map(|r| format!(", rotated {r}°")).unwrap_or_default(),
+            ),
+            "audio" => format!(
+                "{}{}",
+                st.sample_rate.map(|r| format!("{r} Hz")).unwrap_or_default(),
+                st.channels.map(|c| format!(", {c} ch")).unwrap_or_default(),
+            ),
+            _ => String::new(),
+        };
+        streams.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            st.index,
+            html_escape(&st.kind),
+            opt(st.codec.clone()),
+            html_escape(&detail),
+            opt(st.language.clone()),
+        ));
+    }
 
-        let audio_abs = data_dir.join(&audio_rel);
-        if !audio_abs.exists() {
-            let mut cmd = Command::new("ffmpeg");
-            cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
-                .arg("-i")
-                .arg(&input_abs)
-                .args(["-vn", "-ac", "1", "-ar", "16000"])
-                .arg(&audio_abs);
-            run_cmd(&mut cmd)?;
+    let chapters = if probe.chapters.is_empty() {
+        String::new()
+    } else {
+        let mut out = String::from("<h3>Chapters</h3><table><thead><tr><th>Start</th><th>End</th><th>Title</th></tr></thead><tbody>");
+        for c in &probe.chapters {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                vtt_timestamp(c.start_s),
+                vtt_timestamp(c.end_s),
+                opt(c.title.clone()),
+            ));
         }
+        out.push_str("</tbody></table>");
+        out
+    };
 
-        let thumb_specs = [(start_s, &thumb_start_rel), (mid_s, &thumb_mid_rel), (end_s, &thumb_end_rel)];
-        for (ss, rel) in thumb_specs {
-            let abs = data_dir.join(rel);
-            if abs.exists() {
-                continue;
-            }
-            let mut cmd = Command::new("ffmpeg");
-            cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
-                .arg("-ss")
-                .arg(format!("{ss:.3}"))
-                .arg("-i")
-                .arg(&input_abs)
-                .args(["-frames:v", "1", "-q:v", "2"])
-                .arg(&abs);
-            run_cmd(&mut cmd)?;
-        }
+    let warnings: String = probe
+        .warnings()
+        .iter()
+        .map(|w| format!("<li>{}: {}</li>", w.severity, html_escape(&w.message)))
+        .collect();
+    let warnings = if warnings.is_empty() {
+        String::new()
+    } else {
+        format!("<ul class=\"muted\">{warnings}</ul>")
+    };
 
-        let created_at_ms = now_ms();
-        let metadata_art = ensure_artifact(&conn, &project_id, "metadata_json", &metadata_rel, created_at_ms)?;
-        let clip_start_art = ensure_artifact(&conn, &project_id, "clip_start", &clip_start_rel, created_at_ms)?;
-        let clip_mid_art = ensure_artifact(&conn, &project_id, "clip_mid", &clip_mid_rel, created_at_ms)?;
-        let clip_end_art = ensure_artifact(&conn, &project_id, "clip_end", &clip_end_rel, created_at_ms)?;
-        let audio_art = ensure_artifact(&conn, &project_id, "audio_wav", &audio_rel, created_at_ms)?;
-        let thumb_start_art = ensure_artifact(&conn, &project_id, "thumb_start", &thumb_start_rel, created_at_ms)?;
-        let thumb_mid_art = ensure_artifact(&conn, &project_id, "thumb_mid", &thumb_mid_rel, created_at_ms)?;
-        let thumb_end_art = ensure_artifact(&conn, &project_id, "thumb_end", &thumb_end_rel, created_at_ms)?;
+    Ok(format!(
+        "<div class=\"card\">\n    <h2>Media</h2>\n    {summary}\n    <table><thead><tr><th>#</th><th>Type</th><th>Codec</th><th>Details</th><th>Language</th></tr></thead><tbody>{streams}</tbody></table>\n    {chapters}{warnings}\n  </div>"
+    ))
+}
 
-        conn.execute(
-            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'ffmpeg_pipeline', ?3)",
-            params![
-                &project_id,
-                created_at_ms,
-                serde_json::json!({ "input_artifact_id": &input_artifact_id, "fingerprint": &fingerprint, "duration_s": duration_s }).to_string()
-            ],
-        )?;
+/// "Audio" card for report.html: loudness curve as inline SVG, speech ratio and top peaks.
+fn report_audio_html(conn: &Connection, data_dir: &FsPath, project_id: &str) -> anyhow::Result<String> {
+    let Some(path) = latest_pipeline_paths(conn, project_id, "audio_analysis", &[])?.into_iter().next() else {
+        return Ok(String::new());
+    };
+    let Ok(bytes) = std::fs::read(data_dir.join(path)) else {
+        return Ok(String::new());
+    };
+    let analysis: AudioAnalysis = serde_json::from_slice(&bytes)?;
+
+    let (w, h, floor_db) = (720.0, 90.0, -60.0);
+    let n = analysis.loudness_db.len().max(2) as f64 - 1.0;
+    let points = analysis
+        .loudness_db
+        .iter()
+        .enumerate()
+        .map(|(i, db)| {
+            let y = (db.max(floor_db) / floor_db) * h;
+            format!("{:.1},{:.1}", i as f64 / n * w, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
 
-        Ok(Some(FfmpegPipelineResponse {
-            input_video_artifact_id: input_artifact_id,
-            fingerprint,
-            metadata: metadata_art,
-            clips: vec![clip_start_art, clip_mid_art, clip_end_art],
-            audio: audio_art,
-            thumbnails: vec![thumb_start_art, thumb_mid_art, thumb_end_art],
-        }))
-    })
-    .await
-    .context("ffmpeg_pipeline task failed")??;
+    let speech_s: f64 = analysis.speech.iter().map(|s| s.end_s - s.start_s).sum();
+    let speech_pct = if analysis.duration_s > 0.0 {
+        speech_s / analysis.duration_s * 100.0
+    } else {
+        0.0
+    };
 
-    match result {
-        Some(r) => Ok(Json(r)),
-        None => Err(AppError::NotFound("project not found".to_string())),
+    let mut peaks = String::new();
+    for p in &analysis.peaks {
+        peaks.push_str(&format!(
+            "<tr><td>{}</td><td>{:.1}s</td><td>{:.1} dBFS</td><td>+{:.1} dB</td></tr>",
+            p.rank, p.t_s, p.loudness_db, p.rise_db
+        ));
     }
+
+    Ok(format!(
+        r##"<div class="card">
+    <h2>Audio</h2>
+    <p class="muted">Loudness per second (dBFS, floor {floor_db} dB). Non-silent: {speech_pct:.0}% ({silences} silent spans).</p>
+    <svg viewBox="0 0 {w} {h}" width="100%" height="{h}" preserveAspectRatio="none" style="background:rgba(255,255,255,.03);border-radius:6px">
+      <polyline fill="none" stroke="#93c5fd" stroke-width="1.5" points="{points}" />
+    </svg>
+    <table><thead><tr><th>#</th><th>Time</th><th>Loudness</th><th>Rise</th></tr></thead><tbody>{peaks}</tbody></table>
+  </div>"##,
+        silences = analysis.silences.len(),
+    ))
 }
 
 #[derive(Serialize)]
@@ -3186,21 +11188,10 @@ async fn generate_report(State(state): State<AppState>, Path(project_id): Path<S
 
         let pool_items: Vec<PoolItemResponse> = {
             let mut stmt = conn.prepare(
-                "SELECT id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms\n                 FROM pool_items WHERE project_id = ?1 ORDER BY created_at_ms ASC",
+                &format!("SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND deleted_at_ms IS NULL ORDER BY created_at_ms ASC"),
             )?;
             let rows = stmt.query_map([&project_id], |row| {
-                Ok(PoolItemResponse {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    kind: row.get(2)?,
-                    title: row.get(3)?,
-                    source_url: row.get(4)?,
-                    license: row.get(5)?,
-                    dedup_key: row.get(6)?,
-                    data_json: row.get(7)?,
-                    selected: row.get::<_, i64>(8)? != 0,
-                    created_at_ms: row.get(9)?,
-                })
+                pool_item_from_row(row)
             })?;
             rows.filter_map(Result::ok).collect()
         };
@@ -3256,6 +11247,10 @@ async fn generate_report(State(state): State<AppState>, Path(project_id): Path<S
             out
         };
 
+        let media_html = report_probe_html(&conn, &data_dir, &project_id)?;
+        let preview_html = report_preview_html(&conn, &data_dir, &project_id)?;
+        let audio_html = report_audio_html(&conn, &data_dir, &project_id)?;
+
         let pool_html = {
             let mut out = String::new();
             if pool_items.is_empty() {
@@ -3306,6 +11301,12 @@ async fn generate_report(State(state): State<AppState>, Path(project_id): Path<S
     <p><strong>Created:</strong> {created}</p>
   </div>
 
+  {media_html}
+
+  {preview_html}
+
+  {audio_html}
+
   <div class="card">
     <h2>Asset Pool</h2>
     {pool_html}
@@ -3326,6 +11327,9 @@ async fn generate_report(State(state): State<AppState>, Path(project_id): Path<S
             title = html_escape(&project_title),
             pid = html_escape(&project_id),
             created = project_created_at_ms,
+            media_html = media_html,
+            preview_html = preview_html,
+            audio_html = audio_html,
             pool_html = pool_html,
             citations_html = citations_html,
         );
@@ -3448,6 +11452,283 @@ struct ExportZipRequest {
     include_clips: Option<bool>,
     include_audio: Option<bool>,
     include_thumbnails: Option<bool>,
+    include_previews: Option<bool>,
+    /// Which input videos `include_original_video` exports: `"primary"` (default), `"all"`, or a
+    /// list of input video ids.
+    inputs: Option<ExportInputs>,
+    /// Include the local files of selected pool items under `assets/`.
+    include_pool_assets: Option<bool>,
+    /// `"skip"` (default), `"fail"` or `"links"`; see [`MissingPoolAssetPolicy`].
+    missing_pool_assets: Option<MissingPoolAssetPolicy>,
+    wait: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ExportInputMode {
+    All,
+    Primary,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum ExportInputs {
+    Mode(ExportInputMode),
+    Ids(Vec<String>),
+}
+
+impl ExportInputs {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Ids(ids) if ids.is_empty() => Err("inputs must list at least one input video id".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Mode(ExportInputMode::All) => "all".to_string(),
+            Self::Mode(ExportInputMode::Primary) => "primary".to_string(),
+            Self::Ids(ids) => format!("{} chosen", ids.len()),
+        }
+    }
+
+    /// Narrows the project's input videos (in project order) down to this selection.
+    fn pick(&self, inputs: Vec<InputVideoResponse>) -> Result<Vec<InputVideoResponse>, String> {
+        match self {
+            Self::Mode(ExportInputMode::All) => Ok(inputs),
+            Self::Mode(ExportInputMode::Primary) => Ok(inputs.into_iter().filter(|i| i.primary).collect()),
+            Self::Ids(ids) => {
+                if let Some(unknown) = ids.iter().find(|id| !inputs.iter().any(|i| &i.artifact.id == *id)) {
+                    return Err(format!("not an input video of this project: {unknown}"));
+                }
+                Ok(inputs.into_iter().filter(|i| ids.contains(&i.artifact.id)).collect())
+            }
+        }
+    }
+}
+
+/// Zip entry names for the exported input videos. A single input keeps the historical
+/// `input_video/{file}` name; several are prefixed with their export position so they sort in
+/// project order and cannot collide.
+fn export_input_entries(data_dir: &FsPath, inputs: &[InputVideoResponse]) -> Vec<(InputVideoResponse, String)> {
+    let present: Vec<&InputVideoResponse> = inputs
+        .iter()
+        .filter(|i| data_dir.join(&i.artifact.path).exists())
+        .collect();
+    let many = present.len() > 1;
+    present
+        .into_iter()
+        .enumerate()
+        .map(|(n, input)| {
+            let file_name = FsPath::new(&input.artifact.path)
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("input_video");
+            let name = if many {
+                format!("input_video/{:02}_{file_name}", n + 1)
+            } else {
+                format!("input_video/{file_name}")
+            };
+            (input.clone(), name)
+        })
+        .collect()
+}
+
+/// `input_video/inputs.json`: labels and the primary flag travel with the exported videos.
+fn export_inputs_json(project_id: &str, entries: &[(InputVideoResponse, String)]) -> anyhow::Result<Vec<u8>> {
+    let inputs: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|(input, name)| {
+            serde_json::json!({
+                "artifact_id": &input.artifact.id,
+                "file": name,
+                "label": &input.label,
+                "primary": input.primary,
+                "position": input.position,
+            })
+        })
+        .collect();
+    Ok(serde_json::to_vec_pretty(&serde_json::json!({
+        "version": 1,
+        "project_id": project_id,
+        "inputs": inputs,
+    }))?)
+}
+
+/// What the export does with a selected pool item that has no local asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum MissingPoolAssetPolicy {
+    /// Leave it out of `assets/` (it is still listed in `selected_pool.json`).
+    #[default]
+    Skip,
+    /// Refuse to export.
+    Fail,
+    /// List it in `assets/links.json` so the editor can fetch it.
+    Links,
+}
+
+/// Selected pool items as they land under `assets/`.
+#[derive(Default)]
+struct PoolAssetExport {
+    /// `(item, zip entry name without extension)`; the file is `{stem}.{ext}` and its provenance
+    /// sidecar `{stem}.provenance.json`.
+    files: Vec<(PoolItemResponse, String)>,
+    links: Vec<PoolItemResponse>,
+    skipped: Vec<String>,
+}
+
+/// Lowercase, dash-separated file name part; keeps non-ASCII letters so CJK titles stay readable.
+fn export_slug(s: &str, max_chars: usize) -> String {
+    let mut out = String::new();
+    for ch in s.chars().flat_map(|c| c.to_lowercase()) {
+        if ch.is_alphanumeric() {
+            out.push(ch);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+        if out.chars().count() >= max_chars {
+            break;
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+fn selected_pool_items(conn: &Connection, project_id: &str) -> anyhow::Result<Vec<PoolItemResponse>> {
+    let mut stmt = conn.prepare(
+        &format!(
+            "SELECT {POOL_ITEM_COLUMNS} FROM pool_items WHERE project_id = ?1 AND selected = 1 AND deleted_at_ms IS NULL ORDER BY created_at_ms ASC"
+        ),
+    )?;
+    let rows = stmt.query_map([project_id], pool_item_from_row)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// Lays out the selected items' local files as `assets/{kind}/{slug}.{ext}`, applying `policy` to
+/// items that were never materialized (or whose file is gone). `Err` names the first missing item
+/// under [`MissingPoolAssetPolicy::Fail`].
+fn export_pool_asset_entries(
+    data_dir: &FsPath,
+    items: &[PoolItemResponse],
+    policy: MissingPoolAssetPolicy,
+) -> Result<PoolAssetExport, String> {
+    let mut out = PoolAssetExport::default();
+    let mut taken: HashSet<String> = HashSet::new();
+    for item in items {
+        let present = item.asset.as_ref().is_some_and(|a| data_dir.join(&a.path).is_file());
+        if !present {
+            match policy {
+                MissingPoolAssetPolicy::Skip => out.skipped.push(item.id.clone()),
+                MissingPoolAssetPolicy::Links => out.links.push(item.clone()),
+                MissingPoolAssetPolicy::Fail => {
+                    return Err(format!(
+                        "pool item {} ({}) has no local asset; materialize it first or export with missing_pool_assets \"skip\" or \"links\"",
+                        item.id,
+                        item.title.as_deref().unwrap_or("untitled")
+                    ))
+                }
+            }
+            continue;
+        }
+
+        let kind = Some(export_slug(&item.kind, 40)).filter(|k| !k.is_empty()).unwrap_or_else(|| "other".to_string());
+        let base = item
+            .title
+            .as_deref()
+            .map(|t| export_slug(t, 60))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| item.id.chars().take(8).collect());
+        let mut stem = format!("assets/{kind}/{base}");
+        let mut n = 2;
+        while !taken.insert(stem.clone()) {
+            stem = format!("assets/{kind}/{base}-{n}");
+            n += 1;
+        }
+        out.files.push((item.clone(), stem));
+    }
+    Ok(out)
+}
+
+/// Zip file name of an exported asset, `{stem}.{ext}` with the extension of the local copy.
+fn pool_asset_entry_name(item: &PoolItemResponse, stem: &str) -> String {
+    let ext = item
+        .asset
+        .as_ref()
+        .and_then(|a| FsPath::new(&a.path).extension())
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin");
+    format!("{stem}.{ext}")
+}
+
+/// `{stem}.provenance.json`: where an exported asset came from and under which license.
+fn pool_asset_provenance_json(item: &PoolItemResponse, file: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(serde_json::to_vec_pretty(&serde_json::json!({
+        "version": 1,
+        "file": file,
+        "item_id": &item.id,
+        "kind": &item.kind,
+        "title": &item.title,
+        "source_url": &item.source_url,
+        "license": &item.license,
+        "media_url": pool_item_media_url(item),
+        "mime": item.asset.as_ref().map(|a| &a.mime),
+        "bytes": item.asset.as_ref().map(|a| a.bytes),
+        "sha256": item.asset.as_ref().map(|a| &a.sha256),
+    }))?)
+}
+
+/// `assets/links.json`: selected items without a local file, for the `links` policy.
+fn pool_asset_links_json(project_id: &str, links: &[PoolItemResponse]) -> anyhow::Result<Vec<u8>> {
+    let items: Vec<serde_json::Value> = links
+        .iter()
+        .map(|item| {
+            serde_json::json!({
+                "item_id": &item.id,
+                "kind": &item.kind,
+                "title": &item.title,
+                "url": pool_item_media_url(item),
+                "source_url": &item.source_url,
+                "license": &item.license,
+            })
+        })
+        .collect();
+    Ok(serde_json::to_vec_pretty(&serde_json::json!({
+        "version": 1,
+        "project_id": project_id,
+        "links": items,
+    }))?)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportZipOptions {
+    include_original_video: bool,
+    inputs: ExportInputs,
+    include_report: bool,
+    include_manifest: bool,
+    include_clips: bool,
+    include_audio: bool,
+    include_thumbnails: bool,
+    include_previews: bool,
+    include_pool_assets: bool,
+    missing_pool_assets: MissingPoolAssetPolicy,
+}
+
+impl ExportZipOptions {
+    fn from_request(req: &ExportZipRequest) -> Self {
+        Self {
+            include_original_video: req.include_original_video.unwrap_or(true),
+            inputs: req.inputs.clone().unwrap_or(ExportInputs::Mode(ExportInputMode::Primary)),
+            include_report: req.include_report.unwrap_or(true),
+            include_manifest: req.include_manifest.unwrap_or(true),
+            include_clips: req.include_clips.unwrap_or(false),
+            include_audio: req.include_audio.unwrap_or(false),
+            include_thumbnails: req.include_thumbnails.unwrap_or(false),
+            include_previews: req.include_previews.unwrap_or(false),
+            include_pool_assets: req.include_pool_assets.unwrap_or(false),
+            missing_pool_assets: req.missing_pool_assets.unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -3471,17 +11752,13 @@ async fn estimate_export_zip(
         return Err(AppError::BadRequest("missing project id".to_string()));
     }
 
-    let include_original_video = req.include_original_video.unwrap_or(true);
-    let include_report = req.include_report.unwrap_or(true);
-    let include_manifest = req.include_manifest.unwrap_or(true);
-    let include_clips = req.include_clips.unwrap_or(false);
-    let include_audio = req.include_audio.unwrap_or(false);
-    let include_thumbnails = req.include_thumbnails.unwrap_or(false);
+    let opts = ExportZipOptions::from_request(&req);
+    opts.inputs.validate().map_err(AppError::BadRequest)?;
 
     let data_dir = state.data_dir.clone();
     let db_path = state.db_path.clone();
 
-    let estimate = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<ExportZipEstimateResponse>> {
+    let estimate = tokio::task::spawn_blocking(move || -> anyhow::Result<Result<ExportZipEstimateResponse, AppError>> {
         let conn = Connection::open(&db_path)?;
 
         let exists: bool = conn
@@ -3489,15 +11766,133 @@ async fn estimate_export_zip(
             .optional()?
             .is_some();
         if !exists {
-            return Ok(None);
+            return Ok(Err(AppError::NotFound("project not found".to_string())));
+        }
+
+        let input_videos = if opts.include_original_video {
+            match opts.inputs.pick(list_input_videos(&conn, &project_id)?) {
+                Ok(inputs) => inputs,
+                Err(msg) => return Ok(Err(AppError::BadRequest(msg))),
+            }
+        } else {
+            Vec::new()
+        };
+
+        let mut files: Vec<ExportZipFileEstimate> = Vec::new();
+
+        if opts.include_report {
+            if let Some((path, _)) = conn
+                .query_row(
+                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'report_html' ORDER BY created_at_ms DESC LIMIT 1",
+                    [&project_id],
+                    |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
+                )
+                .optional()?
+            {
+                let abs = data_dir.join(&path);
+                if abs.exists() {
+                    files.push(ExportZipFileEstimate {
+                        name: "report.html".to_string(),
+                        bytes: std::fs::metadata(abs)?.len(),
+                    });
+                }
+            }
+        }
+
+        if opts.include_manifest {
+            if let Some((path, _)) = conn
+                .query_row(
+                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'manifest_json' ORDER BY created_at_ms DESC LIMIT 1",
+                    [&project_id],
+                    |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
+                )
+                .optional()?
+            {
+                let abs = data_dir.join(&path);
+                if abs.exists() {
+                    files.push(ExportZipFileEstimate {
+                        name: "manifest.json".to_string(),
+                        bytes: std::fs::metadata(abs)?.len(),
+                    });
+                }
+            }
+        }
+
+        // Always include a selected_pool.json snapshot (selected items only).
+        let selected_items = selected_pool_items(&conn, &project_id)?;
+        let pool_assets = if opts.include_pool_assets {
+            match export_pool_asset_entries(&data_dir, &selected_items, opts.missing_pool_assets) {
+                Ok(a) => a,
+                Err(msg) => return Ok(Err(AppError::BadRequest(msg))),
+            }
+        } else {
+            PoolAssetExport::default()
+        };
+        let selected_pool_bytes = serde_json::to_vec_pretty(&serde_json::json!({
+            "version": 1,
+            "project_id": &project_id,
+            "generated_at_ms": now_ms(),
+            "selected_pool_items": selected_items,
+        }))?;
+        files.push(ExportZipFileEstimate {
+            name: "selected_pool.json".to_string(),
+            bytes: selected_pool_bytes.len() as u64,
+        });
+
+        let input_entries = export_input_entries(&data_dir, &input_videos);
+        for (input, name) in &input_entries {
+            files.push(ExportZipFileEstimate {
+                name: name.clone(),
+                bytes: std::fs::metadata(data_dir.join(&input.artifact.path))?.len(),
+            });
+        }
+        if !input_entries.is_empty() {
+            files.push(ExportZipFileEstimate {
+                name: "input_video/inputs.json".to_string(),
+                bytes: export_inputs_json(&project_id, &input_entries)?.len() as u64,
+            });
+        }
+
+        for (item, stem) in &pool_assets.files {
+            let Some(asset) = item.asset.as_ref() else { continue };
+            let name = pool_asset_entry_name(item, stem);
+            let sidecar = pool_asset_provenance_json(item, &name)?;
+            files.push(ExportZipFileEstimate {
+                name,
+                bytes: std::fs::metadata(data_dir.join(&asset.path))?.len(),
+            });
+            files.push(ExportZipFileEstimate {
+                name: format!("{stem}.provenance.json"),
+                bytes: sidecar.len() as u64,
+            });
+        }
+        if !pool_assets.links.is_empty() {
+            files.push(ExportZipFileEstimate {
+                name: "assets/links.json".to_string(),
+                bytes: pool_asset_links_json(&project_id, &pool_assets.links)?.len() as u64,
+            });
+        }
+
+        if opts.include_clips {
+            for path in latest_pipeline_paths(&conn, &project_id, "clip", &["clip_start", "clip_mid", "clip_end"])? {
+                let abs = data_dir.join(&path);
+                if abs.exists() {
+                    let file_name = FsPath::new(&path)
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("clip");
+                    files.push(ExportZipFileEstimate {
+                        name: format!("clips/{}", file_name),
+                        bytes: std::fs::metadata(abs)?.len(),
+                    });
+                }
+            }
         }
 
-        let mut files: Vec<ExportZipFileEstimate> = Vec::new();
-
-        if include_report {
+        if opts.include_audio {
             if let Some((path, _)) = conn
                 .query_row(
-                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'report_html' ORDER BY created_at_ms DESC LIMIT 1",
+                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'audio_wav' ORDER BY created_at_ms DESC LIMIT 1",
                     [&project_id],
                     |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
                 )
@@ -3505,496 +11900,1411 @@ async fn estimate_export_zip(
             {
                 let abs = data_dir.join(&path);
                 if abs.exists() {
+                    let file_name = FsPath::new(&path)
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("audio.wav");
                     files.push(ExportZipFileEstimate {
-                        name: "report.html".to_string(),
+                        name: format!("audio/{}", file_name),
                         bytes: std::fs::metadata(abs)?.len(),
                     });
                 }
             }
         }
 
-        if include_manifest {
-            if let Some((path, _)) = conn
-                .query_row(
-                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'manifest_json' ORDER BY created_at_ms DESC LIMIT 1",
-                    [&project_id],
-                    |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
-                )
-                .optional()?
-            {
+        if opts.include_thumbnails {
+            for path in latest_pipeline_paths(&conn, &project_id, "clip_thumb", &["thumb_start", "thumb_mid", "thumb_end"])? {
                 let abs = data_dir.join(&path);
                 if abs.exists() {
+                    let file_name = FsPath::new(&path)
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("clip_thumb");
                     files.push(ExportZipFileEstimate {
-                        name: "manifest.json".to_string(),
+                        name: format!("thumbnails/{}", file_name),
                         bytes: std::fs::metadata(abs)?.len(),
                     });
                 }
             }
         }
 
-        // Always include a selected_pool.json snapshot (selected items only).
-        let selected_items: Vec<PoolItemResponse> = {
-            let mut stmt = conn.prepare(
-                "SELECT id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms\n                 FROM pool_items WHERE project_id = ?1 AND selected = 1 ORDER BY created_at_ms ASC",
-            )?;
-            let rows = stmt.query_map([&project_id], |row| {
-                Ok(PoolItemResponse {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    kind: row.get(2)?,
-                    title: row.get(3)?,
-                    source_url: row.get(4)?,
-                    license: row.get(5)?,
-                    dedup_key: row.get(6)?,
-                    data_json: row.get(7)?,
-                    selected: row.get::<_, i64>(8)? != 0,
-                    created_at_ms: row.get(9)?,
-                })
-            })?;
-            rows.filter_map(Result::ok).collect()
-        };
-        let selected_pool_bytes = serde_json::to_vec_pretty(&serde_json::json!({
-            "version": 1,
-            "project_id": &project_id,
-            "generated_at_ms": now_ms(),
-            "selected_pool_items": selected_items,
-        }))?;
-        files.push(ExportZipFileEstimate {
-            name: "selected_pool.json".to_string(),
-            bytes: selected_pool_bytes.len() as u64,
-        });
-
-        if include_original_video {
-            if let Some((path, _)) = conn
-                .query_row(
-                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'input_video' ORDER BY created_at_ms DESC LIMIT 1",
-                    [&project_id],
-                    |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
-                )
-                .optional()?
-            {
+        if opts.include_previews {
+            for path in latest_pipeline_preview_paths(&conn, &project_id)? {
                 let abs = data_dir.join(&path);
                 if abs.exists() {
                     let file_name = FsPath::new(&path)
                         .file_name()
                         .and_then(|s| s.to_str())
-                        .unwrap_or("input_video");
+                        .unwrap_or("preview");
                     files.push(ExportZipFileEstimate {
-                        name: format!("input_video/{}", file_name),
+                        name: format!("previews/{}", file_name),
                         bytes: std::fs::metadata(abs)?.len(),
                     });
                 }
             }
         }
 
-        if include_clips {
-            for kind in ["clip_start", "clip_mid", "clip_end"] {
-                if let Some((path, _)) = conn
-                    .query_row(
-                        "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = ?2 ORDER BY created_at_ms DESC LIMIT 1",
-                        params![&project_id, kind],
-                        |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
-                    )
-                    .optional()?
-                {
-                    let abs = data_dir.join(&path);
-                    if abs.exists() {
-                        let file_name = FsPath::new(&path)
-                            .file_name()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or(kind);
-                        files.push(ExportZipFileEstimate {
-                            name: format!("clips/{}", file_name),
-                            bytes: std::fs::metadata(abs)?.len(),
-                        });
-                    }
-                }
-            }
+        let total_bytes = files.iter().map(|f| f.bytes).sum();
+        Ok(Ok(ExportZipEstimateResponse { total_bytes, files }))
+    })
+    .await
+    .context("estimate_export_zip task failed")??;
+
+    Ok(Json(estimate?))
+}
+
+#[derive(Serialize)]
+struct ExportZipResponse {
+    zip: ArtifactResponse,
+    total_bytes: u64,
+    download_url: String,
+}
+
+impl RunArtifacts for ExportZipResponse {
+    fn run_artifacts(&self) -> Vec<ArtifactResponse> {
+        vec![self.zip.clone()]
+    }
+}
+
+async fn export_zip(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Json(req): Json<ExportZipRequest>,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let opts = ExportZipOptions::from_request(&req);
+    opts.inputs.validate().map_err(AppError::BadRequest)?;
+    let check_inputs = opts.include_original_video && matches!(opts.inputs, ExportInputs::Ids(_));
+    let check_pool = opts.include_pool_assets && opts.missing_pool_assets == MissingPoolAssetPolicy::Fail;
+    if check_inputs || check_pool {
+        // Reject unknown ids and missing assets up front instead of failing the run.
+        let db_path = state.db_path.clone();
+        let data_dir = state.data_dir.clone();
+        let pid = project_id.clone();
+        let opts = opts.clone();
+        tokio::task::spawn_blocking(move || -> anyhow::Result<Result<(), AppError>> {
+            let conn = Connection::open(&db_path)?;
+            if check_inputs {
+                if let Err(msg) = opts.inputs.pick(list_input_videos(&conn, &pid)?) {
+                    return Ok(Err(AppError::BadRequest(msg)));
+                }
+            }
+            if check_pool {
+                let items = selected_pool_items(&conn, &pid)?;
+                if let Err(msg) = export_pool_asset_entries(&data_dir, &items, opts.missing_pool_assets) {
+                    return Ok(Err(AppError::BadRequest(msg)));
+                }
+            }
+            Ok(Ok(()))
+        })
+        .await
+        .context("export_zip task failed")???;
+    }
+    let wait = req.wait.unwrap_or(false);
+    let request_json = serde_json::to_value(&opts).context("failed to encode export options")?;
+
+    let data_dir = state.data_dir.clone();
+    dispatch_run(&state, &project_id, "export_zip", request_json, wait, move |job| {
+        Ok(build_export_zip(job, &data_dir, &opts)?)
+    })
+    .await
+}
+
+fn build_export_zip(job: &JobCtx, data_dir: &FsPath, opts: &ExportZipOptions) -> anyhow::Result<ExportZipResponse> {
+    let conn = &job.conn;
+    let project_id = job.project_id.as_str();
+
+    let export_dir_rel = format!("projects/{}/out/export", project_id);
+    let export_dir_abs = data_dir.join(&export_dir_rel);
+    std::fs::create_dir_all(&export_dir_abs)?;
+
+    let report_path = if opts.include_report {
+        conn.query_row(
+            "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'report_html' ORDER BY created_at_ms DESC LIMIT 1",
+            [project_id],
+            |r| r.get::<_, String>(0),
+        )
+        .optional()?
+    } else {
+        None
+    };
+
+    let manifest_path = if opts.include_manifest {
+        conn.query_row(
+            "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'manifest_json' ORDER BY created_at_ms DESC LIMIT 1",
+            [project_id],
+            |r| r.get::<_, String>(0),
+        )
+        .optional()?
+    } else {
+        None
+    };
+
+    let input_entries = if opts.include_original_video {
+        let inputs = opts
+            .inputs
+            .pick(list_input_videos(conn, project_id)?)
+            .map_err(anyhow::Error::msg)?;
+        export_input_entries(data_dir, &inputs)
+    } else {
+        Vec::new()
+    };
+    let input_paths: Vec<String> = input_entries.iter().map(|(i, _)| i.artifact.path.clone()).collect();
+
+    let clip_paths: Vec<String> = if opts.include_clips {
+        latest_pipeline_paths(conn, project_id, "clip", &["clip_start", "clip_mid", "clip_end"])?
+    } else {
+        Vec::new()
+    };
+
+    let audio_path: Option<String> = if opts.include_audio {
+        conn.query_row(
+            "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'audio_wav' ORDER BY created_at_ms DESC LIMIT 1",
+            [project_id],
+            |r| r.get::<_, String>(0),
+        )
+        .optional()?
+    } else {
+        None
+    };
+
+    let thumbnail_paths: Vec<String> = if opts.include_thumbnails {
+        latest_pipeline_paths(conn, project_id, "clip_thumb", &["thumb_start", "thumb_mid", "thumb_end"])?
+    } else {
+        Vec::new()
+    };
+
+    let preview_paths: Vec<String> = if opts.include_previews {
+        latest_pipeline_preview_paths(conn, project_id)?
+    } else {
+        Vec::new()
+    };
+
+    // selected_pool.json snapshot
+    let selected_items = selected_pool_items(conn, project_id)?;
+    let pool_assets = if opts.include_pool_assets {
+        export_pool_asset_entries(data_dir, &selected_items, opts.missing_pool_assets).map_err(anyhow::Error::msg)?
+    } else {
+        PoolAssetExport::default()
+    };
+    // Sidecars are small and built in memory; they go straight into the zip.
+    let mut pool_asset_sidecars: Vec<(String, Vec<u8>)> = Vec::new();
+    for (item, stem) in &pool_assets.files {
+        let name = pool_asset_entry_name(item, stem);
+        pool_asset_sidecars.push((format!("{stem}.provenance.json"), pool_asset_provenance_json(item, &name)?));
+    }
+    if !pool_assets.links.is_empty() {
+        pool_asset_sidecars.push(("assets/links.json".to_string(), pool_asset_links_json(project_id, &pool_assets.links)?));
+    }
+    let pool_asset_paths: Vec<String> = pool_assets
+        .files
+        .iter()
+        .filter_map(|(item, _)| item.asset.as_ref().map(|a| a.path.clone()))
+        .collect();
+    let selected_pool = serde_json::json!({
+        "version": 1,
+        "project_id": project_id,
+        "generated_at_ms": now_ms(),
+        "selected_pool_items": selected_items,
+    });
+    let selected_pool_rel = format!("{export_dir_rel}/selected_pool.json");
+    std::fs::write(data_dir.join(&selected_pool_rel), serde_json::to_vec_pretty(&selected_pool)?)?;
+
+    let inputs_json_rel = if input_entries.is_empty() {
+        None
+    } else {
+        let rel = format!("{export_dir_rel}/inputs.json");
+        std::fs::write(data_dir.join(&rel), export_inputs_json(project_id, &input_entries)?)?;
+        Some(rel)
+    };
+
+    let ts = now_ms();
+    let zip_name = format!("vidunpack-export-{project_id}-{ts}.zip");
+    let zip_rel = format!("{export_dir_rel}/{zip_name}");
+    let zip_abs = data_dir.join(&zip_rel);
+
+    let file = std::fs::File::create(&zip_abs)?;
+    let partial_zip = PartialOutput::new(&zip_abs);
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut total_bytes: u64 = 0;
+
+    // Size of everything we are about to add, so progress can be reported as a percentage.
+    let planned_bytes: u64 = report_path
+        .iter()
+        .chain(manifest_path.iter())
+        .chain(std::iter::once(&selected_pool_rel))
+        .chain(input_paths.iter())
+        .chain(inputs_json_rel.iter())
+        .chain(clip_paths.iter())
+        .chain(audio_path.iter())
+        .chain(thumbnail_paths.iter())
+        .chain(preview_paths.iter())
+        .chain(pool_asset_paths.iter())
+        .filter_map(|p| std::fs::metadata(data_dir.join(p)).ok())
+        .map(|m| m.len())
+        .sum::<u64>()
+        + pool_asset_sidecars.iter().map(|(_, b)| b.len() as u64).sum::<u64>();
+    let written = Cell::new(0u64);
+    let started = Instant::now();
+
+    let add_file = |zip: &mut ZipWriter<std::fs::File>, abs: &FsPath, name: &str| -> anyhow::Result<u64> {
+        let size = std::fs::metadata(abs)?.len();
+        zip.start_file(name, options)?;
+        let mut f = std::fs::File::open(abs)?;
+        let mut buf = vec![0u8; 256 * 1024];
+        loop {
+            job.cancel.check()?;
+            let n = f.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            zip.write_all(&buf[..n])?;
+            written.set(written.get().saturating_add(n as u64));
+            let fraction = if planned_bytes > 0 {
+                written.get() as f64 / planned_bytes as f64
+            } else {
+                0.0
+            };
+            job.report(
+                "export_zip",
+                Progress {
+                    percent: (planned_bytes > 0).then_some(fraction * 100.0),
+                    eta_s: eta_from_fraction(started, fraction),
+                    bytes: Some(written.get()),
+                    total_bytes: Some(planned_bytes),
+                },
+            );
+        }
+        Ok(size)
+    };
+
+    // report / manifest
+    if let Some(p) = report_path {
+        let abs = data_dir.join(&p);
+        if abs.exists() {
+            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, "report.html")?);
+        }
+    }
+    if let Some(p) = manifest_path {
+        let abs = data_dir.join(&p);
+        if abs.exists() {
+            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, "manifest.json")?);
+        }
+    }
+
+    // selected_pool snapshot
+    {
+        let abs = data_dir.join(&selected_pool_rel);
+        total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, "selected_pool.json")?);
+    }
+
+    // original videos, in project order
+    for (input, name) in &input_entries {
+        let abs = data_dir.join(&input.artifact.path);
+        if abs.exists() {
+            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, name)?);
+        }
+    }
+    if let Some(p) = inputs_json_rel.as_ref() {
+        total_bytes = total_bytes.saturating_add(add_file(&mut zip, &data_dir.join(p), "input_video/inputs.json")?);
+    }
+
+    // clips / audio / thumbnails (if present)
+    if !clip_paths.is_empty() {
+        for p in clip_paths {
+            let abs = data_dir.join(&p);
+            if !abs.exists() {
+                continue;
+            }
+            let file_name = FsPath::new(&p)
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("clip.mp4");
+            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("clips/{file_name}"))?);
+        }
+    }
+
+    if let Some(p) = audio_path {
+        let abs = data_dir.join(&p);
+        if abs.exists() {
+            let file_name = FsPath::new(&p)
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("audio.wav");
+            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("audio/{file_name}"))?);
+        }
+    }
+
+    if !thumbnail_paths.is_empty() {
+        for p in thumbnail_paths {
+            let abs = data_dir.join(&p);
+            if !abs.exists() {
+                continue;
+            }
+            let file_name = FsPath::new(&p)
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("thumb.jpg");
+            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("thumbnails/{file_name}"))?);
+        }
+    }
+
+    // contact sheet + sprite; sprite.vtt refers to sprite.jpg, so they share a folder
+    for p in preview_paths {
+        let abs = data_dir.join(&p);
+        if !abs.exists() {
+            continue;
+        }
+        let file_name = FsPath::new(&p)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("preview");
+        total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("previews/{file_name}"))?);
+    }
+
+    // pool assets, each with its provenance sidecar
+    for (item, stem) in &pool_assets.files {
+        let Some(asset) = item.asset.as_ref() else { continue };
+        let abs = data_dir.join(&asset.path);
+        total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &pool_asset_entry_name(item, stem))?);
+    }
+    for (name, bytes) in &pool_asset_sidecars {
+        job.cancel.check()?;
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(bytes)?;
+        total_bytes = total_bytes.saturating_add(bytes.len() as u64);
+        written.set(written.get().saturating_add(bytes.len() as u64));
+    }
+
+    zip.finish()?;
+    partial_zip.keep();
+
+    let zip_art = ensure_artifact(conn, project_id, "export_zip", &zip_rel, ts)?;
+    // The zip is already on disk; a failed purge only means deleted items linger until next time.
+    if let Err(err) = purge_deleted_pool_items(conn, data_dir, project_id) {
+        tracing::warn!("failed to purge deleted pool items after export: {err:#}");
+        let _ = conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'pool_items_purge_failed', ?3)",
+            params![project_id, ts, serde_json::json!({ "error": err.to_string() }).to_string()],
+        );
+    }
+
+    conn.execute(
+        "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'export_zip', ?3)",
+        params![
+            project_id,
+            ts,
+            serde_json::json!({
+                "zip": &zip_rel,
+                "bytes": total_bytes,
+                "input_video_ids": input_entries.iter().map(|(i, _)| i.artifact.id.as_str()).collect::<Vec<_>>(),
+                "pool_assets": pool_assets.files.len(),
+                "pool_asset_links": pool_assets.links.len(),
+                "pool_assets_skipped": &pool_assets.skipped,
+            })
+            .to_string()
+        ],
+    )?;
+
+    if let Err(err) = update_profile_after_export(conn, data_dir, project_id, ts, opts) {
+        tracing::warn!("failed to update profile after export: {err:#}");
+        let _ = conn.execute(
+            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'profile_update_failed', ?3)",
+            params![project_id, ts, serde_json::json!({ "error": err.to_string() }).to_string()],
+        );
+    }
+
+    let download_url = format!("/projects/{}/exports/download/{}", project_id, zip_name);
+    Ok(ExportZipResponse {
+        zip: zip_art,
+        total_bytes,
+        download_url,
+    })
+}
+
+async fn download_export_file(
+    State(state): State<AppState>,
+    Path((project_id, file)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    if project_id.trim().is_empty() {
+        return Err(AppError::BadRequest("missing project id".to_string()));
+    }
+
+    let safe_name = sanitize_file_name(&file);
+    if safe_name.is_empty() {
+        return Err(AppError::BadRequest("invalid file".to_string()));
+    }
+
+    let rel = format!("projects/{}/out/export/{}", project_id, safe_name);
+    let abs = state.data_dir.join(&rel);
+    if !abs.exists() {
+        return Err(AppError::NotFound("file not found".to_string()));
+    }
+
+    let disp = format!("attachment; filename=\"{}\"", safe_name);
+    serve_file(&headers, &abs, "application/zip", &disp).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Answers a single HTTP request with `head` + `body`, then keeps the socket open for `stall`.
+    fn serve_once(head: String, body: Vec<u8>, stall: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind stand-in");
+        let addr = listener.local_addr().expect("stand-in addr");
+        std::thread::spawn(move || {
+            let Ok((stream, _)) = listener.accept() else {
+                return;
+            };
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).map(|n| n > 0).unwrap_or(false) && line != "\r\n" {
+                line.clear();
+            }
+            let mut stream = reader.into_inner();
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&body);
+            let _ = stream.flush();
+            std::thread::sleep(stall);
+        });
+        format!("http://{addr}/asset")
+    }
+
+    fn serve_body(content_type: &str, body: &[u8]) -> String {
+        let head = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            body.len()
+        );
+        serve_once(head, body.to_vec(), Duration::ZERO)
+    }
+
+    fn png_body(len: usize) -> Vec<u8> {
+        let mut body = b"\x89PNG\r\n\x1a\n".to_vec();
+        body.extend((0..len.saturating_sub(body.len())).map(|i| (i % 251) as u8));
+        body
+    }
+
+    fn limits(max_bytes: u64, timeout: Duration) -> PoolAssetLimits {
+        PoolAssetLimits { max_bytes, timeout }
+    }
+
+    fn fetch_error(url: &str, dest: &FsPath, limits: &PoolAssetLimits) -> String {
+        match download_pool_asset(url, dest, limits).expect("local io") {
+            Ok(got) => panic!("download should fail, got {} bytes of {}", got.bytes, got.mime),
+            Err(e) => e,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vidunpack-test-{name}-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn sniff_media_type_ignores_the_declared_type() {
+        assert_eq!(sniff_media_type(b"\x89PNG\r\n\x1a\nrest"), Some(("image/png", "png")));
+        assert_eq!(sniff_media_type(b"\xff\xd8\xff\xe0"), Some(("image/jpeg", "jpg")));
+        assert_eq!(sniff_media_type(b"RIFF\0\0\0\0WEBPVP8 "), Some(("image/webp", "webp")));
+        assert_eq!(sniff_media_type(b"<!doctype html><html>"), None);
+
+        let dir = temp_dir("sniff");
+        let url = serve_body("application/octet-stream", &png_body(2000));
+        let got = download_pool_asset(&url, &dir.join("a"), &limits(1 << 20, Duration::from_secs(5)))
+            .expect("local io")
+            .expect("download");
+        assert_eq!((got.mime, got.ext), ("image/png", "png"));
+
+        let url = serve_body("image/png", b"<!DOCTYPE html><html><body>login</body></html>");
+        let err = fetch_error(&url, &dir.join("b"), &limits(1 << 20, Duration::from_secs(5)));
+        assert!(err.contains("HTML page"), "{err}");
+        assert!(!dir.join("b.part").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn download_pool_asset_enforces_the_size_cap() {
+        let dir = temp_dir("cap");
+        let url = serve_body("image/png", &png_body(4096));
+        let err = fetch_error(&url, &dir.join("a"), &limits(1024, Duration::from_secs(5)));
+        assert!(err.contains("byte limit"), "{err}");
+
+        // Without a content-length the cap is enforced while streaming.
+        let head = "HTTP/1.1 200 OK\r\ncontent-type: image/png\r\nconnection: close\r\n\r\n".to_string();
+        let url = serve_once(head, png_body(4096), Duration::ZERO);
+        let err = fetch_error(&url, &dir.join("b"), &limits(1024, Duration::from_secs(5)));
+        assert!(err.contains("byte limit"), "{err}");
+        assert!(!dir.join("b.part").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn download_pool_asset_times_out_on_a_stalled_body() {
+        let dir = temp_dir("timeout");
+        let body = png_body(64);
+        let head = "HTTP/1.1 200 OK\r\ncontent-type: image/png\r\ncontent-length: 100000\r\n\r\n".to_string();
+        let url = serve_once(head, body, Duration::from_secs(5));
+        let started = Instant::now();
+        let outcome = download_pool_asset(&url, &dir.join("a"), &limits(1 << 20, Duration::from_millis(300)))
+            .expect("local io");
+        assert!(outcome.is_err(), "a stalled body must not succeed");
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert!(!dir.join("a.part").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn materialize_pool_asset_hashes_and_records_the_asset() {
+        let data_dir = temp_dir("materialize");
+        let db_path = data_dir.join("vidunpack.sqlite3");
+        init_db(&db_path).expect("init db");
+        let conn = Connection::open(&db_path).expect("open db");
+
+        let body = png_body(3000);
+        let url = serve_body("text/plain", &body);
+        conn.execute("INSERT INTO projects (id, title, created_at_ms) VALUES ('p1', 'test', 1)", [])
+            .expect("insert project");
+        conn.execute(
+            "INSERT INTO pool_items (id, project_id, kind, source_url, dedup_key, created_at_ms) VALUES ('i1', 'p1', 'image', ?1, 'k1', 1)",
+            [&url],
+        )
+        .expect("insert pool item");
+        let item = read_pool_item(&conn, "p1", "i1").expect("read item").expect("item exists");
+
+        let asset = materialize_pool_asset(&conn, &data_dir, &item, None, &limits(1 << 20, Duration::from_secs(5)))
+            .expect("local io")
+            .expect("materialized");
+        assert_eq!(asset.mime, "image/png");
+        assert_eq!(asset.bytes, body.len() as u64);
+        assert_eq!(asset.sha256, hex_lower(&Sha256::digest(&body)));
+        assert_eq!(asset.path, "projects/p1/assets/i1.png");
+        assert_eq!(std::fs::read(data_dir.join(&asset.path)).expect("asset file"), body);
+
+        let stored = read_pool_item(&conn, "p1", "i1").expect("read item").expect("item exists");
+        let stored_asset = stored.asset.expect("asset row");
+        assert_eq!(stored_asset.artifact_id, asset.artifact_id);
+        assert_eq!(stored_asset.sha256, asset.sha256);
+        assert_eq!(stored.asset_error, None);
+        let kind: String = conn
+            .query_row("SELECT kind FROM artifacts WHERE id = ?1", [&asset.artifact_id], |row| row.get(0))
+            .expect("artifact row");
+        assert_eq!(kind, "pool_asset");
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn parse_byte_range_open_and_suffix_ranges() {
+        assert_eq!(parse_byte_range("bytes=0-", 1000), Ok(Some((0, 999))));
+        assert_eq!(parse_byte_range("bytes=100-199", 1000), Ok(Some((100, 199))));
+        assert_eq!(parse_byte_range("bytes=900-5000", 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_byte_range("bytes=-500", 1000), Ok(Some((500, 999))));
+        assert_eq!(parse_byte_range("bytes=-5000", 1000), Ok(Some((0, 999))));
+        assert_eq!(parse_byte_range("bytes=-0", 1000), Err(()));
+    }
+
+    #[test]
+    fn parse_byte_range_ignores_what_it_cannot_serve_as_one_range() {
+        assert_eq!(parse_byte_range("bytes=500-100", 1000), Ok(None));
+        assert_eq!(parse_byte_range("bytes=0-99,200-299", 1000), Ok(None));
+        assert_eq!(parse_byte_range("items=0-1", 1000), Ok(None));
+        assert_eq!(parse_byte_range("bytes=abc-", 1000), Ok(None));
+        assert_eq!(parse_byte_range("bytes=5", 1000), Ok(None));
+    }
+
+    #[test]
+    fn parse_byte_range_rejects_ranges_past_the_end() {
+        assert_eq!(parse_byte_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_byte_range("bytes=1500-1600", 1000), Err(()));
+        assert_eq!(parse_byte_range("bytes=999-", 1000), Ok(Some((999, 999))));
+    }
+
+    #[test]
+    fn parse_byte_range_on_an_empty_file() {
+        assert_eq!(parse_byte_range("bytes=0-", 0), Err(()));
+        assert_eq!(parse_byte_range("bytes=-500", 0), Err(()));
+        assert_eq!(parse_byte_range("bytes=0-0", 0), Err(()));
+    }
+
+    fn cookie_errors(text: &str) -> Vec<String> {
+        match parse_netscape_cookies(text) {
+            Ok(cookies) => panic!("expected errors, parsed {} cookies", cookies.len()),
+            Err(errors) => errors,
+        }
+    }
+
+    #[test]
+    fn parse_netscape_cookies_reads_httponly_lines_and_skips_comments() {
+        let text = "# Netscape HTTP Cookie File\n\
+                    \n\
+                    # This is a generated file\n\
+                    .bilibili.com\tTRUE\t/\tFALSE\t1900000000\tbili_jct\tabc\n\
+                    #HttpOnly_.Bilibili.com\tTRUE\t/\tTRUE\t1900000000\tSESSDATA\txyz\n\
+                    www.youtube.com\tFALSE\t/\tFALSE\t0\tPREF\tf1\n";
+        let cookies = parse_netscape_cookies(text).expect("valid file");
+        assert_eq!(cookies.len(), 3);
+        assert_eq!((cookies[0].domain.as_str(), cookies[0].name.as_str()), ("bilibili.com", "bili_jct"));
+        assert_eq!(cookies[0].expires_at_ms, Some(1_900_000_000_000));
+        assert_eq!((cookies[1].domain.as_str(), cookies[1].name.as_str()), ("bilibili.com", "SESSDATA"));
+        assert_eq!(cookies[2].domain, "www.youtube.com");
+        assert_eq!(cookies[2].expires_at_ms, None);
+    }
+
+    #[test]
+    fn parse_netscape_cookies_accepts_crlf_line_endings() {
+        let text = "# Netscape HTTP Cookie File\r\n\r\n.example.com\tTRUE\t/\tFALSE\t1900000000\tsid\tv\r\n";
+        let cookies = parse_netscape_cookies(text).expect("valid file");
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name, "sid");
+        assert_eq!(cookies[0].expires_at_ms, Some(1_900_000_000_000));
+    }
+
+    #[test]
+    fn parse_netscape_cookies_reports_bad_lines_by_number() {
+        let text = "# comment\n\
+                    .example.com\tTRUE\t/\tFALSE\t1900000000\tsid\n\
+                    .example.com\tTRUE\t/\tFALSE\tsoon\tsid\tv\n\
+                    .example.com\tMAYBE\t/\tFALSE\t1900000000\tsid\tv\n\
+                    .example.com\tTRUE\t/\tFALSE\t1900000000\tok\tv\n";
+        let errors = cookie_errors(text);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors[0].starts_with("line 2: expected 7 tab-separated fields, found 6"), "{errors:?}");
+        assert!(errors[1].starts_with("line 3: expiry must be a unix timestamp"), "{errors:?}");
+        assert!(errors[2].starts_with("line 4: invalid domain or TRUE/FALSE flag"), "{errors:?}");
+    }
+
+    #[test]
+    fn parse_netscape_cookies_rejects_files_without_cookies() {
+        let errors = cookie_errors("# Netscape HTTP Cookie File\n\n# only comments\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("no cookies found"), "{errors:?}");
+        assert!(cookie_errors("not a cookie file at all")[0].starts_with("line 1: expected 7"));
+    }
+
+    #[test]
+    fn expand_braces_expands_every_group() {
+        assert_eq!(expand_braces("*.mp4"), Ok(vec!["*.mp4".to_string()]));
+        assert_eq!(
+            expand_braces("{a,b}/*.{mp4,mkv}"),
+            Ok(vec!["a/*.mp4", "a/*.mkv", "b/*.mp4", "b/*.mkv"].into_iter().map(String::from).collect())
+        );
+        assert_eq!(
+            expand_braces("片段/*.{mp4,{mkv,webm}}"),
+            Ok(vec!["片段/*.mp4", "片段/*.mkv", "片段/*.webm"].into_iter().map(String::from).collect())
+        );
+        assert_eq!(expand_braces("x{,_hd}.mp4"), Ok(vec!["x.mp4".to_string(), "x_hd.mp4".to_string()]));
+    }
+
+    #[test]
+    fn expand_braces_rejects_unbalanced_and_oversized_globs() {
+        assert!(expand_braces("*.{mp4,mkv").is_err());
+        assert!(expand_braces("*.mp4}").is_err());
+        assert!(expand_braces("a}{b,c}").is_err());
+        assert!(expand_braces(&"{a,b,c,d}".repeat(5)).is_err());
+    }
+
+    #[test]
+    fn finish_batch_import_keeps_input_order() {
+        let data_dir = temp_dir("batch");
+        let db_path = data_dir.join("vidunpack.sqlite3");
+        init_db(&db_path).expect("init db");
+        let conn = Connection::open(&db_path).expect("open db");
+        conn.execute("INSERT INTO projects (id, title, created_at_ms) VALUES ('p1', 'test', 1)", [])
+            .expect("insert project");
+        std::fs::create_dir_all(data_dir.join("projects/p1/media")).expect("media dir");
+        for name in ["a.mp4", "c.mp4"] {
+            std::fs::write(data_dir.join(format!("projects/p1/media/{name}")), name).expect("media file");
         }
+        let staged = |name: &str| StagedImport {
+            rel_path: format!("projects/p1/media/{name}"),
+            bytes: 5,
+            sha256: hex_lower(&Sha256::digest(name.as_bytes())),
+        };
 
-        if include_audio {
-            if let Some((path, _)) = conn
-                .query_row(
-                    "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = 'audio_wav' ORDER BY created_at_ms DESC LIMIT 1",
-                    [&project_id],
-                    |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
-                )
-                .optional()?
-            {
-                let abs = data_dir.join(&path);
-                if abs.exists() {
-                    let file_name = FsPath::new(&path)
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("audio.wav");
-                    files.push(ExportZipFileEstimate {
-                        name: format!("audio/{}", file_name),
-                        bytes: std::fs::metadata(abs)?.len(),
-                    });
-                }
-            }
+        let files = vec![
+            ("a.mp4".to_string(), Ok(staged("a.mp4"))),
+            ("b.mp4".to_string(), Err("write failed".to_string())),
+            ("c.mp4".to_string(), Ok(staged("c.mp4"))),
+        ];
+        let resp = finish_batch_import(&conn, &data_dir, "p1", files, serde_json::json!({ "kind": "test" }))
+            .expect("finish batch");
+        assert_eq!((resp.imported, resp.failed), (2, 1));
+        let order: Vec<(&str, bool)> = resp.items.iter().map(|i| (i.source.as_str(), i.ok)).collect();
+        assert_eq!(order, vec![("a.mp4", true), ("b.mp4", false), ("c.mp4", true)]);
+        assert_eq!(resp.items[1].error.as_deref(), Some("write failed"));
+        assert!(resp.items[2].artifact.is_some());
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    fn cue(start_s: f64) -> TimedTextCue {
+        TimedTextCue {
+            start_s,
+            end_s: start_s,
+            text: "233".to_string(),
         }
+    }
 
-        if include_thumbnails {
-            for kind in ["thumb_start", "thumb_mid", "thumb_end"] {
-                if let Some((path, _)) = conn
-                    .query_row(
-                        "SELECT path, created_at_ms FROM artifacts WHERE project_id = ?1 AND kind = ?2 ORDER BY created_at_ms DESC LIMIT 1",
-                        params![&project_id, kind],
-                        |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
-                    )
-                    .optional()?
-                {
-                    let abs = data_dir.join(&path);
-                    if abs.exists() {
-                        let file_name = FsPath::new(&path)
-                            .file_name()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or(kind);
-                        files.push(ExportZipFileEstimate {
-                            name: format!("thumbnails/{}", file_name),
-                            bytes: std::fs::metadata(abs)?.len(),
-                        });
-                    }
-                }
-            }
+    #[test]
+    fn danmaku_heatmap_drops_out_of_range_timestamps() {
+        let cues = vec![cue(1.2), cue(1.8), cue(3.0), cue(1e12), cue(-4.0), cue(95.0)];
+        let heatmap = danmaku_heatmap(&cues, Some(90.0));
+        assert_eq!(heatmap.counts.len(), 91);
+        assert_eq!(heatmap.total, 3);
+        assert_eq!((heatmap.counts[1], heatmap.counts[3]), (2, 1));
+
+        // Without a duration the timeline follows the cues, but never past the hard cap.
+        let heatmap = danmaku_heatmap(&[cue(2.0), cue(1e12)], None);
+        assert_eq!(heatmap.counts.len(), 3);
+        assert_eq!(heatmap.total, 1);
+        let heatmap = danmaku_heatmap(&[cue(5.0)], Some(1e12));
+        assert_eq!(heatmap.counts.len(), DANMAKU_HEATMAP_MAX_S as usize + 1);
+    }
+
+    #[test]
+    fn unavailable_reason_only_maps_known_messages() {
+        let cases = [
+            ("ERROR: [youtube] abc: Private video. Sign in if you've been granted access", Some("private")),
+            ("ERROR: [youtube] abc: Video unavailable", Some("unavailable")),
+            ("ERROR: [youtube] abc: This video has been removed by the uploader", Some("unavailable")),
+            ("ERROR: [youtube] abc: Join this channel to get access to members-only content", Some("needs_auth")),
+            ("ERROR: [youtube] abc: Sign in to confirm your age", Some("needs_auth")),
+            ("ERROR: [youtube] abc: Unable to download webpage: <urlopen error [Errno -3] Temporary failure in name resolution>", None),
+            ("ERROR: [youtube] abc: HTTP Error 429: Too Many Requests", None),
+            ("ERROR: [youtube] abc: Sign in to confirm you're not a bot", None),
+            ("ERROR: [BiliBili] abc: Unable to extract initial state; please report this issue on GitHub", None),
+        ];
+        for (error, want) in cases {
+            assert_eq!(unavailable_reason(error), want, "{error}");
         }
+    }
 
-        let total_bytes = files.iter().map(|f| f.bytes).sum();
-        Ok(Some(ExportZipEstimateResponse { total_bytes, files }))
-    })
-    .await
-    .context("estimate_export_zip task failed")??;
+    /// 16-bit mono PCM WAV with an odd-sized chunk (plus its pad byte) before `fmt `.
+    fn write_test_wav(path: &FsPath, sample_rate: u32, samples: &[i16]) {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut fmt = Vec::new();
+        fmt.extend(1u16.to_le_bytes());
+        fmt.extend(1u16.to_le_bytes());
+        fmt.extend(sample_rate.to_le_bytes());
+        fmt.extend((sample_rate * 2).to_le_bytes());
+        fmt.extend(2u16.to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+
+        let mut body = b"WAVE".to_vec();
+        body.extend(b"LIST");
+        body.extend(3u32.to_le_bytes());
+        body.extend(b"abc\0");
+        body.extend(b"fmt ");
+        body.extend((fmt.len() as u32).to_le_bytes());
+        body.extend(&fmt);
+        body.extend(b"data");
+        body.extend((data.len() as u32).to_le_bytes());
+        body.extend(&data);
+        let mut wav = b"RIFF".to_vec();
+        wav.extend((body.len() as u32).to_le_bytes());
+        wav.extend(body);
+        std::fs::write(path, wav).expect("write wav");
+    }
 
-    match estimate {
-        Some(e) => Ok(Json(e)),
-        None => Err(AppError::NotFound("project not found".to_string())),
+    #[test]
+    fn wav_loudness_per_second_skips_odd_chunks_and_keeps_the_tail() {
+        let dir = temp_dir("wav");
+        let path = dir.join("audio.wav");
+        // 1 s at half scale, 1 s of silence, then a 0.5 s tail at a tenth of full scale.
+        let mut samples = vec![16384i16; 100];
+        samples.extend(vec![0i16; 100]);
+        samples.extend(vec![3277i16; 50]);
+        write_test_wav(&path, 100, &samples);
+
+        let (sample_rate, loudness) = wav_loudness_per_second(&path).expect("parse wav");
+        assert_eq!(sample_rate, 100);
+        assert_eq!(loudness.len(), 3);
+        assert!((loudness[0] - -6.02).abs() < 0.01, "{loudness:?}");
+        assert_eq!(loudness[1], LOUDNESS_FLOOR_DB);
+        assert!((loudness[2] - -20.0).abs() < 0.01, "{loudness:?}");
+        let _ = std::fs::remove_dir_all(&dir);
     }
-}
 
-#[derive(Serialize)]
-struct ExportZipResponse {
-    zip: ArtifactResponse,
-    total_bytes: u64,
-    download_url: String,
-}
+    #[test]
+    fn rank_audio_peaks_keeps_peaks_apart() {
+        let mut loudness = vec![LOUDNESS_FLOOR_DB; 20];
+        loudness[2] = -10.0;
+        // Within AUDIO_PEAK_SEPARATION_S of the first peak: the same moment.
+        loudness[4] = -12.0;
+        // Exactly AUDIO_PEAK_SEPARATION_S away from both neighbours: still separate peaks.
+        loudness[7] = -20.0;
+        loudness[12] = -15.0;
+
+        let peaks = rank_audio_peaks(&loudness);
+        let got: Vec<(usize, f64)> = peaks.iter().map(|p| (p.rank, p.t_s)).collect();
+        assert_eq!(got, vec![(1, 2.5), (2, 12.5), (3, 7.5)]);
+        // Rises out of silence are measured from -60 dB and capped.
+        assert_eq!(peaks[0].rise_db, 20.0);
+        assert!(rank_audio_peaks(&[LOUDNESS_FLOOR_DB; 5]).is_empty());
+    }
 
-async fn export_zip(
-    State(state): State<AppState>,
-    Path(project_id): Path<String>,
-    Json(req): Json<ExportZipRequest>,
-) -> AppResult<Json<ExportZipResponse>> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
+    #[test]
+    fn parse_silences_closes_silence_at_eof() {
+        let lines: Vec<String> = [
+            "frame:0 pts:0",
+            "lavfi.silence_start=-0.02",
+            "lavfi.silence_end=1.5",
+            "lavfi.silence_start=4",
+            "lavfi.silence_end=5.25",
+            "lavfi.silence_start=8",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        let silences = parse_silences(&lines, 10.0);
+        let got: Vec<(f64, f64)> = silences.iter().map(|s| (s.start_s, s.end_s)).collect();
+        assert_eq!(got, vec![(0.0, 1.5), (4.0, 5.25), (8.0, 10.0)]);
+
+        let speech = complement_spans(&silences, 10.0);
+        let got: Vec<(f64, f64)> = speech.iter().map(|s| (s.start_s, s.end_s)).collect();
+        assert_eq!(got, vec![(1.5, 4.0), (5.25, 8.0)]);
+
+        // A silence that starts at or past the end adds nothing.
+        assert!(parse_silences(&["lavfi.silence_start=10".to_string()], 10.0).is_empty());
+        assert_eq!(complement_spans(&[], 3.0).len(), 1);
     }
 
-    let include_original_video = req.include_original_video.unwrap_or(true);
-    let include_report = req.include_report.unwrap_or(true);
-    let include_manifest = req.include_manifest.unwrap_or(true);
-    let include_clips = req.include_clips.unwrap_or(false);
-    let include_audio = req.include_audio.unwrap_or(false);
-    let include_thumbnails = req.include_thumbnails.unwrap_or(false);
+    fn cue_tuples(cues: &[TimedTextCue]) -> Vec<(f64, f64, &str)> {
+        cues.iter().map(|c| (c.start_s, c.end_s, c.text.as_str())).collect()
+    }
 
-    let data_dir = state.data_dir.clone();
-    let db_path = state.db_path.clone();
+    #[test]
+    fn parse_srt_or_vtt_reads_srt_with_comma_milliseconds() {
+        let srt = "1\r\n00:00:01,500 --> 00:00:03,250\r\nHello <i>there</i>\r\nsecond line\r\n\r\n\
+                   2\r\n01:02:03,004 --> 01:02:04,000\r\nTom &amp; Jerry\r\n";
+        assert_eq!(
+            cue_tuples(&parse_srt_or_vtt(srt)),
+            vec![(1.5, 3.25, "Hello there\nsecond line"), (3723.004, 3724.0, "Tom & Jerry")]
+        );
+    }
 
-    let res = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<ExportZipResponse>> {
-        let conn = Connection::open(&db_path)?;
+    #[test]
+    fn parse_srt_or_vtt_drops_vtt_cue_settings_and_inline_tags() {
+        let vtt = "WEBVTT\n\nNOTE a comment\n\n\
+                   intro\n00:01.000 --> 00:02.500 align:start position:10%\n<c.yellow>Hi</c> <00:00:01.500>you\n\n\
+                   00:00:03.000 --> 00:00:04.000\n<v Roger>&lt;laughs&gt;\n";
+        assert_eq!(
+            cue_tuples(&parse_srt_or_vtt(vtt)),
+            vec![(1.0, 2.5, "Hi you"), (3.0, 4.0, "<laughs>")]
+        );
+    }
 
-        let exists: bool = conn
-            .query_row("SELECT 1 FROM projects WHERE id = ?1", [&project_id], |_row| Ok(()))
-            .optional()?
-            .is_some();
-        if !exists {
-            return Ok(None);
+    #[test]
+    fn parse_ass_keeps_commas_in_text_and_drops_override_blocks() {
+        let ass = "[Script Info]\nTitle: test\n\n[V4+ Styles]\nFormat: Name, Fontname\nStyle: Default,Arial\n\n\
+                   [Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Comment: 0,0:00:00.00,0:00:01.00,Default,,0,0,0,,ignored\n\
+                   Dialogue: 0,0:00:01.50,0:00:04.00,Default,,0,0,0,,{\\pos(10,20)\\b1}Well, well,{\\i1} well\\Nnext line\n\
+                   Dialogue: 0,1:00:00.00,1:00:02.25,Default,,0,0,0,,plain\n";
+        assert_eq!(
+            cue_tuples(&parse_ass(ass)),
+            vec![(1.5, 4.0, "Well, well, well\nnext line"), (3600.0, 3602.25, "plain")]
+        );
+    }
+
+    #[test]
+    fn parse_bilibili_danmaku_decodes_entities() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><i><chatid>1</chatid>\
+                   <d p=\"12.345,1,25,16777215,1700000000,0,abcd,1\">前方高能 &amp; &lt;3 &#x1F602;&#33;</d>\
+                   <d p=\"not-a-time,1\">skipped</d>\
+                   <d p=\"3,5,25,16777215,1700000000,0,abcd,2\"> hi &bogus; &amp </d></i>";
+        assert_eq!(
+            cue_tuples(&parse_bilibili_danmaku(xml)),
+            vec![(12.345, 12.345, "前方高能 & <3 😂!"), (3.0, 3.0, "hi &bogus; &amp")]
+        );
+        assert_eq!(decode_xml_entities("&quot;a&apos; &#65;&#x42;"), "\"a' AB");
+    }
+
+    fn pool_item(id: &str, created_at_ms: i64) -> PoolItemResponse {
+        PoolItemResponse {
+            id: id.to_string(),
+            project_id: "p1".to_string(),
+            kind: "meme".to_string(),
+            title: None,
+            source_url: None,
+            license: None,
+            dedup_key: format!("random:{id}"),
+            data_json: None,
+            selected: true,
+            created_at_ms,
+            asset: None,
+            asset_error: None,
+            updated_at_ms: created_at_ms,
+            deleted_at_ms: None,
         }
+    }
 
-        let export_dir_rel = format!("projects/{}/out/export", project_id);
-        let export_dir_abs = data_dir.join(&export_dir_rel);
-        std::fs::create_dir_all(&export_dir_abs)?;
+    #[test]
+    fn phash_distance_counts_differing_bits() {
+        assert_eq!(phash_distance("0000000000000000", "0000000000000000"), Some(0));
+        assert_eq!(phash_distance("0000000000000000", "00000000000000ff"), Some(8));
+        assert_eq!(phash_distance("ffffffffffffffff", "0000000000000000"), Some(64));
+        assert_eq!(phash_distance("zz", "0000000000000000"), None);
+    }
 
-        let report_path = if include_report {
-            conn.query_row(
-                "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'report_html' ORDER BY created_at_ms DESC LIMIT 1",
-                [&project_id],
-                |r| r.get::<_, String>(0),
-            )
-            .optional()?
-        } else {
-            None
+    #[test]
+    fn pool_duplicate_clusters_groups_transitively_in_item_order() {
+        let hashes = [
+            ("a", "0000000000000000"),
+            ("d", "ffffffff00000000"),
+            ("b", "00000000000000ff"),
+            ("e", "ffffffff0000000f"),
+            ("c", "000000000000ffff"),
+            ("f", "not-a-hash"),
+        ];
+        let items = || -> Vec<(PoolItemResponse, String)> {
+            hashes
+                .iter()
+                .enumerate()
+                .map(|(i, (id, h))| (pool_item(id, i as i64), h.to_string()))
+                .collect()
         };
-
-        let manifest_path = if include_manifest {
-            conn.query_row(
-                "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'manifest_json' ORDER BY created_at_ms DESC LIMIT 1",
-                [&project_id],
-                |r| r.get::<_, String>(0),
-            )
-            .optional()?
-        } else {
-            None
+        let summary = |clusters: &[PoolDuplicateCluster]| -> Vec<(Vec<(String, u32)>, u32)> {
+            clusters
+                .iter()
+                .map(|c| (c.items.iter().map(|m| (m.item.id.clone(), m.distance)).collect(), c.max_distance))
+                .collect()
         };
+        let pair = |id: &str, d: u32| (id.to_string(), d);
+
+        // `c` is 16 bits from `a` but joins through `b`, which is exactly at the threshold of both.
+        assert_eq!(
+            summary(&pool_duplicate_clusters(items(), 8)),
+            vec![
+                (vec![pair("a", 0), pair("b", 8), pair("c", 16)], 16),
+                (vec![pair("d", 0), pair("e", 4)], 4),
+            ]
+        );
+        // One below the boundary breaks the chain.
+        assert_eq!(summary(&pool_duplicate_clusters(items(), 7)), vec![(vec![pair("d", 0), pair("e", 4)], 4)]);
+    }
 
-        let input_video_path = if include_original_video {
-            conn.query_row(
-                "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'input_video' ORDER BY created_at_ms DESC LIMIT 1",
-                [&project_id],
-                |r| r.get::<_, String>(0),
-            )
-            .optional()?
-        } else {
-            None
-        };
-
-        let clip_paths: Vec<String> = if include_clips {
-            let mut out: Vec<String> = Vec::new();
-            for kind in ["clip_start", "clip_mid", "clip_end"] {
-                if let Some(p) = conn
-                    .query_row(
-                        "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = ?2 ORDER BY created_at_ms DESC LIMIT 1",
-                        params![&project_id, kind],
-                        |r| r.get::<_, String>(0),
-                    )
-                    .optional()?
-                {
-                    out.push(p);
-                }
-            }
-            out
-        } else {
-            Vec::new()
-        };
+    fn shot(index: usize, start_s: f64, end_s: f64) -> Shot {
+        Shot {
+            index,
+            start_s,
+            end_s,
+            scene_score: None,
+            keyframe_s: start_s,
+            keyframe_path: format!("shot_{index}.jpg"),
+            keyframe_artifact_id: None,
+        }
+    }
 
-        let audio_path: Option<String> = if include_audio {
-            conn.query_row(
-                "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = 'audio_wav' ORDER BY created_at_ms DESC LIMIT 1",
-                [&project_id],
-                |r| r.get::<_, String>(0),
-            )
-            .optional()?
-        } else {
-            None
-        };
-
-        let thumbnail_paths: Vec<String> = if include_thumbnails {
-            let mut out: Vec<String> = Vec::new();
-            for kind in ["thumb_start", "thumb_mid", "thumb_end"] {
-                if let Some(p) = conn
-                    .query_row(
-                        "SELECT path FROM artifacts WHERE project_id = ?1 AND kind = ?2 ORDER BY created_at_ms DESC LIMIT 1",
-                        params![&project_id, kind],
-                        |r| r.get::<_, String>(0),
-                    )
-                    .optional()?
-                {
-                    out.push(p);
-                }
-            }
-            out
-        } else {
-            Vec::new()
-        };
+    #[test]
+    fn clip_sampling_spec_validate_rejects_out_of_range_values() {
+        let ok = ClipSamplingSpec::default();
+        assert!(ok.validate().is_ok());
+        let bad = [
+            ClipSamplingSpec { count: Some(0), ..Default::default() },
+            ClipSamplingSpec { count: Some(MAX_PIPELINE_CLIPS as u32 + 1), ..Default::default() },
+            ClipSamplingSpec { clip_len_s: Some(0.0), ..Default::default() },
+            ClipSamplingSpec { clip_len_s: Some(f64::NAN), ..Default::default() },
+            ClipSamplingSpec { clip_len_s: Some(MAX_CLIP_LEN_S + 1.0), ..Default::default() },
+            ClipSamplingSpec { ranges: Some(vec![[5.0, 5.0]]), ..Default::default() },
+            ClipSamplingSpec { ranges: Some(vec![[-1.0, 5.0]]), ..Default::default() },
+            ClipSamplingSpec { ranges: Some(vec![[0.0, MAX_CLIP_LEN_S + 1.0]]), ..Default::default() },
+            ClipSamplingSpec { ranges: Some(vec![[0.0, 1.0]; MAX_PIPELINE_CLIPS + 1]), ..Default::default() },
+            ClipSamplingSpec {
+                shots: Some(ShotDetectParams { threshold: Some(1.5), min_shot_s: None }),
+                ..Default::default()
+            },
+        ];
+        for spec in bad {
+            assert!(spec.validate().is_err(), "{spec:?}");
+        }
+    }
 
-        // selected_pool.json snapshot
-        let selected_items: Vec<PoolItemResponse> = {
-            let mut stmt = conn.prepare(
-                "SELECT id, project_id, kind, title, source_url, license, dedup_key, data_json, selected, created_at_ms\n                 FROM pool_items WHERE project_id = ?1 AND selected = 1 ORDER BY created_at_ms ASC",
-            )?;
-            let rows = stmt.query_map([&project_id], |row| {
-                Ok(PoolItemResponse {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    kind: row.get(2)?,
-                    title: row.get(3)?,
-                    source_url: row.get(4)?,
-                    license: row.get(5)?,
-                    dedup_key: row.get(6)?,
-                    data_json: row.get(7)?,
-                    selected: row.get::<_, i64>(8)? != 0,
-                    created_at_ms: row.get(9)?,
-                })
-            })?;
-            rows.filter_map(Result::ok).collect()
+    #[test]
+    fn clip_ranges_default_sampling_spreads_clips_evenly() {
+        let spec = ClipSamplingSpec::default();
+        assert_eq!(spec.clip_ranges(60.0, None, &[]).unwrap(), vec![(0.0, 6.0), (27.0, 33.0), (54.0, 60.0)]);
+
+        let one = ClipSamplingSpec { count: Some(1), ..Default::default() };
+        assert_eq!(one.clip_ranges(60.0, None, &[]).unwrap(), vec![(27.0, 33.0)]);
+
+        // An unknown duration puts every clip at 0; the duplicates collapse into one.
+        assert_eq!(spec.clip_ranges(0.0, None, &[]).unwrap(), vec![(0.0, 6.0)]);
+    }
+
+    #[test]
+    fn clip_ranges_explicit_ranges_win_and_clamp_to_the_end() {
+        let spec = ClipSamplingSpec {
+            count: Some(1),
+            ranges: Some(vec![[55.0, 70.0], [10.0, 20.0], [10.0004, 20.0]]),
+            audio_peaks: Some(true),
+            ..Default::default()
         };
-        let selected_pool = serde_json::json!({
-            "version": 1,
-            "project_id": &project_id,
-            "generated_at_ms": now_ms(),
-            "selected_pool_items": selected_items,
-        });
-        let selected_pool_rel = format!("{export_dir_rel}/selected_pool.json");
-        std::fs::write(data_dir.join(&selected_pool_rel), serde_json::to_vec_pretty(&selected_pool)?)?;
+        let shots = [shot(0, 0.0, 30.0)];
+        // Given order is kept; the near-identical third range shares a cache key with the second.
+        assert_eq!(
+            spec.clip_ranges(60.0, Some(&shots), &[5.0]).unwrap(),
+            vec![(55.0, 60.0), (10.0, 20.0)]
+        );
+        // Without a probed duration there is nothing to clamp against.
+        assert_eq!(spec.clip_ranges(0.0, None, &[]).unwrap(), vec![(55.0, 70.0), (10.0, 20.0)]);
 
-        let ts = now_ms();
-        let zip_name = format!("vidunpack-export-{project_id}-{ts}.zip");
-        let zip_rel = format!("{export_dir_rel}/{zip_name}");
-        let zip_abs = data_dir.join(&zip_rel);
+        let past_end = ClipSamplingSpec { ranges: Some(vec![[70.0, 80.0]]), ..Default::default() };
+        assert!(past_end.clip_ranges(60.0, None, &[]).is_err());
+    }
 
-        let file = std::fs::File::create(&zip_abs)?;
-        let mut zip = ZipWriter::new(file);
-        let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Deflated);
+    #[test]
+    fn clip_ranges_shots_pick_evenly_and_stop_at_the_shot_end() {
+        let shots = [shot(0, 0.0, 10.0), shot(1, 10.0, 12.0), shot(2, 12.0, 30.0), shot(3, 30.0, 31.0), shot(4, 31.0, 60.0)];
+        let two = ClipSamplingSpec { count: Some(2), audio_peaks: Some(true), ..Default::default() };
+        assert_eq!(two.clip_ranges(60.0, Some(&shots), &[40.0]).unwrap(), vec![(0.0, 6.0), (12.0, 18.0)]);
 
-        let mut total_bytes: u64 = 0;
+        let all = ClipSamplingSpec::default();
+        assert_eq!(
+            all.clip_ranges(60.0, Some(&shots), &[]).unwrap(),
+            vec![(0.0, 6.0), (10.0, 12.0), (12.0, 18.0), (30.0, 31.0), (31.0, 37.0)]
+        );
+    }
 
-        let add_file = |zip: &mut ZipWriter<std::fs::File>, abs: &FsPath, name: &str| -> anyhow::Result<u64> {
-            let size = std::fs::metadata(abs)?.len();
-            zip.start_file(name, options)?;
-            let mut f = std::fs::File::open(abs)?;
-            std::io::copy(&mut f, zip)?;
-            Ok(size)
-        };
+    #[test]
+    fn clip_ranges_peaks_center_clips_within_the_video() {
+        let spec = ClipSamplingSpec { count: Some(2), audio_peaks: Some(true), ..Default::default() };
+        // Strongest first; only the top `count` are used, then cut in time order.
+        assert_eq!(spec.clip_ranges(52.0, None, &[50.0, 2.0, 30.0]).unwrap(), vec![(0.0, 6.0), (46.0, 52.0)]);
 
-        // report / manifest
-        if let Some(p) = report_path {
-            let abs = data_dir.join(&p);
-            if abs.exists() {
-                total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, "report.html")?);
-            }
-        }
-        if let Some(p) = manifest_path {
-            let abs = data_dir.join(&p);
-            if abs.exists() {
-                total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, "manifest.json")?);
-            }
-        }
+        let danmaku = ClipSamplingSpec { count: Some(1), danmaku_peaks: Some(true), ..Default::default() };
+        assert_eq!(danmaku.clip_ranges(60.0, None, &[20.0]).unwrap(), vec![(17.0, 23.0)]);
 
-        // selected_pool snapshot
-        {
-            let abs = data_dir.join(&selected_pool_rel);
-            total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, "selected_pool.json")?);
-        }
+        // Peaks are ignored unless asked for, and an empty list falls back to even sampling.
+        let plain = ClipSamplingSpec { count: Some(1), ..Default::default() };
+        assert_eq!(plain.clip_ranges(60.0, None, &[20.0]).unwrap(), vec![(27.0, 33.0)]);
+        let no_peaks = ClipSamplingSpec { count: Some(1), audio_peaks: Some(true), ..Default::default() };
+        assert_eq!(no_peaks.clip_ranges(60.0, None, &[]).unwrap(), vec![(27.0, 33.0)]);
+    }
 
-        // original video
-        if let Some(p) = input_video_path {
-            let abs = data_dir.join(&p);
-            if abs.exists() {
-                let file_name = FsPath::new(&p)
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("input_video");
-                total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("input_video/{file_name}"))?);
-            }
-        }
+    fn probe(json: &str) -> MediaProbe {
+        MediaProbe::parse(json.as_bytes()).expect("ffprobe fixture")
+    }
 
-        // clips / audio / thumbnails (if present)
-        if !clip_paths.is_empty() {
-            for p in clip_paths {
-                let abs = data_dir.join(&p);
-                if !abs.exists() {
-                    continue;
-                }
-                let file_name = FsPath::new(&p)
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("clip.mp4");
-                total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("clips/{file_name}"))?);
-            }
-        }
+    fn warning_codes(p: &MediaProbe) -> Vec<(&'static str, &'static str)> {
+        p.warnings().iter().map(|w| (w.severity, w.code)).collect()
+    }
 
-        if let Some(p) = audio_path {
-            let abs = data_dir.join(&p);
-            if abs.exists() {
-                let file_name = FsPath::new(&p)
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("audio.wav");
-                total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("audio/{file_name}"))?);
-            }
-        }
+    #[test]
+    fn media_probe_reads_rotation_from_side_data_before_tags() {
+        let p = probe(
+            r#"{"format":{"format_name":"mov,mp4,m4a,3gp,3g2,mj2","duration":"12.5"},"streams":[
+                {"index":0,"codec_type":"video","codec_name":"h264","width":1920,"height":1080,"pix_fmt":"yuv420p",
+                 "avg_frame_rate":"30/1","r_frame_rate":"30/1","tags":{"rotate":"180"},
+                 "side_data_list":[{"side_data_type":"Display Matrix","rotation":-90}]},
+                {"index":1,"codec_type":"audio","codec_name":"aac","sample_rate":"48000","channels":2}]}"#,
+        );
+        assert_eq!(p.video().and_then(|v| v.rotation), Some(270));
+        assert_eq!(p.proxy_reasons("mp4"), vec!["rotated 270°".to_string()]);
+        assert_eq!(warning_codes(&p), vec![("warning", "rotated")]);
+
+        let p = probe(
+            r#"{"format":{"duration":"3"},"streams":[
+                {"index":0,"codec_type":"video","codec_name":"h264","pix_fmt":"yuv420p","tags":{"rotate":"90"}},
+                {"index":1,"codec_type":"audio","codec_name":"aac"}]}"#,
+        );
+        assert_eq!(p.video().and_then(|v| v.rotation), Some(90));
+    }
 
-        if !thumbnail_paths.is_empty() {
-            for p in thumbnail_paths {
-                let abs = data_dir.join(&p);
-                if !abs.exists() {
-                    continue;
-                }
-                let file_name = FsPath::new(&p)
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("thumb.jpg");
-                total_bytes = total_bytes.saturating_add(add_file(&mut zip, &abs, &format!("thumbnails/{file_name}"))?);
-            }
-        }
+    #[test]
+    fn media_probe_flags_variable_frame_rate() {
+        let p = probe(
+            r#"{"format":{"duration":"8"},"streams":[
+                {"index":0,"codec_type":"video","codec_name":"h264","pix_fmt":"yuv420p","avg_frame_rate":"24000/1001","r_frame_rate":"30/1"},
+                {"index":1,"codec_type":"audio","codec_name":"aac"}]}"#,
+        );
+        let v = p.video().expect("video stream");
+        assert!(v.variable_frame_rate);
+        assert!((v.fps.unwrap_or(0.0) - 23.976).abs() < 0.001);
+        assert_eq!(p.proxy_reasons("mp4"), vec!["variable frame rate".to_string()]);
+
+        let p = probe(
+            r#"{"format":{"duration":"8"},"streams":[
+                {"index":0,"codec_type":"video","codec_name":"h264","pix_fmt":"yuv420p","avg_frame_rate":"30000/1001","r_frame_rate":"30000/1001"},
+                {"index":1,"codec_type":"audio","codec_name":"aac"}]}"#,
+        );
+        assert!(!p.video().expect("video stream").variable_frame_rate);
+        assert!(p.proxy_reasons("mp4").is_empty());
+    }
 
-        zip.finish()?;
+    #[test]
+    fn media_probe_skips_cover_art_as_video() {
+        let audio_only = probe(
+            r#"{"format":{"duration":"200"},"streams":[
+                {"index":0,"codec_type":"audio","codec_name":"mp3"},
+                {"index":1,"codec_type":"video","codec_name":"mjpeg","width":600,"height":600,"disposition":{"attached_pic":1}}]}"#,
+        );
+        assert!(audio_only.video().is_none());
+        assert!(audio_only.proxy_reasons("mp3").is_empty());
+        assert_eq!(warning_codes(&audio_only), vec![("error", "no_video")]);
+
+        let with_video = probe(
+            r#"{"format":{"duration":"20"},"streams":[
+                {"index":0,"codec_type":"video","codec_name":"png","disposition":{"attached_pic":1}},
+                {"index":1,"codec_type":"video","codec_name":"hevc","pix_fmt":"yuv420p10le","disposition":{"attached_pic":0}},
+                {"index":2,"codec_type":"audio","codec_name":"ac3"}]}"#,
+        );
+        assert_eq!(with_video.video().map(|v| v.index), Some(1));
+        assert_eq!(
+            with_video.proxy_reasons("mkv"),
+            vec!["video codec hevc".to_string(), "pixel format yuv420p10le".to_string(), "audio codec ac3".to_string()]
+        );
+    }
 
-        let zip_art = ensure_artifact(&conn, &project_id, "export_zip", &zip_rel, ts)?;
+    #[test]
+    fn media_probe_without_audio_is_a_blocking_error() {
+        let p = probe(
+            r#"{"format":{},"streams":[
+                {"index":0,"codec_type":"video","codec_name":"vp9","pix_fmt":"yuv420p"}]}"#,
+        );
+        assert_eq!(p.duration_s, None);
+        assert!(p.proxy_reasons("webm").is_empty());
+        assert_eq!(warning_codes(&p), vec![("error", "no_audio"), ("warning", "unknown_duration")]);
+    }
 
-        conn.execute(
-            "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'info', 'export_zip', ?3)",
-            params![
-                &project_id,
-                ts,
-                serde_json::json!({ "zip": &zip_rel, "bytes": total_bytes }).to_string()
-            ],
-        )?;
+    #[test]
+    fn pool_item_filter_matches_every_given_criterion() {
+        let mut item = pool_item("a", 1_000);
+        item.kind = "Meme".to_string();
+        item.source_url = Some("https://i.Example.com/x.png".to_string());
+        item.license = Some("CC-BY".to_string());
+        let filter = |json: serde_json::Value| serde_json::from_value::<PoolItemFilter>(json).expect("filter");
+
+        assert!(filter(serde_json::json!({})).matches(&item));
+        assert!(filter(serde_json::json!({ "kind": " meme " })).matches(&item));
+        assert!(!filter(serde_json::json!({ "kind": "sfx" })).matches(&item));
+        assert!(filter(serde_json::json!({ "domain": "example.com" })).matches(&item));
+        assert!(filter(serde_json::json!({ "domain": ".i.example.com" })).matches(&item));
+        assert!(!filter(serde_json::json!({ "domain": "ample.com" })).matches(&item));
+        assert!(filter(serde_json::json!({ "has_license": true })).matches(&item));
+        assert!(!filter(serde_json::json!({ "has_license": false })).matches(&item));
+        assert!(filter(serde_json::json!({ "created_after_ms": 999 })).matches(&item));
+        assert!(!filter(serde_json::json!({ "created_after_ms": 1_000 })).matches(&item));
+        assert!(!filter(serde_json::json!({ "kind": "meme", "has_license": false })).matches(&item));
+
+        item.source_url = None;
+        assert!(!filter(serde_json::json!({ "domain": "example.com" })).matches(&item));
+    }
 
-        if let Err(err) = update_profile_after_export(
-            &conn,
-            &data_dir,
-            &project_id,
-            ts,
-            include_original_video,
-            include_report,
-            include_manifest,
-            include_clips,
-            include_audio,
-            include_thumbnails,
-        )
+    #[test]
+    fn apply_pool_bulk_changes_rows_in_one_transaction_with_one_event() {
+        let data_dir = temp_dir("bulk");
+        let db_path = data_dir.join("vidunpack.sqlite3");
+        init_db(&db_path).expect("init db");
+        let conn = Connection::open(&db_path).expect("open db");
+        conn.execute("INSERT INTO projects (id, title, created_at_ms) VALUES ('p1', 'test', 1)", [])
+            .expect("insert project");
+        for (created_at_ms, (id, selected, deleted)) in [("a", 1, None), ("b", 0, None), ("c", 1, None), ("d", 1, Some(5_i64))]
+            .into_iter()
+            .enumerate()
         {
-            tracing::warn!("failed to update profile after export: {err:#}");
-            let _ = conn.execute(
-                "INSERT INTO events (project_id, ts_ms, level, message, data_json) VALUES (?1, ?2, 'warn', 'profile_update_failed', ?3)",
-                params![&project_id, ts, serde_json::json!({ "error": err.to_string() }).to_string()],
-            );
+            conn.execute(
+                "INSERT INTO pool_items (id, project_id, kind, dedup_key, selected, created_at_ms, deleted_at_ms) VALUES (?1, 'p1', 'meme', ?1, ?2, ?3, ?4)",
+                params![id, selected, created_at_ms as i64, deleted],
+            )
+            .expect("insert pool item");
         }
+        let events = || -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM events WHERE message = 'pool_items_bulk'", [], |row| row.get(0))
+                .expect("count events")
+        };
+        let selected = |id: &str| read_pool_item(&conn, "p1", id).expect("read").expect("item").selected;
+        let ids = |v: &[&str]| PoolBulkTarget::Ids(v.iter().map(|s| s.to_string()).collect());
+
+        let resp = apply_pool_bulk(&conn, "p1", PoolBulkAction::Deselect, &ids(&["a", "b", "d"]))
+            .expect("db")
+            .expect("bulk");
+        assert_eq!((resp.matched, resp.affected_ids, resp.deleted_ids), (2, vec!["a".to_string()], vec!["d".to_string()]));
+        assert!(!selected("a"));
+        assert_eq!(events(), 1);
+
+        // Nothing left to change: no second event.
+        let resp = apply_pool_bulk(&conn, "p1", PoolBulkAction::Deselect, &ids(&["a", "b"]))
+            .expect("db")
+            .expect("bulk");
+        assert!(resp.affected_ids.is_empty());
+        assert_eq!(events(), 1);
+
+        // An unknown id rejects the whole request before anything is written.
+        let err = apply_pool_bulk(&conn, "p1", PoolBulkAction::Invert, &ids(&["a", "zzz"])).expect("db");
+        assert!(matches!(err, Err(AppError::BadRequest(_))));
+        assert!(!selected("a"));
+        assert_eq!(events(), 1);
+
+        let resp = apply_pool_bulk(&conn, "p1", PoolBulkAction::Invert, &PoolBulkTarget::Filter(PoolItemFilter::default()))
+            .expect("db")
+            .expect("bulk");
+        assert_eq!(resp.affected_ids, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert!(selected("a") && selected("b") && !selected("c") && selected("d"));
+        assert_eq!(events(), 2);
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
 
-        let download_url = format!("/projects/{}/exports/download/{}", project_id, zip_name);
-        Ok(Some(ExportZipResponse {
-            zip: zip_art,
-            total_bytes,
-            download_url,
-        }))
-    })
-    .await
-    .context("export_zip task failed")??;
+    #[test]
+    fn parse_flat_playlist_keeps_positions_and_drops_unusable_urls() {
+        let info = serde_json::json!({
+            "_type": "playlist",
+            "extractor": "BiliBiliSeries",
+            "id": "series-7",
+            "title": "Cut compilation",
+            "webpage_url": "https://space.bilibili.com/1/channel/seriesdetail?sid=7",
+            "entries": [
+                { "id": "BV1a", "title": "  first\n part ", "url": "https://www.bilibili.com/video/BV1a", "duration": 61.5 },
+                { "title": "no url or id" },
+                { "id": "BV1c", "title": "", "webpage_url": "https://www.bilibili.com/video/BV1c" },
+                { "id": "BV1d", "url": "BV1d" }
+            ]
+        });
+        let (summary, entries) = parse_flat_playlist(&info, "https://example.com/requested");
+        assert_eq!(summary.extractor, "BiliBiliSeries");
+        assert_eq!(summary.id, "series-7");
+        assert_eq!(summary.webpage_url, "https://space.bilibili.com/1/channel/seriesdetail?sid=7");
 
-    match res {
-        Some(r) => Ok(Json(r)),
-        None => Err(AppError::NotFound("project not found".to_string())),
+        let rows: Vec<_> = entries
+            .iter()
+            .map(|e| (e.index, e.id.as_deref(), e.title.as_deref(), e.url.as_deref(), e.duration_s))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, Some("BV1a"), Some("first part"), Some("https://www.bilibili.com/video/BV1a"), Some(61.5)),
+                (2, None, Some("no url or id"), None, None),
+                (3, Some("BV1c"), None, Some("https://www.bilibili.com/video/BV1c"), None),
+                (4, Some("BV1d"), None, None, None),
+            ]
+        );
+
+        assert_eq!(playlist_entry_url(&entries, 1).as_deref(), Ok("https://www.bilibili.com/video/BV1a"));
+        assert_eq!(playlist_entry_url(&entries, 2), Err("entry has no downloadable url".to_string()));
+        assert_eq!(playlist_entry_url(&entries, 4), Err("entry has no downloadable url".to_string()));
+        assert_eq!(playlist_entry_url(&entries, 5), Err("playlist has no entry 5 (4 entries)".to_string()));
     }
-}
 
-async fn download_export_file(
-    State(state): State<AppState>,
-    Path((project_id, file)): Path<(String, String)>,
-) -> AppResult<Response> {
-    if project_id.trim().is_empty() {
-        return Err(AppError::BadRequest("missing project id".to_string()));
+    #[test]
+    fn parse_flat_playlist_treats_a_single_video_as_one_entry() {
+        let info = serde_json::json!({ "extractor_key": "Youtube", "id": "abc", "duration": 12.0 });
+        let (summary, entries) = parse_flat_playlist(&info, "https://youtu.be/abc");
+        assert_eq!(summary.extractor, "Youtube");
+        assert_eq!(summary.title, "untitled");
+        assert_eq!(summary.webpage_url, "https://youtu.be/abc");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].index, 1);
+        assert_eq!(entries[0].duration_s, Some(12.0));
+        assert_eq!(playlist_entry_url(&entries, 1).as_deref(), Ok("https://youtu.be/abc"));
+        assert!(playlist_entry_url(&entries, 2).is_err());
+
+        let (_, none) = parse_flat_playlist(&serde_json::json!({ "entries": [] }), "https://youtu.be/abc");
+        assert!(none.is_empty());
+        assert_eq!(playlist_entry_url(&none, 1), Err("playlist has no entry 1 (0 entries)".to_string()));
     }
 
-    let safe_name = sanitize_file_name(&file);
-    if safe_name.is_empty() {
-        return Err(AppError::BadRequest("invalid file".to_string()));
+    #[test]
+    fn playlist_entry_indexes_sort_dedup_and_bound() {
+        assert_eq!(playlist_entry_indexes(&[3, 1, 3, 2, 1]), Ok(vec![1, 2, 3]));
+        assert_eq!(playlist_entry_indexes(&[0, 2]), Err("entry indexes start at 1".to_string()));
+        assert!(playlist_entry_indexes(&[]).is_err());
+
+        let max: Vec<usize> = (1..=MAX_PLAYLIST_ENTRIES).collect();
+        assert_eq!(playlist_entry_indexes(&max).map(|v| v.len()), Ok(MAX_PLAYLIST_ENTRIES));
+        let over: Vec<usize> = (1..=MAX_PLAYLIST_ENTRIES + 1).collect();
+        assert!(playlist_entry_indexes(&over).is_err());
+        // Duplicates count once, so a padded request at the cap still passes.
+        let padded: Vec<usize> = max.iter().chain(max.iter()).copied().collect();
+        assert!(playlist_entry_indexes(&padded).is_ok());
     }
 
-    let rel = format!("projects/{}/out/export/{}", project_id, safe_name);
-    let abs = state.data_dir.join(&rel);
-    if !abs.exists() {
-        return Err(AppError::NotFound("file not found".to_string()));
+    #[test]
+    fn export_slug_lowercases_collapses_and_keeps_cjk() {
+        assert_eq!(export_slug("Drake  Hotline -- Bling!", 60), "drake-hotline-bling");
+        assert_eq!(export_slug("猫咪 表情包！", 60), "猫咪-表情包");
+        assert_eq!(export_slug("ÉTÉ 2024", 60), "été-2024");
+        assert_eq!(export_slug("  --!!", 60), "");
+        assert_eq!(export_slug("abcdef", 3), "abc");
+        // A cut that lands on a separator does not leave a trailing dash.
+        assert_eq!(export_slug("ab cd", 3), "ab");
+        assert_eq!(export_slug("猫咪表情包", 2), "猫咪");
     }
 
-    let file = tokio::fs::File::open(&abs)
-        .await
-        .with_context(|| format!("failed to open {}", abs.display()))?;
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+    #[test]
+    fn export_pool_asset_entries_names_files_and_applies_the_missing_policy() {
+        let data_dir = temp_dir("export-assets");
+        let with_file = |id: &str, kind: &str, title: Option<&str>, file: &str| {
+            let rel = format!("pool/{file}");
+            std::fs::create_dir_all(data_dir.join("pool")).expect("create pool dir");
+            std::fs::write(data_dir.join(&rel), b"x").expect("write asset");
+            let mut item = pool_item(id, 1);
+            item.kind = kind.to_string();
+            item.title = title.map(str::to_string);
+            item.asset = Some(PoolItemAsset {
+                artifact_id: format!("art-{id}"),
+                path: rel,
+                mime: "image/png".to_string(),
+                bytes: 1,
+                sha256: String::new(),
+                phash: None,
+            });
+            item
+        };
+        let gone = with_file("gone", "meme", Some("Gone"), "gone.png");
+        std::fs::remove_file(data_dir.join("pool/gone.png")).expect("remove asset");
+        let mut never = pool_item("never", 1);
+        never.title = Some("Never fetched".to_string());
+
+        let items = vec![
+            with_file("a", "meme", Some("Drake Hotline"), "a.png"),
+            with_file("b", "meme", Some("drake hotline!"), "b.jpg"),
+            gone,
+            with_file("c", "meme", Some("Drake-Hotline"), "c.gif"),
+            with_file("d", "meme", Some("猫咪 表情包"), "d.webp"),
+            with_file("0123456789ab", "Sound Effect", Some("!!!"), "e.mp3"),
+            with_file("f", "", None, "f"),
+            never,
+        ];
 
-    let mut res = Response::new(body);
-    res.headers_mut()
-        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/zip"));
-    let disp = format!("attachment; filename=\"{}\"", safe_name);
-    res.headers_mut()
-        .insert(header::CONTENT_DISPOSITION, HeaderValue::from_str(&disp).unwrap_or_else(|_| HeaderValue::from_static("attachment")));
-    Ok(res)
+        let skip = export_pool_asset_entries(&data_dir, &items, MissingPoolAssetPolicy::Skip).expect("skip policy");
+        let names: Vec<String> = skip.files.iter().map(|(item, stem)| pool_asset_entry_name(item, stem)).collect();
+        assert_eq!(
+            names,
+            vec![
+                "assets/meme/drake-hotline.png",
+                "assets/meme/drake-hotline-2.jpg",
+                "assets/meme/drake-hotline-3.gif",
+                "assets/meme/猫咪-表情包.webp",
+                "assets/sound-effect/01234567.mp3",
+                "assets/other/f.bin",
+            ]
+        );
+        assert_eq!(skip.skipped, vec!["gone".to_string(), "never".to_string()]);
+        assert!(skip.links.is_empty());
+
+        let links = export_pool_asset_entries(&data_dir, &items, MissingPoolAssetPolicy::Links).expect("links policy");
+        assert_eq!(links.files.len(), 6);
+        assert!(links.skipped.is_empty());
+        let linked: Vec<&str> = links.links.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(linked, vec!["gone", "never"]);
+
+        let err = match export_pool_asset_entries(&data_dir, &items, MissingPoolAssetPolicy::Fail) {
+            Ok(_) => panic!("fail policy exported a missing asset"),
+            Err(err) => err,
+        };
+        assert!(err.starts_with("pool item gone (Gone) has no local asset"), "{err}");
+        let present: Vec<PoolItemResponse> = items.iter().filter(|i| i.id != "gone" && i.id != "never").cloned().collect();
+        assert_eq!(
+            export_pool_asset_entries(&data_dir, &present, MissingPoolAssetPolicy::Fail).map(|e| e.files.len()),
+            Ok(6)
+        );
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
 }